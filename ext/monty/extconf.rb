@@ -3,4 +3,7 @@
 require "mkmf"
 require "rb_sys/mkmf"
 
-create_rust_makefile("monty/monty")
+create_rust_makefile("monty/monty") do |r|
+  # Optional Cargo features, e.g. MONTY_FEATURES=arrow gem install monty-rb
+  r.features = ENV.fetch("MONTY_FEATURES", "").split(",").map(&:strip).reject(&:empty?)
+end
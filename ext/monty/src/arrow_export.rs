@@ -0,0 +1,136 @@
+use arrow_array::{ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema};
+use magnus::Error;
+use monty_lang::MontyObject;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::errors::monty_error;
+
+/// Encode a list-of-dicts result as an Arrow IPC stream.
+///
+/// Column types are inferred from the values: bool, int, float (ints are
+/// widened when a column mixes both) or str. None and missing keys become
+/// nulls. Columns mixing other types are rejected.
+pub fn rows_to_arrow_ipc(obj: MontyObject) -> Result<Vec<u8>, Error> {
+    let MontyObject::List(rows) = obj else {
+        return Err(monty_error("to_arrow requires a list of dicts".to_string()));
+    };
+
+    let row_count = rows.len();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut names: Vec<String> = Vec::new();
+    let mut cells: Vec<Vec<Option<MontyObject>>> = Vec::new();
+
+    for (row_number, row) in rows.into_iter().enumerate() {
+        let MontyObject::Dict(pairs) = row else {
+            return Err(monty_error(format!(
+                "to_arrow requires a list of dicts (row {row_number} is not a dict)"
+            )));
+        };
+        for (key, value) in pairs {
+            let MontyObject::String(name) = key else {
+                return Err(monty_error(format!(
+                    "to_arrow requires string keys (row {row_number})"
+                )));
+            };
+            let column = *index.entry(name.clone()).or_insert_with(|| {
+                names.push(name);
+                cells.push(vec![None; row_count]);
+                cells.len() - 1
+            });
+            cells[column][row_number] = Some(value);
+        }
+    }
+
+    let mut fields = Vec::with_capacity(names.len());
+    let mut arrays: Vec<ArrayRef> = Vec::with_capacity(names.len());
+    for (name, column) in names.into_iter().zip(cells) {
+        let array = column_array(&name, column)?;
+        fields.push(Field::new(name, array.data_type().clone(), true));
+        arrays.push(array);
+    }
+
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), arrays).map_err(arrow_error)?;
+
+    let mut buffer = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut buffer, &schema).map_err(arrow_error)?;
+    writer.write(&batch).map_err(arrow_error)?;
+    writer.finish().map_err(arrow_error)?;
+    drop(writer);
+    Ok(buffer)
+}
+
+fn column_array(name: &str, column: Vec<Option<MontyObject>>) -> Result<ArrayRef, Error> {
+    let mut data_type: Option<DataType> = None;
+    for value in column.iter().flatten() {
+        let value_type = match value {
+            MontyObject::None => continue,
+            MontyObject::Bool(_) => DataType::Boolean,
+            MontyObject::Int(_) => DataType::Int64,
+            MontyObject::Float(_) => DataType::Float64,
+            MontyObject::String(_) => DataType::Utf8,
+            _ => return Err(unsupported(name)),
+        };
+        data_type = Some(match (data_type, value_type) {
+            (None, t) => t,
+            (Some(a), b) if a == b => a,
+            (Some(DataType::Int64), DataType::Float64)
+            | (Some(DataType::Float64), DataType::Int64) => DataType::Float64,
+            _ => return Err(unsupported(name)),
+        });
+    }
+
+    let array: ArrayRef = match data_type.unwrap_or(DataType::Utf8) {
+        DataType::Boolean => Arc::new(BooleanArray::from(
+            column
+                .into_iter()
+                .map(|v| match v {
+                    Some(MontyObject::Bool(b)) => Some(b),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        DataType::Int64 => Arc::new(Int64Array::from(
+            column
+                .into_iter()
+                .map(|v| match v {
+                    Some(MontyObject::Int(i)) => Some(i),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        DataType::Float64 => Arc::new(Float64Array::from(
+            column
+                .into_iter()
+                .map(|v| match v {
+                    Some(MontyObject::Float(f)) => Some(f),
+                    Some(MontyObject::Int(i)) => Some(i as f64),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        _ => Arc::new(StringArray::from(
+            column
+                .into_iter()
+                .map(|v| match v {
+                    Some(MontyObject::String(s)) => Some(s),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+    };
+    Ok(array)
+}
+
+fn unsupported(name: &str) -> Error {
+    monty_error(format!(
+        "to_arrow cannot infer a single Arrow type for column '{name}'"
+    ))
+}
+
+fn arrow_error(e: arrow_schema::ArrowError) -> Error {
+    monty_error(format!("arrow encoding error: {e}"))
+}
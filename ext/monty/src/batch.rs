@@ -0,0 +1,147 @@
+use magnus::Error;
+use monty_lang::{
+    LimitedTracker, MontyException, MontyObject, MontyRun, NoLimitTracker, ResourceLimits, StdPrint,
+};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::control::{check_not_stopped, current_epoch, ActiveRun};
+use crate::gvl::without_gvl;
+use crate::mailbox::Mailbox;
+use crate::stop_tracker::{StopSignal, StopTracker};
+
+/// One input set of a batch with the limits it runs under.
+pub struct BatchJob {
+    pub inputs: Vec<MontyObject>,
+    pub limits: Option<ResourceLimits>,
+    /// The job's own `max_duration`, kept so it can be combined with the
+    /// time left before the batch deadline.
+    pub max_duration: Option<Duration>,
+}
+
+impl BatchJob {
+    /// Limits for a job starting now, or None once the deadline has passed.
+    fn limits_at(&self, deadline: Option<Instant>) -> Option<Option<ResourceLimits>> {
+        let Some(deadline) = deadline else {
+            return Some(self.limits.clone());
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return None;
+        }
+        let max_duration = self.max_duration.map_or(remaining, |d| d.min(remaining));
+        let limits = self.limits.clone().unwrap_or_else(ResourceLimits::new);
+        Some(Some(limits.max_duration(max_duration)))
+    }
+}
+
+/// One finished execution of a batch.
+pub struct Completed {
+    pub index: usize,
+    pub result: Result<MontyObject, MontyException>,
+    pub duration: Duration,
+}
+
+/// Run every job on `concurrency` scoped threads, each pulling the next
+/// unclaimed job until none are left, the deadline has passed or the
+/// batch is cancelled through `active` or `Monty.abort_all!`.
+///
+/// The calling thread releases the GVL while it waits and hands each
+/// execution to `on_complete` with the GVL held, in completion order. If
+/// `on_complete` fails or the thread is interrupted, no further jobs are
+/// started, executions already running are stopped at their next time
+/// check and the error is returned. `Monty.abort_all!` and
+/// `Monty.cancel_run` stop them the same way and return
+/// Monty::Interrupted. Jobs never started or
+/// stopped part way are not reported.
+pub fn run_batch(
+    run: &MontyRun,
+    active: &ActiveRun,
+    jobs: Vec<BatchJob>,
+    concurrency: usize,
+    deadline: Option<Instant>,
+    mut on_complete: impl FnMut(Completed) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let jobs: Vec<Mutex<Option<BatchJob>>> =
+        jobs.into_iter().map(|job| Mutex::new(Some(job))).collect();
+    let epoch = current_epoch();
+    let signal = StopSignal::new(epoch, active);
+    let next = AtomicUsize::new(0);
+    let cancelled = AtomicBool::new(false);
+    let mailbox = Mailbox::new();
+
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..concurrency.max(1).min(jobs.len()))
+            .map(|_| {
+                let sender = mailbox.sender();
+                let (jobs, next, cancelled, signal) = (&jobs, &next, &cancelled, &signal);
+                scope.spawn(move || {
+                    while !cancelled.load(Ordering::Relaxed) && !signal.is_stopped() {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(slot) = jobs.get(index) else {
+                            break;
+                        };
+                        let Some(job) = slot.lock().unwrap().take() else {
+                            continue;
+                        };
+                        let Some(limits) = job.limits_at(deadline) else {
+                            cancelled.store(true, Ordering::Relaxed);
+                            break;
+                        };
+                        let started = Instant::now();
+                        let result = match limits {
+                            Some(limits) => run.run(
+                                job.inputs,
+                                StopTracker::new(LimitedTracker::new(limits), signal),
+                                &mut StdPrint,
+                            ),
+                            None => run.run(
+                                job.inputs,
+                                StopTracker::new(NoLimitTracker, signal),
+                                &mut StdPrint,
+                            ),
+                        };
+                        if signal.is_stopped() {
+                            break;
+                        }
+                        let completed = Completed {
+                            index,
+                            result,
+                            duration: started.elapsed(),
+                        };
+                        if sender.send(completed).is_err() {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let outcome = mailbox.deliver(&mut on_complete);
+        if outcome.is_err() {
+            cancelled.store(true, Ordering::Relaxed);
+            signal.interrupt();
+        }
+
+        // An interrupt stops the workers and is returned once they have
+        // finished. If one is already pending, the closure never runs and
+        // the scope joins the workers on exit.
+        let joined = without_gvl(
+            || {
+                for worker in workers {
+                    if let Err(payload) = worker.join() {
+                        std::panic::resume_unwind(payload);
+                    }
+                }
+            },
+            || {
+                cancelled.store(true, Ordering::Relaxed);
+                signal.interrupt();
+            },
+        );
+        outcome
+            .and(joined)
+            .and_then(|()| check_not_stopped(epoch, active))
+    })
+}
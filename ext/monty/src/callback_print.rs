@@ -0,0 +1,101 @@
+use magnus::value::ReprValue;
+use magnus::{Error, Value};
+use monty_lang::{ExcType, MontyException, PrintWriter};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::control::{check_not_stopped, ActiveRun};
+use crate::gvl::with_gvl;
+
+/// Print writer handing each line the script prints to a Ruby callable as it
+/// is written, rather than buffering it until the run finishes.
+///
+/// The interpreter runs with the GVL released; each line is delivered with
+/// it re-acquired, after checking that the run has not been aborted or
+/// cancelled.
+pub struct CallbackPrint {
+    callback: Value,
+    line: String,
+    abort_epoch: u64,
+    run: Arc<ActiveRun>,
+    /// First exception raised by the callback, or Monty::Interrupted;
+    /// re-raised by `finish`.
+    error: Option<Error>,
+}
+
+impl CallbackPrint {
+    pub fn new(callback: Value, abort_epoch: u64, run: Arc<ActiveRun>) -> Self {
+        Self {
+            callback,
+            line: String::new(),
+            abort_epoch,
+            run,
+            error: None,
+        }
+    }
+
+    /// Pass the buffered line to the callback. Once it has raised, or the
+    /// run has been stopped, output is dropped. Needs the GVL.
+    fn deliver(&mut self) {
+        if self.error.is_some() || self.line.is_empty() {
+            return;
+        }
+        let line = std::mem::take(&mut self.line);
+        let delivered = check_not_stopped(self.abort_epoch, &self.run)
+            .and_then(|()| self.callback.funcall::<_, _, Value>("call", (line,)));
+        if let Err(e) = delivered {
+            self.error = Some(e);
+        }
+    }
+
+    /// `deliver` from the interpreter, which runs without the GVL. After a
+    /// failed delivery the script sees a RuntimeError on every print.
+    fn flush(&mut self) -> Result<(), MontyException> {
+        if self.error.is_none() && !self.line.is_empty() {
+            with_gvl(|| self.deliver());
+        }
+        match self.error {
+            Some(_) => Err(MontyException::new(
+                ExcType::RuntimeError,
+                Some("output callback raised".to_string()),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Deliver any trailing partial line, then return the callback's
+    /// exception in preference to `result`. Called with the GVL held.
+    pub fn finish<T>(mut self, result: Result<T, Error>) -> Result<T, Error> {
+        self.deliver();
+        match self.error {
+            Some(e) => Err(e),
+            None => result,
+        }
+    }
+}
+
+impl PrintWriter for CallbackPrint {
+    fn stdout_write(&mut self, output: Cow<'_, str>) -> Result<(), MontyException> {
+        if self.error.is_some() {
+            return self.flush();
+        }
+        for chunk in output.split_inclusive('\n') {
+            self.line.push_str(chunk);
+            if chunk.ends_with('\n') {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn stdout_push(&mut self, end: char) -> Result<(), MontyException> {
+        if self.error.is_some() {
+            return self.flush();
+        }
+        self.line.push(end);
+        if end == '\n' {
+            self.flush()?;
+        }
+        Ok(())
+    }
+}
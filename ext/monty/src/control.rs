@@ -0,0 +1,219 @@
+use magnus::{function, Error, Object, RArray, Ruby, Value};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Instant;
+
+use crate::errors::interrupted_error;
+
+static DRAINING: AtomicBool = AtomicBool::new(false);
+static ABORT_EPOCH: AtomicU64 = AtomicU64::new(0);
+static NEXT_RUN_ID: AtomicU64 = AtomicU64::new(1);
+static ACTIVE_RUNS: Mutex<Vec<Weak<ActiveRun>>> = Mutex::new(Vec::new());
+
+/// Fiber-local key under which `Monty.within_run` keeps the parent run
+/// id; matches `PARENT_RUN_KEY` in lib/monty/config.rb.
+const PARENT_RUN_KEY: &str = "__monty_parent_run__";
+
+/// An execution listed by `Monty.active_runs`.
+///
+/// Whoever drives the execution holds the Arc; the registry only keeps a
+/// Weak reference, so a run disappears from the list once it completes or
+/// its paused progress object is garbage collected.
+pub struct ActiveRun {
+    id: u64,
+    parent: Option<u64>,
+    script_name: Option<String>,
+    started: Instant,
+    running: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+impl ActiveRun {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Record whether the interpreter is executing (true) or paused
+    /// waiting for the host (false).
+    pub fn set_running(&self, running: bool) {
+        self.running.store(running, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Add an execution to the registry, marked as running.
+pub fn register_run(script_name: Option<&str>) -> Arc<ActiveRun> {
+    let run = Arc::new(ActiveRun {
+        id: NEXT_RUN_ID.fetch_add(1, Ordering::Relaxed),
+        parent: current_parent(),
+        script_name: script_name.map(str::to_string),
+        started: Instant::now(),
+        running: AtomicBool::new(true),
+        cancelled: AtomicBool::new(false),
+    });
+    let mut runs = ACTIVE_RUNS.lock().unwrap();
+    runs.retain(|weak| weak.strong_count() > 0);
+    runs.push(Arc::downgrade(&run));
+    run
+}
+
+/// Parent set by `Monty.within_run` on the calling fiber. Read from
+/// `Thread.current` rather than an OS-thread-local, so fibers sharing a
+/// thread under a fiber scheduler keep their own parents.
+fn current_parent() -> Option<u64> {
+    let ruby = Ruby::get().ok()?;
+    let thread: Value = ruby.class_thread().funcall("current", ()).ok()?;
+    thread
+        .funcall("[]", (ruby.to_symbol(PARENT_RUN_KEY),))
+        .ok()
+        .flatten()
+}
+
+fn live_runs() -> Vec<Arc<ActiveRun>> {
+    ACTIVE_RUNS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect()
+}
+
+/// In-flight executions as an Array of Hashes with :id, :parent_id,
+/// :script_name, :state (:running or :paused) and :elapsed (seconds) keys.
+fn active_runs() -> Result<RArray, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let runs = live_runs();
+    let arr = ruby.ary_new_capa(runs.len());
+    for run in runs {
+        let state = if run.running.load(Ordering::Relaxed) {
+            "running"
+        } else {
+            "paused"
+        };
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("id"), run.id)?;
+        hash.aset(ruby.to_symbol("parent_id"), run.parent)?;
+        hash.aset(ruby.to_symbol("script_name"), run.script_name.clone())?;
+        hash.aset(ruby.to_symbol("state"), ruby.to_symbol(state))?;
+        hash.aset(
+            ruby.to_symbol("elapsed"),
+            run.started.elapsed().as_secs_f64(),
+        )?;
+        arr.push(hash)?;
+    }
+    Ok(arr)
+}
+
+/// Cancel one run. Like `abort_all`, an executing script stops at its
+/// next time check and a paused run when resumed. Returns false if no
+/// such run is active.
+fn cancel_run(id: u64) -> bool {
+    match live_runs().into_iter().find(|run| run.id == id) {
+        Some(run) => {
+            run.cancelled.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Cancel a run and every live run started within it, directly or
+/// through further nesting. Returns how many runs were cancelled.
+fn cancel_tree(id: u64) -> usize {
+    let runs = live_runs();
+    let mut tree = vec![id];
+    let mut next = 0;
+    while next < tree.len() {
+        let parent = tree[next];
+        for run in &runs {
+            if run.parent == Some(parent) && !tree.contains(&run.id) {
+                tree.push(run.id);
+            }
+        }
+        next += 1;
+    }
+
+    let mut cancelled = 0;
+    for run in runs.iter().filter(|run| tree.contains(&run.id)) {
+        run.cancelled.store(true, Ordering::Relaxed);
+        cancelled += 1;
+    }
+    cancelled
+}
+
+/// Stop new runs from starting until `undrain` is called.
+fn drain() -> bool {
+    DRAINING.store(true, Ordering::SeqCst);
+    true
+}
+
+fn undrain() -> bool {
+    DRAINING.store(false, Ordering::SeqCst);
+    false
+}
+
+fn is_draining() -> bool {
+    DRAINING.load(Ordering::SeqCst)
+}
+
+/// Mark every run started so far as aborted. Executing scripts stop at
+/// their next time check (see `stop_tracker`); paused runs stop when
+/// resumed.
+fn abort_all() -> u64 {
+    ABORT_EPOCH.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+/// Token captured when a run starts and compared by `aborted_since`.
+pub fn current_epoch() -> u64 {
+    ABORT_EPOCH.load(Ordering::SeqCst)
+}
+
+pub fn aborted_since(epoch: u64) -> bool {
+    current_epoch() != epoch
+}
+
+/// Raise Monty::Interrupted if `Monty.abort_all!` was called since
+/// `epoch` or `run` was cancelled, for runs that check between steps.
+pub fn check_not_stopped(epoch: u64, run: &ActiveRun) -> Result<(), Error> {
+    let message = if aborted_since(epoch) {
+        "run aborted by Monty.abort_all!"
+    } else if run.is_cancelled() {
+        "run cancelled by Monty.cancel_run"
+    } else {
+        return Ok(());
+    };
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    Err(interrupted_error(
+        message.to_string(),
+        String::new(),
+        ruby.hash_new(),
+    ))
+}
+
+/// Raise Monty::Interrupted if the process is draining.
+pub fn check_accepting() -> Result<(), Error> {
+    if !is_draining() {
+        return Ok(());
+    }
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    Err(interrupted_error(
+        "Monty is draining; no new runs are accepted".to_string(),
+        String::new(),
+        ruby.hash_new(),
+    ))
+}
+
+pub fn define_control(_ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    module.define_singleton_method("drain!", function!(drain, 0))?;
+    module.define_singleton_method("undrain!", function!(undrain, 0))?;
+    module.define_singleton_method("draining?", function!(is_draining, 0))?;
+    module.define_singleton_method("abort_all!", function!(abort_all, 0))?;
+    module.define_singleton_method("active_runs", function!(active_runs, 0))?;
+    module.define_singleton_method("cancel_run", function!(cancel_run, 1))?;
+    module.define_singleton_method("cancel_tree", function!(cancel_tree, 1))?;
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use magnus::r_hash::ForEach;
+use magnus::value::ReprValue;
+use magnus::{function, Error, Object, RHash, Ruby, Symbol, Value};
+use monty_lang::MontyObject;
+
+use crate::errors::monty_error;
+use crate::object_handle::ObjectHandle;
+
+/// How a column's cells become Python values.
+#[derive(Clone, Copy, PartialEq)]
+enum ColumnType {
+    Str,
+    Int,
+    Float,
+    Bool,
+}
+
+impl ColumnType {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "str" | "string" => Some(Self::Str),
+            "int" | "integer" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "bool" | "boolean" => Some(Self::Bool),
+            _ => None,
+        }
+    }
+
+    /// Narrowest type every non-empty cell parses as.
+    fn infer(cells: &[&str]) -> Self {
+        let filled: Vec<&str> = cells.iter().copied().filter(|c| !c.is_empty()).collect();
+        if filled.is_empty() {
+            return Self::Str;
+        }
+        [Self::Int, Self::Float, Self::Bool]
+            .into_iter()
+            .find(|t| filled.iter().all(|cell| t.parse(cell).is_some()))
+            .unwrap_or(Self::Str)
+    }
+
+    fn parse(self, cell: &str) -> Option<MontyObject> {
+        match self {
+            Self::Str => Some(MontyObject::String(cell.to_string())),
+            Self::Int => cell.trim().parse().ok().map(MontyObject::Int),
+            Self::Float => cell.trim().parse().ok().map(MontyObject::Float),
+            Self::Bool => match cell.trim().to_ascii_lowercase().as_str() {
+                "true" => Some(MontyObject::Bool(true)),
+                "false" => Some(MontyObject::Bool(false)),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Split CSV text into records following RFC 4180: quoted fields may
+/// contain separators, newlines and doubled quotes. Raises Monty::Error
+/// if the text ends inside a quoted field.
+fn parse_records(text: &str, separator: char) -> Result<Vec<Vec<String>>, Error> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut quote_line = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line += 1;
+        }
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_quotes = true;
+                quote_line = line;
+            }
+            c if c == separator => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(monty_error(format!(
+            "CSV: unterminated quoted field starting on line {quote_line}"
+        )));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Parse CSV into a list of dicts keyed by the header row.
+///
+/// Arguments:
+///   text      - CSV source
+///   separator - single-character column separator
+///   types     - nil for all strings, :infer to detect int/float/bool
+///               per column, or a Hash of column name => type name
+fn parse_csv(text: String, separator: String, types: Value) -> Result<ObjectHandle, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let mut sep_chars = separator.chars();
+    let (Some(separator), None) = (sep_chars.next(), sep_chars.next()) else {
+        return Err(Error::new(
+            ruby.exception_arg_error(),
+            "col_sep must be a single character",
+        ));
+    };
+
+    let mut records = parse_records(&text, separator)?.into_iter();
+    let Some(headers) = records.next() else {
+        return Ok(ObjectHandle::new(MontyObject::List(Vec::new())));
+    };
+    let rows: Vec<Vec<String>> = records.collect();
+
+    let column_types = column_types(&ruby, &headers, &rows, types)?;
+
+    let mut table = Vec::with_capacity(rows.len());
+    for (row_number, row) in rows.into_iter().enumerate() {
+        let mut pairs = Vec::with_capacity(headers.len());
+        for (column, header) in headers.iter().enumerate() {
+            let cell = row.get(column).map(String::as_str).unwrap_or("");
+            let value = if cell.is_empty() && column_types[column] != ColumnType::Str {
+                MontyObject::None
+            } else {
+                column_types[column].parse(cell).ok_or_else(|| {
+                    monty_error(format!(
+                        "CSV row {}: cannot parse {cell:?} in column '{header}'",
+                        row_number + 2
+                    ))
+                })?
+            };
+            pairs.push((MontyObject::String(header.clone()), value));
+        }
+        table.push(MontyObject::dict(pairs));
+    }
+
+    Ok(ObjectHandle::new(MontyObject::List(table)))
+}
+
+fn column_types(
+    ruby: &Ruby,
+    headers: &[String],
+    rows: &[Vec<String>],
+    types: Value,
+) -> Result<Vec<ColumnType>, Error> {
+    if let Some(types) = RHash::from_value(types) {
+        let mut resolved = vec![ColumnType::Str; headers.len()];
+        types.foreach(|column: Value, type_name: Symbol| {
+            let column: String = column.funcall("to_s", ())?;
+            let type_name = type_name.name()?;
+            let column_type = ColumnType::from_name(&type_name).ok_or_else(|| {
+                Error::new(
+                    ruby.exception_arg_error(),
+                    format!("unknown CSV column type :{type_name}"),
+                )
+            })?;
+            if let Some(index) = headers.iter().position(|h| *h == column) {
+                resolved[index] = column_type;
+            }
+            Ok(ForEach::Continue)
+        })?;
+        return Ok(resolved);
+    }
+
+    if Symbol::from_value(types).is_some_and(|s| s.name().is_ok_and(|n| n == "infer")) {
+        return Ok((0..headers.len())
+            .map(|column| {
+                let cells: Vec<&str> = rows
+                    .iter()
+                    .map(|row| row.get(column).map(String::as_str).unwrap_or(""))
+                    .collect();
+                ColumnType::infer(&cells)
+            })
+            .collect());
+    }
+
+    Ok(vec![ColumnType::Str; headers.len()])
+}
+
+pub fn define_csv_input(_ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    module.define_singleton_method("_parse_csv", function!(parse_csv, 3))?;
+
+    Ok(())
+}
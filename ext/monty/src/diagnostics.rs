@@ -0,0 +1,69 @@
+use magnus::{function, Error, Object, RArray, RHash, Ruby};
+use monty_lang::{MontyException, MontyRun};
+
+use crate::source_analysis::unused_declarations;
+
+/// Compile the code and report problems as data instead of raising.
+///
+/// Returns an Array of Hashes with :severity, :message and :span keys;
+/// the Array is empty when the code compiles cleanly. Declared inputs and
+/// external functions the code never mentions are reported as warnings.
+fn diagnostics(
+    code: String,
+    script_name: String,
+    inputs: Vec<String>,
+    external_functions: Vec<String>,
+) -> Result<RArray, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let arr = ruby.ary_new();
+
+    for message in unused_declarations(&code, &inputs, &external_functions) {
+        arr.push(warning_hash(ruby, message)?)?;
+    }
+    if let Err(exc) = MontyRun::new(code, &script_name, inputs, external_functions) {
+        arr.push(diagnostic_hash(ruby, "error", &exc)?)?;
+    }
+
+    Ok(arr)
+}
+
+fn diagnostic_hash(ruby: &Ruby, severity: &str, exc: &MontyException) -> Result<RHash, Error> {
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("severity"), ruby.to_symbol(severity))?;
+    hash.aset(ruby.to_symbol("message"), exc.summary())?;
+    hash.aset(ruby.to_symbol("span"), span_hash(ruby, exc)?)?;
+    Ok(hash)
+}
+
+/// A warning found by static analysis, which carries no source span.
+pub fn warning_hash(ruby: &Ruby, message: String) -> Result<RHash, Error> {
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("severity"), ruby.to_symbol("warning"))?;
+    hash.aset(ruby.to_symbol("message"), message)?;
+    hash.aset(ruby.to_symbol("span"), ruby.qnil())?;
+    Ok(hash)
+}
+
+/// Source span of the innermost traceback frame, or nil if there is none.
+fn span_hash(ruby: &Ruby, exc: &MontyException) -> Result<Option<RHash>, Error> {
+    let Some(frame) = exc.traceback().last() else {
+        return Ok(None);
+    };
+
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("line"), frame.start.line)?;
+    hash.aset(ruby.to_symbol("column"), frame.start.column)?;
+    hash.aset(ruby.to_symbol("end_line"), frame.end.line)?;
+    hash.aset(ruby.to_symbol("end_column"), frame.end.column)?;
+    Ok(Some(hash))
+}
+
+pub fn define_diagnostics(_ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    module.define_singleton_method("_diagnostics", function!(diagnostics, 4))?;
+    module.define_singleton_method(
+        "_float_uses",
+        function!(crate::source_analysis::float_uses_to_ruby, 1),
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use magnus::{function, Error, Object, RArray, Ruby, Value};
+use monty_lang::MontyObject;
+
+use crate::monty_object::{monty_to_ruby, ruby_to_monty};
+use crate::object_handle::py_eq;
+
+/// One difference between two values, located by the keys and indices
+/// leading to it from the root.
+enum Change {
+    Added(Vec<MontyObject>, MontyObject),
+    Removed(Vec<MontyObject>, MontyObject),
+    Changed(Vec<MontyObject>, MontyObject, MontyObject),
+}
+
+/// Walk `a` and `b` together, descending into dicts, lists, tuples and
+/// dataclasses of the same shape. Anything else that is not `==` is
+/// reported as changed at the current path.
+fn diff_into(a: &MontyObject, b: &MontyObject, path: &mut Vec<MontyObject>, out: &mut Vec<Change>) {
+    match (a, b) {
+        (MontyObject::Dict(x), MontyObject::Dict(y)) => diff_pairs(x, y, path, out),
+        (
+            MontyObject::Dataclass {
+                name: x_name,
+                attrs: x_attrs,
+                ..
+            },
+            MontyObject::Dataclass {
+                name: y_name,
+                attrs: y_attrs,
+                ..
+            },
+        ) if x_name == y_name => diff_pairs(x_attrs, y_attrs, path, out),
+        (MontyObject::List(x), MontyObject::List(y))
+        | (MontyObject::Tuple(x), MontyObject::Tuple(y)) => diff_items(x, y, path, out),
+        _ if py_eq(a, b) => {}
+        _ => out.push(Change::Changed(path.clone(), a.clone(), b.clone())),
+    }
+}
+
+fn diff_pairs<'a, P>(x: &'a P, y: &'a P, path: &mut Vec<MontyObject>, out: &mut Vec<Change>)
+where
+    &'a P: IntoIterator<Item = &'a (MontyObject, MontyObject)>,
+{
+    for (key, old) in x {
+        path.push(key.clone());
+        match y.into_iter().find(|(k, _)| py_eq(k, key)) {
+            Some((_, new)) => diff_into(old, new, path, out),
+            None => out.push(Change::Removed(path.clone(), old.clone())),
+        }
+        path.pop();
+    }
+    for (key, new) in y {
+        if !x.into_iter().any(|(k, _)| py_eq(k, key)) {
+            path.push(key.clone());
+            out.push(Change::Added(path.clone(), new.clone()));
+            path.pop();
+        }
+    }
+}
+
+fn diff_items(
+    x: &[MontyObject],
+    y: &[MontyObject],
+    path: &mut Vec<MontyObject>,
+    out: &mut Vec<Change>,
+) {
+    for index in 0..x.len().max(y.len()) {
+        path.push(MontyObject::Int(index as i64));
+        match (x.get(index), y.get(index)) {
+            (Some(old), Some(new)) => diff_into(old, new, path, out),
+            (Some(old), None) => out.push(Change::Removed(path.clone(), old.clone())),
+            (None, Some(new)) => out.push(Change::Added(path.clone(), new.clone())),
+            (None, None) => unreachable!(),
+        }
+        path.pop();
+    }
+}
+
+/// Structural diff of two values as an Array of Hashes with :op
+/// (:added, :removed or :changed), :path (Array of keys and indices) and
+/// :from and/or :to. Accepts Ruby values or Monty::Object handles.
+fn diff(a: Value, b: Value) -> Result<RArray, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let mut changes = Vec::new();
+    diff_into(
+        &ruby_to_monty(a)?,
+        &ruby_to_monty(b)?,
+        &mut Vec::new(),
+        &mut changes,
+    );
+
+    let arr = ruby.ary_new_capa(changes.len());
+    for change in changes {
+        let hash = ruby.hash_new();
+        let (op, path, from, to) = match change {
+            Change::Added(path, to) => ("added", path, None, Some(to)),
+            Change::Removed(path, from) => ("removed", path, Some(from), None),
+            Change::Changed(path, from, to) => ("changed", path, Some(from), Some(to)),
+        };
+        hash.aset(ruby.to_symbol("op"), ruby.to_symbol(op))?;
+        let path_arr = ruby.ary_new_capa(path.len());
+        for step in path {
+            path_arr.push(monty_to_ruby(step)?)?;
+        }
+        hash.aset(ruby.to_symbol("path"), path_arr)?;
+        if let Some(from) = from {
+            hash.aset(ruby.to_symbol("from"), monty_to_ruby(from)?)?;
+        }
+        if let Some(to) = to {
+            hash.aset(ruby.to_symbol("to"), monty_to_ruby(to)?)?;
+        }
+        arr.push(hash)?;
+    }
+    Ok(arr)
+}
+
+pub fn define_diff(_ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    module.define_singleton_method("diff", function!(diff, 2))?;
+
+    Ok(())
+}
@@ -1,130 +1,396 @@
-use magnus::{Error, ExceptionClass, Module, Ruby};
-use std::cell::RefCell;
+use magnus::value::Opaque;
+use magnus::{Class, Error, ExceptionClass, IntoValue, Module, Object, RArray, RHash, Ruby};
+use monty_lang::MontyException;
+use std::fmt::Write;
+use std::sync::OnceLock;
 
-thread_local! {
-    static MONTY_ERROR: RefCell<Option<ExceptionClass>> = const { RefCell::new(None) };
-    static SYNTAX_ERROR: RefCell<Option<ExceptionClass>> = const { RefCell::new(None) };
-    static RESOURCE_ERROR: RefCell<Option<ExceptionClass>> = const { RefCell::new(None) };
-    static CONSUMED_ERROR: RefCell<Option<ExceptionClass>> = const { RefCell::new(None) };
-}
+// Process-wide rather than thread-local, so errors raised inside a Ractor
+// (which runs on its own thread) get the same classes. The classes are
+// constants of Monty and are never collected.
+static MONTY_ERROR: OnceLock<Opaque<ExceptionClass>> = OnceLock::new();
+static SYNTAX_ERROR: OnceLock<Opaque<ExceptionClass>> = OnceLock::new();
+static RESOURCE_ERROR: OnceLock<Opaque<ExceptionClass>> = OnceLock::new();
+static CONSUMED_ERROR: OnceLock<Opaque<ExceptionClass>> = OnceLock::new();
+static INTERRUPTED_ERROR: OnceLock<Opaque<ExceptionClass>> = OnceLock::new();
+static SNAPSHOT_EXPIRED_ERROR: OnceLock<Opaque<ExceptionClass>> = OnceLock::new();
+static CONVERSION_ERROR: OnceLock<Opaque<ExceptionClass>> = OnceLock::new();
+static PYTHON_ERRORS: OnceLock<Vec<(&'static str, Opaque<ExceptionClass>)>> = OnceLock::new();
+static LIMIT_ERRORS: OnceLock<Vec<(&'static str, Opaque<ExceptionClass>)>> = OnceLock::new();
+
+/// Python exception types raised as their own Monty::Error subclass, each
+/// with its parent class, following Python's hierarchy. Parents come
+/// before their children.
+const PYTHON_ERROR_CLASSES: &[(&str, &str)] = &[
+    ("ArithmeticError", "Error"),
+    ("ZeroDivisionError", "ArithmeticError"),
+    ("OverflowError", "ArithmeticError"),
+    ("LookupError", "Error"),
+    ("KeyError", "LookupError"),
+    ("IndexError", "LookupError"),
+    ("NameError", "Error"),
+    ("UnboundLocalError", "NameError"),
+    ("RuntimeError", "Error"),
+    ("NotImplementedError", "RuntimeError"),
+    ("RecursionError", "RuntimeError"),
+    ("ImportError", "Error"),
+    ("ModuleNotFoundError", "ImportError"),
+    ("OSError", "Error"),
+    ("FileNotFoundError", "OSError"),
+    ("TypeError", "Error"),
+    ("ValueError", "Error"),
+    ("AttributeError", "Error"),
+    ("AssertionError", "Error"),
+    ("StopIteration", "Error"),
+    ("TimeoutError", "Error"),
+];
+
+/// Monty::ResourceError subclasses, one per interpreter limit.
+const LIMIT_ERROR_CLASSES: &[&str] = &[
+    "TimeLimitExceeded",
+    "MemoryLimitExceeded",
+    "AllocationLimitExceeded",
+    "RecursionLimitExceeded",
+];
 
 pub fn define_exceptions(ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
     let standard_error = ruby.exception_standard_error();
 
     let monty_error = module.define_error("Error", standard_error)?;
-    MONTY_ERROR.with(|cell| {
-        *cell.borrow_mut() = Some(monty_error);
-    });
+    store(&MONTY_ERROR, monty_error);
 
     let syntax_error = module.define_error("SyntaxError", monty_error)?;
-    SYNTAX_ERROR.with(|cell| {
-        *cell.borrow_mut() = Some(syntax_error);
-    });
+    store(&SYNTAX_ERROR, syntax_error);
 
     let resource_error = module.define_error("ResourceError", monty_error)?;
-    RESOURCE_ERROR.with(|cell| {
-        *cell.borrow_mut() = Some(resource_error);
-    });
+    store(&RESOURCE_ERROR, resource_error);
 
     let consumed_error = module.define_error("ConsumedError", monty_error)?;
-    CONSUMED_ERROR.with(|cell| {
-        *cell.borrow_mut() = Some(consumed_error);
-    });
+    store(&CONSUMED_ERROR, consumed_error);
+
+    let interrupted_error = module.define_error("Interrupted", monty_error)?;
+    store(&INTERRUPTED_ERROR, interrupted_error);
+
+    let snapshot_expired_error = module.define_error("SnapshotExpiredError", monty_error)?;
+    store(&SNAPSHOT_EXPIRED_ERROR, snapshot_expired_error);
+
+    let conversion_error = module.define_error("ConversionError", monty_error)?;
+    store(&CONVERSION_ERROR, conversion_error);
+
+    // Raised from Ruby by Monty::Policy.
+    module.define_error("PolicyError", monty_error)?;
+
+    let mut python_errors: Vec<(&'static str, ExceptionClass)> = Vec::new();
+    for (name, parent) in PYTHON_ERROR_CLASSES {
+        let parent = python_errors
+            .iter()
+            .find(|(n, _)| n == parent)
+            .map_or(monty_error, |(_, class)| *class);
+        python_errors.push((name, module.define_error(*name, parent)?));
+    }
+    let _ = PYTHON_ERRORS.set(opaque_table(python_errors));
+
+    let mut limit_errors: Vec<(&'static str, ExceptionClass)> = Vec::new();
+    for name in LIMIT_ERROR_CLASSES {
+        limit_errors.push((name, module.define_error(*name, resource_error)?));
+    }
+    let _ = LIMIT_ERRORS.set(opaque_table(limit_errors));
 
     Ok(())
 }
 
 pub fn monty_error(message: String) -> Error {
-    MONTY_ERROR.with(|cell| {
-        let class = cell.borrow();
-        match class.as_ref() {
-            Some(cls) => Error::new(*cls, message),
-            None => {
-                let ruby = Ruby::get().expect("Ruby runtime not available");
-                Error::new(ruby.exception_runtime_error(), message)
-            },
-        }
-    })
+    Error::new(stored_class(&MONTY_ERROR), message)
 }
 
 pub fn syntax_error(message: String) -> Error {
-    SYNTAX_ERROR.with(|cell| {
-        let class = cell.borrow();
-        match class.as_ref() {
-            Some(cls) => Error::new(*cls, message),
-            None => {
-                let ruby = Ruby::get().expect("Ruby runtime not available");
-                Error::new(ruby.exception_runtime_error(), message)
-            },
-        }
-    })
+    Error::new(stored_class(&SYNTAX_ERROR), message)
 }
 
 pub fn resource_error(message: String) -> Error {
-    RESOURCE_ERROR.with(|cell| {
-        let class = cell.borrow();
-        match class.as_ref() {
-            Some(cls) => Error::new(*cls, message),
-            None => {
-                let ruby = Ruby::get().expect("Ruby runtime not available");
-                Error::new(ruby.exception_runtime_error(), message)
-            },
-        }
-    })
+    Error::new(stored_class(&RESOURCE_ERROR), message)
 }
 
 pub fn consumed_error() -> Error {
-    CONSUMED_ERROR.with(|cell| {
-        let class = cell.borrow();
-        match class.as_ref() {
-            Some(cls) => Error::new(
-                *cls,
-                "this object has been consumed and can no longer be used",
-            ),
-            None => {
-                let ruby = Ruby::get().expect("Ruby runtime not available");
-                Error::new(
-                    ruby.exception_runtime_error(),
-                    "this object has been consumed and can no longer be used",
-                )
+    Error::new(
+        stored_class(&CONSUMED_ERROR),
+        "this object has been consumed and can no longer be used",
+    )
+}
+
+pub fn conversion_error(message: String) -> Error {
+    Error::new(stored_class(&CONVERSION_ERROR), message)
+}
+
+pub fn snapshot_expired_error(message: String) -> Error {
+    Error::new(stored_class(&SNAPSHOT_EXPIRED_ERROR), message)
+}
+
+/// Raised when the host aborts a run, carrying the output produced and
+/// the stats accumulated before the interruption.
+pub fn interrupted_error(message: String, output: String, stats: RHash) -> Error {
+    let class = stored_class(&INTERRUPTED_ERROR);
+    match class.new_instance((message,)) {
+        Ok(instance) => {
+            if let Err(e) = instance.ivar_set("@output", output) {
+                return e;
+            }
+            if let Err(e) = instance.ivar_set("@stats", stats) {
+                return e;
             }
+            Error::from(instance)
         }
-    })
+        Err(e) => e,
+    }
 }
 
 pub fn map_monty_exception(exc: monty_lang::MontyException) -> Error {
+    let name = format!("{:?}", exc.exc_type());
     let summary = exc.summary();
+    if let Some(limit_name) = limit_error_name(&name, &summary) {
+        let err = build_exception(limit_error_class(limit_name), &exc);
+        let (limit, used) = limit_figures(&summary);
+        return match limit_name {
+            "TimeLimitExceeded" => with_limit_figures(err, limit, used),
+            _ => with_limit_figures(err, limit.map(|n| n as u64), used.map(|n| n as u64)),
+        };
+    }
+
+    let class = if exc.exc_type() == monty_lang::ExcType::SyntaxError {
+        stored_class(&SYNTAX_ERROR)
+    } else {
+        python_error_class(&name)
+    };
+
+    build_exception(class, &exc)
+}
 
-    // Check if it's a syntax error
-    if exc.exc_type() == monty_lang::ExcType::SyntaxError {
-        return syntax_error(summary);
+/// The interpreter's resource-limit messages: the Python exception type
+/// each is raised as, the message prefix and the limit error class.
+const LIMIT_MESSAGES: &[(&str, &str, &str)] = &[
+    ("TimeoutError", "time limit exceeded", "TimeLimitExceeded"),
+    (
+        "MemoryError",
+        "allocation limit exceeded",
+        "AllocationLimitExceeded",
+    ),
+    (
+        "MemoryError",
+        "memory limit exceeded",
+        "MemoryLimitExceeded",
+    ),
+    (
+        "RecursionError",
+        "recursion limit exceeded",
+        "RecursionLimitExceeded",
+    ),
+];
+
+/// The limit error raised for a Python exception, if it is the
+/// interpreter reporting one of its resource limits: both the type and the
+/// start of the message match a tracker message. TimeoutError, MemoryError
+/// and RecursionError raised by the script itself or handed back by the
+/// host, such as `TimeoutError("rate limit exceeded")`, keep their Python
+/// classes.
+fn limit_error_name(exc_type: &str, summary: &str) -> Option<&'static str> {
+    let message = summary
+        .strip_prefix(exc_type)
+        .and_then(|rest| rest.strip_prefix(": "))
+        .unwrap_or(summary);
+    LIMIT_MESSAGES
+        .iter()
+        .find(|(exc, prefix, _)| *exc == exc_type && message.starts_with(prefix))
+        .map(|(_, _, name)| *name)
+}
+
+/// The `(limit, used)` figures from a message like "memory limit exceeded:
+/// 2048 bytes used (limit: 1024)". Either is None when the message does not
+/// state it.
+fn limit_figures(message: &str) -> (Option<f64>, Option<f64>) {
+    let number = |text: &str| -> Option<f64> {
+        let start = text.find(|c: char| c.is_ascii_digit())?;
+        let digits: String = text[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        digits.trim_end_matches('.').parse().ok()
+    };
+    match message.split_once("(limit: ") {
+        Some((before, after)) => (number(after), number(before)),
+        None => (None, None),
     }
+}
 
-    monty_error(summary)
+fn limit_error_class(name: &str) -> ExceptionClass {
+    table_class(&LIMIT_ERRORS, name).unwrap_or_else(|| stored_class(&RESOURCE_ERROR))
 }
 
-pub fn map_resource_error(err: monty_lang::ResourceError) -> Error {
-    let message = match err {
-        monty_lang::ResourceError::Allocation { limit, count } => {
-            format!("allocation limit exceeded: {count} allocations (limit: {limit})")
+/// Attach `@limit` and `@used` to a limit error built by the binding.
+fn with_limit_figures<T: IntoValue>(err: Error, limit: Option<T>, used: Option<T>) -> Error {
+    let Some(instance) = err.value() else {
+        return err;
+    };
+    if let Err(e) = instance.ivar_set("@limit", limit) {
+        return e;
+    }
+    if let Err(e) = instance.ivar_set("@used", used) {
+        return e;
+    }
+    err
+}
+
+/// Raise limit error `name` with the structured figures alongside the
+/// message.
+fn limit_error<T: IntoValue>(name: &str, message: String, limit: T, used: T) -> Error {
+    match limit_error_class(name).new_instance((message,)) {
+        Ok(instance) => with_limit_figures(Error::from(instance), Some(limit), Some(used)),
+        Err(e) => e,
+    }
+}
+
+/// Monty::Error subclass for a Python exception type name, falling back to
+/// Monty::Error for types without one.
+fn python_error_class(name: &str) -> ExceptionClass {
+    table_class(&PYTHON_ERRORS, name).unwrap_or_else(|| stored_class(&MONTY_ERROR))
+}
+
+/// Raise the Monty::Error subclass for Python exception type `name` from
+/// the binding, e.g. for a missing key looked up through Monty::Object.
+pub fn python_error(name: &str, message: String) -> Error {
+    Error::new(python_error_class(name), message)
+}
+
+fn store(cell: &OnceLock<Opaque<ExceptionClass>>, class: ExceptionClass) {
+    let _ = cell.set(Opaque::from(class));
+}
+
+fn opaque_table(
+    table: Vec<(&'static str, ExceptionClass)>,
+) -> Vec<(&'static str, Opaque<ExceptionClass>)> {
+    table
+        .into_iter()
+        .map(|(name, class)| (name, Opaque::from(class)))
+        .collect()
+}
+
+fn stored_class(cell: &OnceLock<Opaque<ExceptionClass>>) -> ExceptionClass {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    match cell.get() {
+        Some(class) => ruby.get_inner(*class),
+        None => ruby.exception_runtime_error(),
+    }
+}
+
+fn table_class(
+    table: &OnceLock<Vec<(&'static str, Opaque<ExceptionClass>)>>,
+    name: &str,
+) -> Option<ExceptionClass> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    table
+        .get()?
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, class)| ruby.get_inner(*class))
+}
+
+/// Instantiate the Ruby exception eagerly so the formatted traceback,
+/// frames and Python exception class can be attached alongside the
+/// summary message.
+fn build_exception(class: ExceptionClass, exc: &MontyException) -> Error {
+    let attach = |instance: magnus::Exception| -> Result<Error, Error> {
+        instance.ivar_set("@formatted_traceback", format_traceback(exc))?;
+        instance.ivar_set("@traceback", traceback_frames(exc)?)?;
+        instance.ivar_set("@python_class", format!("{:?}", exc.exc_type()))?;
+        Ok(Error::from(instance))
+    };
+    match class.new_instance((exc.summary(),)) {
+        Ok(instance) => attach(instance).unwrap_or_else(|e| e),
+        Err(e) => e,
+    }
+}
+
+/// The traceback as Hashes with :file, :line, :function and :source_line
+/// keys, outermost frame first.
+fn traceback_frames(exc: &MontyException) -> Result<RArray, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let frames = exc.traceback();
+    let arr = ruby.ary_new_capa(frames.len());
+    for frame in frames {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("file"), frame.filename.to_string())?;
+        hash.aset(ruby.to_symbol("line"), frame.start.line)?;
+        hash.aset(
+            ruby.to_symbol("function"),
+            frame.frame_name.as_deref().unwrap_or("<module>"),
+        )?;
+        hash.aset(
+            ruby.to_symbol("source_line"),
+            frame.preview_line.as_deref().map(str::trim),
+        )?;
+        arr.push(hash)?;
+    }
+    Ok(arr)
+}
+
+/// Render a CPython-style traceback with source excerpts and caret markers.
+pub fn format_traceback(exc: &MontyException) -> String {
+    let mut out = String::new();
+    let frames = exc.traceback();
+
+    if !frames.is_empty() {
+        out.push_str("Traceback (most recent call last):\n");
+    }
+    for frame in frames {
+        let name = frame.frame_name.as_deref().unwrap_or("<module>");
+        let _ = writeln!(
+            out,
+            "  File \"{}\", line {}, in {name}",
+            frame.filename, frame.start.line
+        );
+        if let Some(source) = &frame.preview_line {
+            let trimmed = source.trim_start();
+            let _ = writeln!(out, "    {}", trimmed.trim_end());
+            if !frame.hide_caret && frame.start.line == frame.end.line {
+                let indent = source.len() - trimmed.len();
+                let start = (frame.start.column as usize).saturating_sub(1 + indent);
+                let width = (frame.end.column as usize).saturating_sub(frame.start.column as usize);
+                let _ = writeln!(out, "    {}{}", " ".repeat(start), "^".repeat(width.max(1)));
+            }
         }
-        monty_lang::ResourceError::Time { limit, elapsed } => {
+    }
+    out.push_str(&exc.summary());
+    out
+}
+
+pub fn map_resource_error(err: monty_lang::ResourceError) -> Error {
+    match err {
+        monty_lang::ResourceError::Allocation { limit, count } => limit_error(
+            "AllocationLimitExceeded",
+            format!("allocation limit exceeded: {count} allocations (limit: {limit})"),
+            limit as u64,
+            count as u64,
+        ),
+        monty_lang::ResourceError::Time { limit, elapsed } => limit_error(
+            "TimeLimitExceeded",
             format!(
                 "time limit exceeded: {:.2}s elapsed (limit: {:.2}s)",
                 elapsed.as_secs_f64(),
                 limit.as_secs_f64()
-            )
-        }
-        monty_lang::ResourceError::Memory { limit, used } => {
-            format!("memory limit exceeded: {used} bytes used (limit: {limit})")
-        }
-        monty_lang::ResourceError::Recursion { limit, depth } => {
-            format!("recursion limit exceeded: depth {depth} (limit: {limit})")
-        }
-        monty_lang::ResourceError::Exception(exc) => {
-            return map_monty_exception(exc);
-        }
-    };
-
-    resource_error(message)
+            ),
+            limit.as_secs_f64(),
+            elapsed.as_secs_f64(),
+        ),
+        monty_lang::ResourceError::Memory { limit, used } => limit_error(
+            "MemoryLimitExceeded",
+            format!("memory limit exceeded: {used} bytes used (limit: {limit})"),
+            limit as u64,
+            used as u64,
+        ),
+        monty_lang::ResourceError::Recursion { limit, depth } => limit_error(
+            "RecursionLimitExceeded",
+            format!("recursion limit exceeded: depth {depth} (limit: {limit})"),
+            limit as u64,
+            depth as u64,
+        ),
+        monty_lang::ResourceError::Exception(exc) => map_monty_exception(exc),
+    }
 }
@@ -0,0 +1,157 @@
+use magnus::value::ReprValue;
+use magnus::{Error, RArray, RHash, Ruby, Symbol, Value};
+use monty_lang::MontyObject;
+
+use crate::monty_object::ruby_to_monty;
+
+/// How to produce one random input value, parsed from a Ruby type spec.
+pub enum Generator {
+    Int(Option<(i64, i64)>),
+    Float,
+    Str,
+    Bool,
+    None,
+    List(Box<Generator>),
+    Dict(Vec<(MontyObject, Generator)>),
+}
+
+impl Generator {
+    /// Parse a spec: :int, :float, :str, :bool or :none, an Integer
+    /// Range, `[spec]` for a list, or `{key => spec}` for a dict with
+    /// those keys.
+    pub fn parse(spec: Value) -> Result<Self, Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        if let Some(symbol) = Symbol::from_value(spec) {
+            return match symbol.name()?.as_ref() {
+                "int" | "integer" => Ok(Self::Int(None)),
+                "float" => Ok(Self::Float),
+                "str" | "string" => Ok(Self::Str),
+                "bool" | "boolean" => Ok(Self::Bool),
+                "none" | "nil" => Ok(Self::None),
+                name => Err(Error::new(
+                    ruby.exception_arg_error(),
+                    format!("unknown fuzz generator :{name}"),
+                )),
+            };
+        }
+        if spec.is_kind_of(ruby.class_range()) {
+            let low: i64 = spec.funcall("begin", ())?;
+            let mut high: i64 = spec.funcall("end", ())?;
+            if spec.funcall::<_, _, bool>("exclude_end?", ())? {
+                high -= 1;
+            }
+            if low > high {
+                return Err(Error::new(
+                    ruby.exception_arg_error(),
+                    format!("empty fuzz range {}", spec.inspect()),
+                ));
+            }
+            return Ok(Self::Int(Some((low, high))));
+        }
+        if let Some(arr) = RArray::from_value(spec) {
+            if arr.len() == 1 {
+                return Ok(Self::List(Box::new(Self::parse(arr.entry(0)?)?)));
+            }
+        }
+        if let Some(hash) = RHash::from_value(spec) {
+            let keys: RArray = hash.funcall("keys", ())?;
+            let mut fields = Vec::with_capacity(keys.len());
+            for key in keys.to_vec::<Value>()? {
+                fields.push((ruby_to_monty(key)?, Self::parse(hash.aref(key)?)?));
+            }
+            return Ok(Self::Dict(fields));
+        }
+        Err(Error::new(
+            ruby.exception_arg_error(),
+            format!("invalid fuzz generator {}", spec.inspect()),
+        ))
+    }
+
+    /// A random value, drawn from the type's edge cases a quarter of the
+    /// time since those are what scripts most often mishandle.
+    pub fn generate(&self, rng: &mut Rng) -> MontyObject {
+        let edge = rng.below(4) == 0;
+        match self {
+            Self::Int(None) if edge => MontyObject::Int(*rng.pick(&[0, 1, -1, i64::MAX, i64::MIN])),
+            Self::Int(None) => MontyObject::Int(rng.between(-1000, 1000)),
+            Self::Int(Some((low, high))) if edge => MontyObject::Int(*rng.pick(&[*low, *high])),
+            Self::Int(Some((low, high))) => MontyObject::Int(rng.between(*low, *high)),
+            Self::Float if edge => MontyObject::Float(*rng.pick(&[
+                0.0,
+                -0.0,
+                f64::NAN,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::MAX,
+                f64::MIN_POSITIVE,
+            ])),
+            Self::Float => MontyObject::Float(rng.unit() * 2000.0 - 1000.0),
+            Self::Str if edge => {
+                MontyObject::String(rng.pick(&["", " ", "0", "é", "😀", "\n"]).to_string())
+            }
+            Self::Str => {
+                let len = rng.below(16) as usize;
+                MontyObject::String(
+                    (0..len)
+                        .map(|_| char::from(b' ' + rng.below(95) as u8))
+                        .collect(),
+                )
+            }
+            Self::Bool => MontyObject::Bool(rng.below(2) == 1),
+            Self::None => MontyObject::None,
+            Self::List(item) => {
+                let len = rng.below(6) as usize;
+                MontyObject::List((0..len).map(|_| item.generate(rng)).collect())
+            }
+            Self::Dict(fields) => MontyObject::dict(
+                fields
+                    .iter()
+                    .map(|(key, field)| (key.clone(), field.generate(rng)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Small seeded generator (xorshift64*), so a failing iteration can be
+/// reproduced from the seed without pulling in a random number crate.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    fn between(&mut self, low: i64, high: i64) -> i64 {
+        let span = (high as i128 - low as i128 + 1) as u128;
+        (low as i128 + (self.next_u64() as u128 % span) as i128) as i64
+    }
+
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn pick<'a, T>(&mut self, choices: &'a [T]) -> &'a T {
+        &choices[self.below(choices.len() as u64) as usize]
+    }
+}
+
+/// Parse one generator per input from a Ruby Array of specs.
+pub fn parse_generators(specs: RArray) -> Result<Vec<Generator>, Error> {
+    specs
+        .to_vec::<Value>()?
+        .into_iter()
+        .map(Generator::parse)
+        .collect()
+}
@@ -0,0 +1,96 @@
+use magnus::rb_sys::{protect, AsRawValue};
+use magnus::{Error, Ruby};
+use std::any::Any;
+use std::ffi::c_void;
+use std::panic::{self, AssertUnwindSafe};
+
+struct Call<F, R> {
+    func: Option<F>,
+    result: Option<Result<R, Box<dyn Any + Send>>>,
+}
+
+unsafe extern "C" fn trampoline<F, R>(data: *mut c_void) -> *mut c_void
+where
+    F: FnOnce() -> R,
+{
+    let call = &mut *(data as *mut Call<F, R>);
+    if let Some(func) = call.func.take() {
+        call.result = Some(panic::catch_unwind(AssertUnwindSafe(func)));
+    }
+    std::ptr::null_mut()
+}
+
+unsafe extern "C" fn unblock_trampoline<U>(data: *mut c_void)
+where
+    U: Fn() + Sync,
+{
+    let unblock = &*(data as *const U);
+    unblock();
+}
+
+/// Run `func` with the GVL released so other Ruby threads keep running.
+///
+/// `func` must not touch any Ruby object. If the thread is interrupted
+/// (Thread#raise, Thread#kill, Timeout, Ctrl-C), Ruby calls `unblock`
+/// from another thread; it must make `func` return promptly, typically
+/// by setting a flag `func` polls. The interrupt is then returned as an
+/// error instead of unwinding through the caller's frames, and `func`'s
+/// result is dropped. An interrupt already pending on entry is returned
+/// without calling `func` at all.
+///
+/// A panic inside `func` is carried back and resumed once the GVL is held
+/// again.
+pub fn without_gvl<F, U, R>(func: F, unblock: U) -> Result<R, Error>
+where
+    F: FnOnce() -> R,
+    U: Fn() + Sync,
+{
+    let mut call = Call {
+        func: Some(func),
+        result: None,
+    };
+    let interrupted = protect(|| {
+        unsafe {
+            rb_sys::rb_thread_call_without_gvl(
+                Some(trampoline::<F, R>),
+                &mut call as *mut Call<F, R> as *mut c_void,
+                Some(unblock_trampoline::<U>),
+                &unblock as *const U as *mut c_void,
+            );
+        }
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        ruby.qnil().as_raw()
+    })
+    .err();
+    match (call.result, interrupted) {
+        (Some(Err(payload)), _) => panic::resume_unwind(payload),
+        (_, Some(e)) => Err(e),
+        (Some(Ok(result)), None) => Ok(result),
+        (None, None) => unreachable!("without_gvl callback did not run"),
+    }
+}
+
+/// Run `func` with the GVL held again, from inside a `without_gvl`
+/// callback, e.g. to call back into Ruby while the interpreter prints.
+/// Must only be called on a thread that released the GVL with
+/// `without_gvl`. A panic inside `func` is resumed on the calling side.
+pub fn with_gvl<F, R>(func: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let mut call = Call {
+        func: Some(func),
+        result: None,
+    };
+    unsafe {
+        rb_sys::rb_thread_call_with_gvl(
+            Some(trampoline::<F, R>),
+            &mut call as *mut Call<F, R> as *mut c_void,
+        );
+    }
+    match call.result {
+        Some(Ok(result)) => result,
+        Some(Err(payload)) => panic::resume_unwind(payload),
+        None => unreachable!("with_gvl callback did not run"),
+    }
+}
@@ -0,0 +1,344 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use monty_lang::{ExcType, MontyException, MontyObject};
+use sha2::{Digest, Sha256};
+
+use crate::json;
+
+/// Numeric, string-similarity, JSON, base64 and digest functions a Run can
+/// opt into with `intrinsics: true`. Scripts call them like external functions, but the
+/// call is answered in Rust without returning to the host.
+pub const NAMES: &[&str] = &[
+    "fsum",
+    "mean",
+    "stdev",
+    "dot",
+    "percentile",
+    "levenshtein",
+    "jaro_winkler",
+    "similarity",
+    "json_loads",
+    "json_dumps",
+    "b64encode",
+    "b64decode",
+    "sha256",
+    "md5",
+    "hmac_sha256",
+];
+
+/// Largest `len(a) * len(b)` the string-similarity intrinsics accept.
+/// They take time proportional to that product with the GVL held and
+/// outside the run's instruction budget, so longer inputs raise
+/// ValueError instead.
+const MAX_SIMILARITY_CELLS: usize = 4_000_000;
+
+/// Why an intrinsic rejected its arguments; raised inside the script.
+pub enum Failure {
+    Type(String),
+    Value(String),
+}
+
+impl Failure {
+    /// Python-style description for the segment log.
+    pub fn describe(&self) -> String {
+        match self {
+            Failure::Type(message) => format!("raise TypeError({message:?})"),
+            Failure::Value(message) => format!("raise ValueError({message:?})"),
+        }
+    }
+
+    pub fn into_exception(self) -> MontyException {
+        match self {
+            Failure::Type(message) => MontyException::new(ExcType::TypeError, Some(message)),
+            Failure::Value(message) => MontyException::new(ExcType::ValueError, Some(message)),
+        }
+    }
+}
+
+/// Evaluate intrinsic `name`, or return None if it is not one.
+pub fn call(
+    name: &str,
+    args: &[MontyObject],
+    kwargs: &[(MontyObject, MontyObject)],
+) -> Option<Result<MontyObject, Failure>> {
+    if !NAMES.contains(&name) {
+        return None;
+    }
+    if !kwargs.is_empty() {
+        return Some(Err(Failure::Type(format!(
+            "{name}() takes no keyword arguments"
+        ))));
+    }
+    Some(evaluate(name, args))
+}
+
+fn evaluate(name: &str, args: &[MontyObject]) -> Result<MontyObject, Failure> {
+    let float = MontyObject::Float;
+    match (name, args) {
+        ("fsum", [xs]) => Ok(float(fsum(&numbers(name, xs)?))),
+        ("mean", [xs]) => mean(&numbers(name, xs)?).map(float),
+        ("stdev", [xs]) => stdev(&numbers(name, xs)?).map(float),
+        ("dot", [xs, ys]) => dot(&numbers(name, xs)?, &numbers(name, ys)?).map(float),
+        ("percentile", [xs, p]) => percentile(&numbers(name, xs)?, number(name, p)?).map(float),
+        ("levenshtein", [a, b]) => {
+            let (a, b) = char_pair(name, a, b)?;
+            Ok(MontyObject::Int(levenshtein(&a, &b) as i64))
+        }
+        ("jaro_winkler", [a, b]) => {
+            let (a, b) = char_pair(name, a, b)?;
+            Ok(float(jaro_winkler(&a, &b)))
+        }
+        ("similarity", [a, b]) => {
+            let (a, b) = char_pair(name, a, b)?;
+            Ok(float(similarity(&a, &b)))
+        }
+        ("json_loads", [MontyObject::String(text)]) => json::loads(text),
+        ("json_loads", [_]) => Err(Failure::Type(
+            "json_loads() expects a str argument".to_string(),
+        )),
+        ("json_dumps", [obj]) => json::dumps(obj),
+        ("b64encode", [data]) => Ok(MontyObject::String(STANDARD.encode(bytes(name, data)?))),
+        ("b64decode", [data]) => STANDARD
+            .decode(bytes(name, data)?)
+            .map(MontyObject::Bytes)
+            .map_err(|e| Failure::Value(format!("invalid base64: {e}"))),
+        ("sha256", [data]) => Ok(hex_digest(Sha256::digest(bytes(name, data)?))),
+        ("md5", [data]) => Ok(hex_digest(Md5::digest(bytes(name, data)?))),
+        ("hmac_sha256", [key, data]) => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(&bytes(name, key)?)
+                .map_err(|e| Failure::Value(e.to_string()))?;
+            mac.update(&bytes(name, data)?);
+            Ok(hex_digest(mac.finalize().into_bytes()))
+        }
+        _ => {
+            let expected = if matches!(
+                name,
+                "dot"
+                    | "percentile"
+                    | "levenshtein"
+                    | "jaro_winkler"
+                    | "similarity"
+                    | "hmac_sha256"
+            ) {
+                2
+            } else {
+                1
+            };
+            Err(Failure::Type(format!(
+                "{name}() takes {expected} positional argument{} ({} given)",
+                if expected == 1 { "" } else { "s" },
+                args.len()
+            )))
+        }
+    }
+}
+
+/// The elements of a list or tuple of numbers as floats.
+fn numbers(name: &str, obj: &MontyObject) -> Result<Vec<f64>, Failure> {
+    match obj {
+        MontyObject::List(items) | MontyObject::Tuple(items) => {
+            items.iter().map(|item| number(name, item)).collect()
+        }
+        _ => Err(Failure::Type(format!(
+            "{name}() expects a list or tuple of numbers"
+        ))),
+    }
+}
+
+fn number(name: &str, obj: &MontyObject) -> Result<f64, Failure> {
+    match obj {
+        MontyObject::Int(i) => Ok(*i as f64),
+        MontyObject::Float(f) => Ok(*f),
+        MontyObject::Bool(b) => Ok(f64::from(u8::from(*b))),
+        _ => Err(Failure::Type(format!(
+            "{name}() expects int or float values"
+        ))),
+    }
+}
+
+fn chars(name: &str, obj: &MontyObject) -> Result<Vec<char>, Failure> {
+    match obj {
+        MontyObject::String(s) => Ok(s.chars().collect()),
+        _ => Err(Failure::Type(format!("{name}() expects str arguments"))),
+    }
+}
+
+/// A str argument as its UTF-8 encoding, or a bytes argument as is.
+fn bytes(name: &str, obj: &MontyObject) -> Result<Vec<u8>, Failure> {
+    match obj {
+        MontyObject::String(s) => Ok(s.as_bytes().to_vec()),
+        MontyObject::Bytes(b) => Ok(b.clone()),
+        _ => Err(Failure::Type(format!("{name}() expects str or bytes"))),
+    }
+}
+
+/// Lowercase hex, like `hexdigest()`.
+fn hex_digest(digest: impl AsRef<[u8]>) -> MontyObject {
+    let hex = digest.as_ref().iter().map(|b| format!("{b:02x}")).collect();
+    MontyObject::String(hex)
+}
+
+/// Compensated (Neumaier) summation, matching `math.fsum` closely for
+/// inputs without extreme cancellation.
+fn fsum(xs: &[f64]) -> f64 {
+    let mut sum = 0.0;
+    let mut compensation = 0.0;
+    for &x in xs {
+        let t = sum + x;
+        if sum.abs() >= x.abs() {
+            compensation += (sum - t) + x;
+        } else {
+            compensation += (x - t) + sum;
+        }
+        sum = t;
+    }
+    sum + compensation
+}
+
+fn mean(xs: &[f64]) -> Result<f64, Failure> {
+    if xs.is_empty() {
+        return Err(Failure::Value(
+            "mean requires at least one data point".to_string(),
+        ));
+    }
+    Ok(fsum(xs) / xs.len() as f64)
+}
+
+/// Sample standard deviation, like `statistics.stdev`.
+fn stdev(xs: &[f64]) -> Result<f64, Failure> {
+    if xs.len() < 2 {
+        return Err(Failure::Value(
+            "stdev requires at least two data points".to_string(),
+        ));
+    }
+    let mean = mean(xs)?;
+    let squares: Vec<f64> = xs.iter().map(|x| (x - mean) * (x - mean)).collect();
+    Ok((fsum(&squares) / (xs.len() - 1) as f64).sqrt())
+}
+
+fn dot(xs: &[f64], ys: &[f64]) -> Result<f64, Failure> {
+    if xs.len() != ys.len() {
+        return Err(Failure::Value(format!(
+            "dot() vectors differ in length ({} and {})",
+            xs.len(),
+            ys.len()
+        )));
+    }
+    let products: Vec<f64> = xs.iter().zip(ys).map(|(x, y)| x * y).collect();
+    Ok(fsum(&products))
+}
+
+/// The `p`th percentile (0-100) with linear interpolation between the
+/// closest ranks, like numpy's default method.
+fn percentile(xs: &[f64], p: f64) -> Result<f64, Failure> {
+    if xs.is_empty() {
+        return Err(Failure::Value(
+            "percentile requires at least one data point".to_string(),
+        ));
+    }
+    if !(0.0..=100.0).contains(&p) {
+        return Err(Failure::Value(
+            "percentile must be between 0 and 100".to_string(),
+        ));
+    }
+    let mut sorted = xs.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    Ok(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
+/// Both arguments of a string-similarity intrinsic as characters,
+/// refusing pairs over `MAX_SIMILARITY_CELLS`.
+fn char_pair(
+    name: &str,
+    a: &MontyObject,
+    b: &MontyObject,
+) -> Result<(Vec<char>, Vec<char>), Failure> {
+    let (a, b) = (chars(name, a)?, chars(name, b)?);
+    if a.len().saturating_mul(b.len()) > MAX_SIMILARITY_CELLS {
+        return Err(Failure::Value(format!(
+            "{name}() inputs too long: {} x {} characters exceeds {MAX_SIMILARITY_CELLS}",
+            a.len(),
+            b.len()
+        )));
+    }
+    Ok((a, b))
+}
+
+/// Minimum number of single-character insertions, deletions and
+/// substitutions turning `a` into `b`.
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Jaro similarity boosted for a common prefix of up to four characters,
+/// with the usual scaling factor of 0.1. 1.0 means identical.
+fn jaro_winkler(a: &[char], b: &[char]) -> f64 {
+    let jaro = jaro(a, b);
+    let prefix = a.iter().zip(b).take(4).take_while(|(x, y)| x == y).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+fn jaro(a: &[char], b: &[char]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut b_matched = vec![false; b.len()];
+    let mut a_matches = Vec::new();
+    for (i, ca) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        if start >= end {
+            continue;
+        }
+        let candidates = b_matched[start..end].iter_mut().zip(&b[start..end]);
+        for (matched, cb) in candidates {
+            if !*matched && cb == ca {
+                *matched = true;
+                a_matches.push(*ca);
+                break;
+            }
+        }
+    }
+    if a_matches.is_empty() {
+        return 0.0;
+    }
+    let b_matches = b
+        .iter()
+        .zip(&b_matched)
+        .filter(|(_, m)| **m)
+        .map(|(c, _)| c);
+    let transpositions = a_matches
+        .iter()
+        .zip(b_matches)
+        .filter(|(x, y)| x != y)
+        .count()
+        / 2;
+    let m = a_matches.len() as f64;
+    (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0
+}
+
+/// Levenshtein distance normalized to a 0.0-1.0 ratio, where 1.0 means
+/// identical.
+fn similarity(a: &[char], b: &[char]) -> f64 {
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
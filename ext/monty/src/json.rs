@@ -0,0 +1,221 @@
+use magnus::{function, Error, Object, Ruby};
+use monty_lang::MontyObject;
+use num_bigint::BigInt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::intrinsics::Failure;
+use crate::repr::py_repr;
+
+/// Default for `Monty.max_json_bytes`.
+const DEFAULT_MAX_JSON_BYTES: usize = 8 * 1024 * 1024;
+/// Default for `Monty.max_json_depth`.
+const DEFAULT_MAX_JSON_DEPTH: usize = 64;
+
+static MAX_JSON_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_JSON_BYTES);
+static MAX_JSON_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_JSON_DEPTH);
+
+/// Largest text `json_loads` accepts or `json_dumps` produces, in bytes.
+fn max_json_bytes() -> usize {
+    MAX_JSON_BYTES.load(Ordering::Relaxed)
+}
+
+fn set_max_json_bytes(bytes: usize) -> usize {
+    MAX_JSON_BYTES.store(bytes, Ordering::Relaxed);
+    bytes
+}
+
+/// Deepest nesting of arrays and objects `json_loads` and `json_dumps`
+/// accept.
+fn max_json_depth() -> usize {
+    MAX_JSON_DEPTH.load(Ordering::Relaxed)
+}
+
+fn set_max_json_depth(depth: usize) -> usize {
+    MAX_JSON_DEPTH.store(depth, Ordering::Relaxed);
+    depth
+}
+
+/// Parse JSON text like `json.loads`, keeping object key order.
+pub fn loads(text: &str) -> Result<MontyObject, Failure> {
+    if text.len() > max_json_bytes() {
+        return Err(Failure::Value(format!(
+            "JSON text of {} bytes exceeds Monty.max_json_bytes ({})",
+            text.len(),
+            max_json_bytes()
+        )));
+    }
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| Failure::Value(format!("invalid JSON: {e}")))?;
+    from_json(value, 0)
+}
+
+/// Serialize like `json.dumps` with its default separators and
+/// `ensure_ascii=True`.
+pub fn dumps(obj: &MontyObject) -> Result<MontyObject, Failure> {
+    let mut out = String::new();
+    write_json(obj, 0, &mut out)?;
+    if out.len() > max_json_bytes() {
+        return Err(Failure::Value(format!(
+            "JSON text of {} bytes exceeds Monty.max_json_bytes ({})",
+            out.len(),
+            max_json_bytes()
+        )));
+    }
+    Ok(MontyObject::String(out))
+}
+
+fn too_deep() -> Failure {
+    Failure::Value(format!(
+        "JSON nesting exceeds Monty.max_json_depth ({})",
+        max_json_depth()
+    ))
+}
+
+fn from_json(value: serde_json::Value, depth: usize) -> Result<MontyObject, Failure> {
+    Ok(match value {
+        serde_json::Value::Null => MontyObject::None,
+        serde_json::Value::Bool(b) => MontyObject::Bool(b),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                MontyObject::Int(i)
+            } else if let Some(u) = n.as_u64() {
+                MontyObject::BigInt(BigInt::from(u))
+            } else {
+                MontyObject::Float(n.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        serde_json::Value::String(s) => MontyObject::String(s),
+        serde_json::Value::Array(items) => {
+            if depth >= max_json_depth() {
+                return Err(too_deep());
+            }
+            let items = items
+                .into_iter()
+                .map(|item| from_json(item, depth + 1))
+                .collect::<Result<_, _>>()?;
+            MontyObject::List(items)
+        }
+        serde_json::Value::Object(map) => {
+            if depth >= max_json_depth() {
+                return Err(too_deep());
+            }
+            let mut pairs = Vec::with_capacity(map.len());
+            for (key, value) in map {
+                pairs.push((MontyObject::String(key), from_json(value, depth + 1)?));
+            }
+            MontyObject::dict(pairs)
+        }
+    })
+}
+
+fn write_json(obj: &MontyObject, depth: usize, out: &mut String) -> Result<(), Failure> {
+    match obj {
+        MontyObject::None => out.push_str("null"),
+        MontyObject::Bool(true) => out.push_str("true"),
+        MontyObject::Bool(false) => out.push_str("false"),
+        MontyObject::Int(i) => out.push_str(&i.to_string()),
+        MontyObject::BigInt(bi) => out.push_str(&bi.to_string()),
+        MontyObject::Float(f) if f.is_finite() => out.push_str(&py_repr(obj)),
+        MontyObject::Float(_) => {
+            return Err(Failure::Value(
+                "Out of range float values are not JSON compliant".to_string(),
+            ))
+        }
+        MontyObject::String(s) => write_json_str(s, out),
+        MontyObject::List(items) | MontyObject::Tuple(items) => {
+            if depth >= max_json_depth() {
+                return Err(too_deep());
+            }
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_json(item, depth + 1, out)?;
+            }
+            out.push(']');
+        }
+        MontyObject::Dict(pairs) => {
+            if depth >= max_json_depth() {
+                return Err(too_deep());
+            }
+            out.push('{');
+            for (i, (key, value)) in pairs.into_iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                match key {
+                    MontyObject::String(s) => write_json_str(s, out),
+                    MontyObject::Int(_)
+                    | MontyObject::BigInt(_)
+                    | MontyObject::Float(_)
+                    | MontyObject::Bool(_)
+                    | MontyObject::None => {
+                        let mut text = String::new();
+                        write_json(key, depth + 1, &mut text)?;
+                        write_json_str(&text, out);
+                    }
+                    _ => {
+                        return Err(Failure::Type(
+                            "keys must be str, int, float, bool or None".to_string(),
+                        ))
+                    }
+                }
+                out.push_str(": ");
+                write_json(value, depth + 1, out)?;
+            }
+            out.push('}');
+        }
+        other => {
+            return Err(Failure::Type(format!(
+                "Object of type {} is not JSON serializable",
+                type_name(other)
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn type_name(obj: &MontyObject) -> &'static str {
+    match obj {
+        MontyObject::Bytes(_) => "bytes",
+        MontyObject::Set(_) => "set",
+        MontyObject::FrozenSet(_) => "frozenset",
+        MontyObject::NamedTuple { .. } => "namedtuple",
+        MontyObject::Dataclass { .. } => "dataclass",
+        MontyObject::Path(_) => "PosixPath",
+        _ => "object",
+    }
+}
+
+fn write_json_str(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            ' '..='~' => out.push(c),
+            c => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{unit:04x}"));
+                }
+            }
+        }
+    }
+    out.push('"');
+}
+
+pub fn define_json_settings(_ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    module.define_singleton_method("max_json_bytes", function!(max_json_bytes, 0))?;
+    module.define_singleton_method("max_json_bytes=", function!(set_max_json_bytes, 1))?;
+    module.define_singleton_method("max_json_depth", function!(max_json_depth, 0))?;
+    module.define_singleton_method("max_json_depth=", function!(set_max_json_depth, 1))?;
+
+    Ok(())
+}
@@ -1,20 +1,59 @@
 use magnus::{Error, Ruby};
 
+#[cfg(feature = "arrow")]
+mod arrow_export;
+mod batch;
+mod callback_print;
+mod control;
+mod csv_input;
+mod diagnostics;
+mod diff;
 #[allow(dead_code)]
 mod errors;
+mod fuzz;
+mod gvl;
+mod intrinsics;
+mod json;
+mod mailbox;
 mod monty_object;
 mod monty_run;
+mod object_handle;
+mod output_reader;
+mod persistence;
+mod pins;
+mod policy;
+mod repr;
 mod resource_limits;
 mod run_progress;
+mod shared_data;
+mod source_analysis;
+mod stop_tracker;
+mod type_check;
 
 #[magnus::init]
 fn init(ruby: &Ruby) -> Result<(), Error> {
+    // Must precede any method definition. Ractor-shareable Runs rely on
+    // the methods being callable outside the main Ractor.
+    unsafe { rb_sys::rb_ext_ractor_safe(true) };
+
     let module = ruby.define_module("Monty")?;
 
     errors::define_exceptions(ruby, &module)?;
+    pins::define_pins_class(ruby, &module)?;
     resource_limits::define_resource_limits_class(ruby, &module)?;
     monty_run::define_run_class(ruby, &module)?;
     run_progress::define_progress_classes(ruby, &module)?;
+    object_handle::define_object_class(ruby, &module)?;
+    output_reader::define_output_reader_class(ruby, &module)?;
+    shared_data::define_shared_data_class(ruby, &module)?;
+    diagnostics::define_diagnostics(ruby, &module)?;
+    diff::define_diff(ruby, &module)?;
+    monty_object::define_conversion_settings(ruby, &module)?;
+    repr::define_repr_settings(ruby, &module)?;
+    json::define_json_settings(ruby, &module)?;
+    csv_input::define_csv_input(ruby, &module)?;
+    control::define_control(ruby, &module)?;
+    policy::define_policy(ruby, &module)?;
 
     Ok(())
 }
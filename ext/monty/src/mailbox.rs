@@ -0,0 +1,67 @@
+use magnus::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use crate::gvl::without_gvl;
+
+/// How often a waiting `deliver` checks whether it has been interrupted.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Channel carrying results from native threads back to the Ruby thread
+/// that started them.
+///
+/// Native threads must never touch Ruby: `Ruby::get()` panics off a Ruby
+/// thread, and the helpers in `errors` build exception objects through
+/// it. Workers send plain Rust values instead (a `MontyException`, a
+/// `MontyObject`, a request to invoke a callback) and the Ruby thread
+/// turns them into Ruby values, exceptions or block calls in `deliver`.
+pub struct Mailbox<T> {
+    sender: Sender<T>,
+    receiver: mpsc::Receiver<T>,
+}
+
+impl<T: Send> Default for Mailbox<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send> Mailbox<T> {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+
+    /// A sender to move into a worker thread.
+    pub fn sender(&self) -> Sender<T> {
+        self.sender.clone()
+    }
+
+    /// Wait for messages with the GVL released, handing each to `handle`
+    /// with the GVL held. Returns once every sender is dropped, or with
+    /// the first error from `handle`; later sends then fail, telling the
+    /// workers to stop.
+    ///
+    /// If the waiting thread is interrupted, the wait ends within
+    /// `POLL_INTERVAL` and the interrupt is returned as the error.
+    pub fn deliver(self, mut handle: impl FnMut(T) -> Result<(), Error>) -> Result<(), Error> {
+        let Self { sender, receiver } = self;
+        drop(sender);
+        let stop = AtomicBool::new(false);
+        let next = || {
+            while !stop.load(Ordering::Relaxed) {
+                match receiver.recv_timeout(POLL_INTERVAL) {
+                    Ok(message) => return Some(message),
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return None,
+                }
+            }
+            None
+        };
+        while let Some(message) = without_gvl(next, || stop.store(true, Ordering::Relaxed))? {
+            handle(message)?;
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,133 @@
-use magnus::value::ReprValue;
-use magnus::{Error, RArray, RHash, Ruby, TryConvert, Value};
+use magnus::rb_sys::AsRawValue;
+use magnus::typed_data::Obj;
+use magnus::value::{Opaque, ReprValue};
+use magnus::{
+    function, Class, Error, Module, Object, RArray, RClass, RHash, RString, Ruby, TryConvert, Value,
+};
 use monty_lang::MontyObject;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+use crate::errors::conversion_error;
+use crate::object_handle::ObjectHandle;
+use crate::pins::Pins;
+use crate::shared_data::SharedData;
+
+/// Default for `Monty.max_conversion_depth`.
+const DEFAULT_MAX_CONVERSION_DEPTH: usize = 512;
+
+static MAX_CONVERSION_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_CONVERSION_DEPTH);
+
+/// Monty::Tuple, the Array subclass Python tuples convert to and from.
+static TUPLE_CLASS: OnceLock<Opaque<RClass>> = OnceLock::new();
+
+fn tuple_class(ruby: &Ruby) -> RClass {
+    ruby.get_inner(*TUPLE_CLASS.get().expect("Monty::Tuple not defined"))
+}
+
+/// Monty::Bytes, the String subclass that opts an input into Python bytes.
+static BYTES_CLASS: OnceLock<Opaque<RClass>> = OnceLock::new();
+
+fn bytes_class(ruby: &Ruby) -> RClass {
+    ruby.get_inner(*BYTES_CLASS.get().expect("Monty::Bytes not defined"))
+}
+
+/// ::Set, resolved once since it is autoloaded before Ruby 3.5.
+static SET_CLASS: OnceLock<Opaque<RClass>> = OnceLock::new();
+
+fn set_class(ruby: &Ruby) -> RClass {
+    ruby.get_inner(*SET_CLASS.get().expect("Set not resolved"))
+}
+
+fn max_conversion_depth() -> usize {
+    MAX_CONVERSION_DEPTH.load(Ordering::Relaxed)
+}
+
+fn set_max_conversion_depth(depth: usize) -> usize {
+    MAX_CONVERSION_DEPTH.store(depth, Ordering::Relaxed);
+    depth
+}
+
+fn check_depth(depth: usize) -> Result<(), Error> {
+    let limit = max_conversion_depth();
+    if depth > limit {
+        return Err(conversion_error(format!(
+            "value nested deeper than max_conversion_depth ({limit})"
+        )));
+    }
+    Ok(())
+}
+
+/// Arrays and Hashes met so far in one conversion, keyed by object
+/// identity. A container is cached the second time it is seen, so plain
+/// trees pay nothing extra and shared substructures are walked at most
+/// twice. Containers still being converted are tracked to reject cycles.
+///
+/// Every container used as a key is pinned for the duration of the
+/// conversion, since GC compaction triggered by a `funcall` could
+/// otherwise move it and hand its address to a different container.
+struct ConversionMemo {
+    seen: HashMap<rb_sys::VALUE, Option<MontyObject>>,
+    in_progress: HashSet<rb_sys::VALUE>,
+    pins: Obj<Pins>,
+}
+
+impl ConversionMemo {
+    fn new() -> Self {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        Self {
+            seen: HashMap::new(),
+            in_progress: HashSet::new(),
+            pins: Pins::new(ruby),
+        }
+    }
+
+    fn convert(
+        &mut self,
+        val: Value,
+        convert: impl FnOnce(&mut Self) -> Result<MontyObject, Error>,
+    ) -> Result<MontyObject, Error> {
+        let id = val.as_raw();
+        if let Some(Some(cached)) = self.seen.get(&id) {
+            return Ok(cached.clone());
+        }
+        if !self.in_progress.insert(id) {
+            return Err(conversion_error(format!(
+                "cannot convert a recursive {}",
+                val.class().inspect()
+            )));
+        }
+        if !self.seen.contains_key(&id) {
+            self.pins.pin(val);
+        }
+
+        let result = convert(self);
+        self.in_progress.remove(&id);
+        let obj = result?;
+        match self.seen.entry(id) {
+            Entry::Occupied(mut entry) => {
+                entry.insert(Some(obj.clone()));
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(None);
+            }
+        }
+        Ok(obj)
+    }
+}
 
 /// Convert a Ruby value to a MontyObject
 pub fn ruby_to_monty(val: Value) -> Result<MontyObject, Error> {
+    ruby_to_monty_at(val, 0, &mut ConversionMemo::new())
+}
+
+fn ruby_to_monty_at(
+    val: Value,
+    depth: usize,
+    memo: &mut ConversionMemo,
+) -> Result<MontyObject, Error> {
     let ruby = Ruby::get().expect("Ruby runtime not available");
 
     if val.is_nil() {
@@ -35,8 +159,13 @@ pub fn ruby_to_monty(val: Value) -> Result<MontyObject, Error> {
         return Ok(MontyObject::Float(f));
     }
 
-    // String
-    if val.is_kind_of(ruby.class_string()) {
+    // Monty::Bytes -> bytes, any other String (binary ones included) -> str
+    if let Some(s) = RString::from_value(val) {
+        if val.is_kind_of(bytes_class(ruby)) {
+            // SAFETY: the slice is copied before any Ruby code can run.
+            let bytes = unsafe { s.as_slice() }.to_vec();
+            return Ok(MontyObject::Bytes(bytes));
+        }
         let s: String = String::try_convert(val)?;
         return Ok(MontyObject::String(s));
     }
@@ -47,22 +176,75 @@ pub fn ruby_to_monty(val: Value) -> Result<MontyObject, Error> {
         return Ok(MontyObject::String(s));
     }
 
-    // Array -> List
+    // Monty::Tuple -> Tuple, any other Array -> List
     if val.is_kind_of(ruby.class_array()) {
+        check_depth(depth + 1)?;
         let arr: RArray = RArray::try_convert(val)?;
-        let mut items = Vec::with_capacity(arr.len());
-        for i in 0..arr.len() {
-            let item: Value = arr.entry(i as isize)?;
-            items.push(ruby_to_monty(item)?);
-        }
-        return Ok(MontyObject::List(items));
+        let is_tuple = val.is_kind_of(tuple_class(ruby));
+        return memo.convert(val, |memo| {
+            let mut items = Vec::with_capacity(arr.len());
+            for i in 0..arr.len() {
+                let item: Value = arr.entry(i as isize)?;
+                items.push(ruby_to_monty_at(item, depth + 1, memo)?);
+            }
+            if is_tuple {
+                Ok(MontyObject::Tuple(items))
+            } else {
+                Ok(MontyObject::List(items))
+            }
+        });
     }
 
     // Hash -> Dict
     if val.is_kind_of(ruby.class_hash()) {
+        check_depth(depth + 1)?;
         let hash: RHash = RHash::try_convert(val)?;
-        let pairs = hash_to_pairs(hash)?;
-        return Ok(MontyObject::dict(pairs));
+        return memo.convert(val, |memo| {
+            Ok(MontyObject::dict(hash_to_pairs(hash, depth + 1, memo)?))
+        });
+    }
+
+    // Set -> set, or frozenset when the Set is frozen
+    if val.is_kind_of(set_class(ruby)) {
+        check_depth(depth + 1)?;
+        let arr: RArray = val.funcall("to_a", ())?;
+        return memo.convert(val, |memo| {
+            let mut items = Vec::with_capacity(arr.len());
+            for i in 0..arr.len() {
+                let item: Value = arr.entry(i as isize)?;
+                let item = ruby_to_monty_at(item, depth + 1, memo)?;
+                if let Some(type_name) = unhashable_type(&item) {
+                    return Err(Error::new(
+                        ruby.exception_type_error(),
+                        format!("unhashable type: '{type_name}'"),
+                    ));
+                }
+                items.push(item);
+            }
+            if val.is_frozen() {
+                Ok(MontyObject::FrozenSet(items))
+            } else {
+                Ok(MontyObject::Set(items))
+            }
+        });
+    }
+
+    // Monty::Object handle -> the wrapped value, unconverted
+    if let Ok(handle) = <&ObjectHandle>::try_convert(val) {
+        return Ok(handle.inner().clone());
+    }
+
+    // Monty::SharedData -> the preconverted value
+    if let Ok(shared) = <&SharedData>::try_convert(val) {
+        return Ok(shared.inner().clone());
+    }
+
+    // Objects defining #to_monty -> whatever it returns, converted. Each
+    // step counts as a level so a method returning itself cannot loop.
+    if val.respond_to("to_monty", false)? {
+        check_depth(depth + 1)?;
+        let converted: Value = val.funcall("to_monty", ())?;
+        return ruby_to_monty_at(converted, depth + 1, memo);
     }
 
     Err(Error::new(
@@ -76,7 +258,23 @@ pub fn ruby_to_monty(val: Value) -> Result<MontyObject, Error> {
 
 /// Convert a MontyObject to a Ruby value
 pub fn monty_to_ruby(obj: MontyObject) -> Result<Value, Error> {
+    monty_to_ruby_at(obj, 0)
+}
+
+fn monty_to_ruby_at(obj: MontyObject, depth: usize) -> Result<Value, Error> {
     let ruby = Ruby::get().expect("Ruby runtime not available");
+    if matches!(
+        obj,
+        MontyObject::List(_)
+            | MontyObject::Tuple(_)
+            | MontyObject::NamedTuple { .. }
+            | MontyObject::Dict(_)
+            | MontyObject::Set(_)
+            | MontyObject::FrozenSet(_)
+            | MontyObject::Dataclass { .. }
+    ) {
+        check_depth(depth + 1)?;
+    }
 
     match obj {
         MontyObject::None => Ok(ruby.qnil().as_value()),
@@ -103,15 +301,15 @@ pub fn monty_to_ruby(obj: MontyObject) -> Result<Value, Error> {
         MontyObject::List(items) => {
             let arr = ruby.ary_new_capa(items.len());
             for item in items {
-                let val = monty_to_ruby(item)?;
+                let val = monty_to_ruby_at(item, depth + 1)?;
                 arr.push(val)?;
             }
             Ok(arr.as_value())
         }
         MontyObject::Tuple(items) => {
-            let arr = ruby.ary_new_capa(items.len());
+            let arr = RArray::try_convert(tuple_class(ruby).new_instance(())?)?;
             for item in items {
-                let val = monty_to_ruby(item)?;
+                let val = monty_to_ruby_at(item, depth + 1)?;
                 arr.push(val)?;
             }
             arr.funcall::<_, _, Value>("freeze", ())?;
@@ -125,7 +323,7 @@ pub fn monty_to_ruby(obj: MontyObject) -> Result<Value, Error> {
             let hash = ruby.hash_new();
             for (name, value) in field_names.into_iter().zip(values.into_iter()) {
                 let key = ruby.str_new(&name);
-                let val = monty_to_ruby(value)?;
+                let val = monty_to_ruby_at(value, depth + 1)?;
                 hash.aset(key, val)?;
             }
             Ok(hash.as_value())
@@ -133,25 +331,19 @@ pub fn monty_to_ruby(obj: MontyObject) -> Result<Value, Error> {
         MontyObject::Dict(pairs) => {
             let hash = ruby.hash_new();
             for (k, v) in pairs.into_iter() {
-                let key = monty_to_ruby(k)?;
-                let val = monty_to_ruby(v)?;
+                let key = monty_to_ruby_at(k, depth + 1)?;
+                let val = monty_to_ruby_at(v, depth + 1)?;
                 hash.aset(key, val)?;
             }
             Ok(hash.as_value())
         }
-        MontyObject::Set(items) | MontyObject::FrozenSet(items) => {
-            let arr = ruby.ary_new_capa(items.len());
-            for item in items {
-                let val = monty_to_ruby(item)?;
-                arr.push(val)?;
-            }
-            Ok(arr.as_value())
-        }
+        MontyObject::Set(items) => set_to_ruby(items, depth, false),
+        MontyObject::FrozenSet(items) => set_to_ruby(items, depth, true),
         MontyObject::Dataclass { attrs, .. } => {
             let hash = ruby.hash_new();
             for (k, v) in attrs.into_iter() {
-                let key = monty_to_ruby(k)?;
-                let val = monty_to_ruby(v)?;
+                let key = monty_to_ruby_at(k, depth + 1)?;
+                let val = monty_to_ruby_at(v, depth + 1)?;
                 hash.aset(key, val)?;
             }
             Ok(hash.as_value())
@@ -178,12 +370,351 @@ pub fn monty_to_ruby(obj: MontyObject) -> Result<Value, Error> {
     }
 }
 
+fn set_to_ruby(items: Vec<MontyObject>, depth: usize, frozen: bool) -> Result<Value, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let arr = ruby.ary_new_capa(items.len());
+    for item in items {
+        let val = monty_to_ruby_at(item, depth + 1)?;
+        arr.push(val)?;
+    }
+    let set = set_class(ruby).new_instance((arr,))?;
+    if frozen {
+        set.funcall::<_, _, Value>("freeze", ())?;
+    }
+    Ok(set)
+}
+
+/// Convert a list of dicts into `{column_name => Array}` in a single pass.
+///
+/// Column names are frozen Ruby Strings created once per column. Rows that
+/// lack a column get nil in that position.
+pub fn rows_to_columns(obj: MontyObject) -> Result<RHash, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let MontyObject::List(rows) = obj else {
+        return Err(crate::errors::monty_error(
+            "to_columns requires a list of dicts".to_string(),
+        ));
+    };
+
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut columns: Vec<RArray> = Vec::new();
+    let result = ruby.hash_new();
+
+    for (row_number, row) in rows.into_iter().enumerate() {
+        let MontyObject::Dict(pairs) = row else {
+            return Err(crate::errors::monty_error(format!(
+                "to_columns requires a list of dicts (row {row_number} is not a dict)"
+            )));
+        };
+
+        for (key, value) in pairs {
+            let MontyObject::String(name) = key else {
+                return Err(crate::errors::monty_error(format!(
+                    "to_columns requires string keys (row {row_number})"
+                )));
+            };
+            let column = match index.get(&name) {
+                Some(&i) => columns[i],
+                None => {
+                    let column = ruby.ary_new_capa(row_number + 1);
+                    for _ in 0..row_number {
+                        column.push(ruby.qnil())?;
+                    }
+                    let key = ruby.str_new(&name);
+                    key.freeze();
+                    result.aset(key, column)?;
+                    index.insert(name, columns.len());
+                    columns.push(column);
+                    column
+                }
+            };
+            column.push(monty_to_ruby(value)?)?;
+        }
+
+        for column in &columns {
+            if column.len() <= row_number {
+                column.push(ruby.qnil())?;
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Caps on a result checked before it is converted to Ruby objects.
+#[derive(Clone, Copy, Default)]
+pub struct ResultLimits {
+    pub max_bytes: Option<usize>,
+    pub max_items: Option<usize>,
+    /// Raise OverflowError instead of returning ints beyond 64 bits
+    /// (`result_integer_overflow: :raise`).
+    pub bounded_ints: bool,
+}
+
+impl ResultLimits {
+    /// Walk the result and raise Monty::ResourceError as soon as it exceeds
+    /// either cap, before any Ruby objects are allocated for it. An int
+    /// beyond 64 bits under `bounded_ints` raises Monty::OverflowError.
+    pub fn check(&self, obj: &MontyObject) -> Result<(), Error> {
+        if self.bounded_ints && contains_big_int(obj) {
+            return Err(crate::errors::python_error(
+                "OverflowError",
+                "OverflowError: int result exceeds 64 bits".to_string(),
+            ));
+        }
+        if self.max_bytes.is_none() && self.max_items.is_none() {
+            return Ok(());
+        }
+
+        let mut usage = ResultUsage::default();
+        if self.measure(obj, &mut usage) {
+            Ok(())
+        } else if self.max_items.is_some_and(|limit| usage.items > limit) {
+            Err(crate::errors::resource_error(format!(
+                "result too large: more than {} items",
+                self.max_items.unwrap_or_default()
+            )))
+        } else {
+            Err(crate::errors::resource_error(format!(
+                "result too large: more than {} bytes",
+                self.max_bytes.unwrap_or_default()
+            )))
+        }
+    }
+
+    /// Returns false once a cap is exceeded so the walk can stop early.
+    fn measure(&self, obj: &MontyObject, usage: &mut ResultUsage) -> bool {
+        usage.items += 1;
+        usage.bytes += match obj {
+            MontyObject::String(s) | MontyObject::Path(s) | MontyObject::Repr(s) => s.len(),
+            MontyObject::Bytes(b) => b.len(),
+            _ => 8,
+        };
+        if self.max_items.is_some_and(|limit| usage.items > limit)
+            || self.max_bytes.is_some_and(|limit| usage.bytes > limit)
+        {
+            return false;
+        }
+
+        match obj {
+            MontyObject::List(items)
+            | MontyObject::Tuple(items)
+            | MontyObject::Set(items)
+            | MontyObject::FrozenSet(items)
+            | MontyObject::NamedTuple { values: items, .. } => {
+                items.iter().all(|item| self.measure(item, usage))
+            }
+            MontyObject::Dict(pairs) | MontyObject::Dataclass { attrs: pairs, .. } => {
+                for (k, v) in pairs {
+                    if !self.measure(k, usage) || !self.measure(v, usage) {
+                        return false;
+                    }
+                }
+                true
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Whether `obj` measures at most `max_bytes`, counted the same way as
+/// `max_result_bytes`.
+pub fn fits_in_bytes(obj: &MontyObject, max_bytes: usize) -> bool {
+    let limits = ResultLimits {
+        max_bytes: Some(max_bytes),
+        ..ResultLimits::default()
+    };
+    limits.measure(obj, &mut ResultUsage::default())
+}
+
+/// The Python type that makes `obj` unusable as a set element or dict
+/// key, as named in Python's "unhashable type" error; None if hashable.
+fn unhashable_type(obj: &MontyObject) -> Option<&'static str> {
+    match obj {
+        MontyObject::List(_) => Some("list"),
+        MontyObject::Dict(_) => Some("dict"),
+        MontyObject::Set(_) => Some("set"),
+        MontyObject::Tuple(items)
+        | MontyObject::FrozenSet(items)
+        | MontyObject::NamedTuple { values: items, .. } => items.iter().find_map(unhashable_type),
+        _ => None,
+    }
+}
+
+fn contains_big_int(obj: &MontyObject) -> bool {
+    match obj {
+        MontyObject::BigInt(_) => true,
+        MontyObject::List(items)
+        | MontyObject::Tuple(items)
+        | MontyObject::Set(items)
+        | MontyObject::FrozenSet(items)
+        | MontyObject::NamedTuple { values: items, .. } => items.iter().any(contains_big_int),
+        MontyObject::Dict(pairs) | MontyObject::Dataclass { attrs: pairs, .. } => {
+            for (k, v) in pairs {
+                if contains_big_int(k) || contains_big_int(v) {
+                    return true;
+                }
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct ResultUsage {
+    items: usize,
+    bytes: usize,
+}
+
+/// Elements of a Ruby value that would fail to convert, found by walking
+/// Arrays, Hashes and Sets and trying each leaf with `ruby_to_monty`, so
+/// the verdict always matches a real conversion.
+///
+/// Like `ConversionMemo`, containers being walked are pinned while their
+/// addresses are used to detect cycles.
+struct FailureWalk {
+    in_progress: HashSet<rb_sys::VALUE>,
+    pins: Obj<Pins>,
+    /// Keys and indices leading from the root to the current element.
+    path: RArray,
+    failures: RArray,
+    first_only: bool,
+}
+
+impl FailureWalk {
+    fn new(first_only: bool) -> Self {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        Self {
+            in_progress: HashSet::new(),
+            pins: Pins::new(ruby),
+            path: ruby.ary_new(),
+            failures: ruby.ary_new(),
+            first_only,
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.first_only && !self.failures.is_empty()
+    }
+
+    fn record(&self, val: Value, err: Error) -> Result<(), Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let exception = err.value().ok_or(err)?;
+        let failure = ruby.hash_new();
+        failure.aset(ruby.to_symbol("path"), self.path.dup())?;
+        failure.aset(ruby.to_symbol("class"), val.class())?;
+        failure.aset(
+            ruby.to_symbol("message"),
+            exception.funcall::<_, _, Value>("message", ())?,
+        )?;
+        self.failures.push(failure)
+    }
+
+    fn walk(&mut self, val: Value, depth: usize) -> Result<(), Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let is_container = val.is_kind_of(ruby.class_array())
+            || val.is_kind_of(ruby.class_hash())
+            || val.is_kind_of(set_class(ruby));
+        if !is_container {
+            return match ruby_to_monty(val) {
+                Ok(_) => Ok(()),
+                Err(err) => self.record(val, err),
+            };
+        }
+        if let Err(err) = check_depth(depth + 1) {
+            return self.record(val, err);
+        }
+        let id = val.as_raw();
+        if !self.in_progress.insert(id) {
+            let message = format!("cannot convert a recursive {}", val.class().inspect());
+            return self.record(val, conversion_error(message));
+        }
+        self.pins.pin(val);
+
+        let result = self.walk_items(val, depth + 1);
+        self.in_progress.remove(&id);
+        result
+    }
+
+    fn walk_items(&mut self, val: Value, depth: usize) -> Result<(), Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        if let Some(hash) = RHash::from_value(val) {
+            let keys: RArray = hash.funcall("keys", ())?;
+            for i in 0..keys.len() {
+                let key: Value = keys.entry(i as isize)?;
+                self.path.push(key)?;
+                if let Err(err) = ruby_to_monty(key) {
+                    self.record(key, err)?;
+                } else {
+                    self.walk(hash.aref(key)?, depth)?;
+                }
+                self.path.pop::<Value>()?;
+                if self.done() {
+                    break;
+                }
+            }
+            return Ok(());
+        }
+
+        let items: RArray = if val.is_kind_of(set_class(ruby)) {
+            val.funcall("to_a", ())?
+        } else {
+            RArray::try_convert(val)?
+        };
+        for i in 0..items.len() {
+            self.path.push(i)?;
+            self.walk(items.entry(i as isize)?, depth)?;
+            self.path.pop::<Value>()?;
+            if self.done() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `Monty.conversion_report`: every element of `val` that would fail to
+/// convert, as Hashes with :path (the keys and indices leading to it; Set
+/// elements are indexed in iteration order), :class and :message keys.
+fn conversion_report(val: Value) -> Result<RArray, Error> {
+    let mut walk = FailureWalk::new(false);
+    walk.walk(val, 0)?;
+    Ok(walk.failures)
+}
+
+/// `Monty.convertible?`: whether `val` would convert, stopping at the
+/// first failure.
+fn is_convertible(val: Value) -> Result<bool, Error> {
+    let mut walk = FailureWalk::new(true);
+    walk.walk(val, 0)?;
+    Ok(walk.failures.is_empty())
+}
+
+/// `Monty._dump_value`: convert `val` and serialize it in the postcard
+/// encoding FunctionCall#dump uses for arguments, so values can cross a
+/// process boundary without Marshal.
+fn dump_value(val: Value) -> Result<Vec<u8>, Error> {
+    postcard::to_allocvec(&ruby_to_monty(val)?)
+        .map_err(|e| crate::errors::monty_error(format!("failed to dump value: {e}")))
+}
+
+/// `Monty._load_value`: the inverse of `Monty._dump_value`.
+fn load_value(bytes: Vec<u8>) -> Result<Value, Error> {
+    let obj: MontyObject = postcard::from_bytes(&bytes)
+        .map_err(|e| crate::errors::monty_error(format!("failed to load value: {e}")))?;
+    monty_to_ruby(obj)
+}
+
 /// Convert a Ruby Array of values to Vec<MontyObject>
+///
+/// Repeated values are cached across all the inputs of the call.
 pub fn ruby_array_to_monty_vec(arr: RArray) -> Result<Vec<MontyObject>, Error> {
+    let mut memo = ConversionMemo::new();
     let mut result = Vec::with_capacity(arr.len());
     for i in 0..arr.len() {
         let item: Value = arr.entry(i as isize)?;
-        result.push(ruby_to_monty(item)?);
+        result.push(ruby_to_monty_at(item, 0, &mut memo)?);
     }
     Ok(result)
 }
@@ -199,13 +730,42 @@ fn detect_bool(val: Value) -> Option<bool> {
     }
 }
 
-fn hash_to_pairs(hash: RHash) -> Result<Vec<(MontyObject, MontyObject)>, Error> {
+fn hash_to_pairs(
+    hash: RHash,
+    depth: usize,
+    memo: &mut ConversionMemo,
+) -> Result<Vec<(MontyObject, MontyObject)>, Error> {
     let keys: RArray = hash.funcall("keys", ())?;
     let mut pairs = Vec::with_capacity(keys.len());
     for i in 0..keys.len() {
         let key: Value = keys.entry(i as isize)?;
         let val: Value = hash.aref(key)?;
-        pairs.push((ruby_to_monty(key)?, ruby_to_monty(val)?));
+        pairs.push((
+            ruby_to_monty_at(key, depth, memo)?,
+            ruby_to_monty_at(val, depth, memo)?,
+        ));
     }
     Ok(pairs)
 }
+
+pub fn define_conversion_settings(ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    let tuple = module.define_class("Tuple", ruby.class_array())?;
+    let _ = TUPLE_CLASS.set(Opaque::from(tuple));
+    let bytes = module.define_class("Bytes", ruby.class_string())?;
+    let _ = BYTES_CLASS.set(Opaque::from(bytes));
+    let set: RClass = ruby.class_object().const_get("Set")?;
+    let _ = SET_CLASS.set(Opaque::from(set));
+
+    module.define_singleton_method("conversion_report", function!(conversion_report, 1))?;
+    module.define_singleton_method("convertible?", function!(is_convertible, 1))?;
+    module.define_singleton_method("_dump_value", function!(dump_value, 1))?;
+    module.define_singleton_method("_load_value", function!(load_value, 1))?;
+
+    module.define_singleton_method("max_conversion_depth", function!(max_conversion_depth, 0))?;
+    module.define_singleton_method(
+        "max_conversion_depth=",
+        function!(set_max_conversion_depth, 1),
+    )?;
+
+    Ok(())
+}
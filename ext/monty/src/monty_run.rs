@@ -1,21 +1,82 @@
+use magnus::r_hash::ForEach;
 use magnus::value::ReprValue;
-use magnus::{function, method, Error, Module, Object, RArray, RHash, Ruby, Value};
-use monty_lang::{CollectStringPrint, LimitedTracker, MontyRun, NoLimitTracker, StdPrint};
-use std::cell::RefCell;
-
-use crate::errors::{consumed_error, map_monty_exception};
+use magnus::{function, method, Error, Module, Object, RArray, RHash, Ruby, TryConvert, Value};
+use monty_lang::{
+    CollectStringPrint, LimitedTracker, MontyObject, MontyRun, NoLimitTracker, StdPrint,
+};
+use std::collections::hash_map::DefaultHasher;
+use sha2::{Digest, Sha256};
+use std::hash::{Hash, Hasher};
+use std::sync::{OnceLock, PoisonError, RwLock, RwLockReadGuard, TryLockError};
+use std::time::{Duration, Instant};
+
+use crate::batch::{run_batch, BatchJob, Completed};
+use crate::callback_print::CallbackPrint;
+use crate::control::{check_accepting, check_not_stopped, current_epoch, register_run};
+use crate::diagnostics::warning_hash;
+use crate::errors::{consumed_error, map_monty_exception, monty_error, python_error};
+use crate::fuzz::{parse_generators, Rng};
+use crate::intrinsics;
 use crate::monty_object::{monty_to_ruby, ruby_array_to_monty_vec};
-use crate::resource_limits::parse_limits_hash;
-use crate::run_progress::Progress;
+use crate::persistence::{unwrap_checking_expiry, wrap_with_expiry};
+use crate::resource_limits::{
+    parse_limits_hash, parse_max_duration, parse_max_pending_futures, parse_max_tool_result_bytes,
+    parse_result_limits,
+};
+use crate::run_progress::{drive, Progress, ProgressContext, Timeline};
+use crate::source_analysis::{
+    call_graph_to_ruby, capabilities_to_ruby, defined_names_to_ruby, imported_modules,
+    unused_declarations,
+};
+use crate::stop_tracker::run_stoppable;
+use crate::type_check::type_issues_to_ruby;
 
 /// Ruby wrapper for monty::MontyRun
 ///
-/// Uses RefCell<Option<>> to support the consuming `start` method.
-/// After `start` is called, the inner MontyRun is taken and the
-/// Run object can no longer be used for `run` or `start`.
-#[magnus::wrap(class = "Monty::Run", free_immediately, size)]
+/// The `run*` methods release the GVL while the interpreter executes, so
+/// other Ruby threads keep running during long scripts. An interrupt
+/// (Thread#raise, Timeout, Ctrl-C) stops the script at the interpreter's
+/// next time check; see `stop_tracker`.
+///
+/// Uses RwLock<Option<>> so one Run can be shared across threads and, once
+/// frozen, across Ractors, and to support the consuming `start` method. After `start` is called, the
+/// inner MontyRun is taken and the Run object can no longer be used for
+/// `run` or `start`.
+#[magnus::wrap(class = "Monty::Run", free_immediately, size, frozen_shareable)]
 pub struct Run {
-    inner: RefCell<Option<MontyRun>>,
+    inner: RwLock<Option<MontyRun>>,
+    /// Reported by `Monty.active_runs`; not known for loaded Runs.
+    script_name: Option<String>,
+    /// Used by `call_graph`; not known for loaded Runs.
+    external_functions: Vec<String>,
+    /// Used by `named_inputs`; not known for loaded Runs.
+    input_names: Option<Vec<String>>,
+    /// Unused input and external function declarations found at compile time.
+    warnings: Vec<String>,
+    /// Time spent compiling; not known for loaded Runs.
+    compile_stats: Option<CompileStats>,
+    /// Whether calls to `intrinsics::NAMES` are answered in Rust; not kept
+    /// by `dump`.
+    intrinsics: bool,
+    /// Digest of the compiled program; see `Run::fingerprint`.
+    fingerprint: OnceLock<[u8; 32]>,
+}
+
+/// SHA-256 of a compiled program: its serialized form, which covers the
+/// bytecode, input names and external functions, or the source when it
+/// cannot be serialized.
+fn program_fingerprint(run: &MontyRun) -> [u8; 32] {
+    match run.dump() {
+        Ok(bytes) => Sha256::digest(bytes).into(),
+        Err(_) => Sha256::digest(run.code()).into(),
+    }
+}
+
+/// How long `Run::new` spent in the bindings' static checks and in the
+/// interpreter's parser and compiler.
+struct CompileStats {
+    analysis: Duration,
+    compile: Duration,
 }
 
 impl Run {
@@ -26,11 +87,15 @@ impl Run {
     ///   script_name - filename for error messages (default: "script.py")
     ///   inputs     - Array of input variable names (default: [])
     ///   external_functions - Array of external function names (default: [])
+    ///   disabled_modules - Array of module names the script may not import (default: [])
+    ///   intrinsics - declare the Rust-backed numeric functions (default: false)
     fn new(
         code: String,
         script_name: Option<String>,
         inputs: Option<RArray>,
         external_functions: Option<RArray>,
+        disabled_modules: Option<RArray>,
+        intrinsics: bool,
     ) -> Result<Self, Error> {
         let script_name = script_name.unwrap_or_else(|| "script.py".to_string());
 
@@ -58,31 +123,256 @@ impl Run {
             None => Vec::new(),
         };
 
-        let monty_run = MontyRun::new(code, &script_name, input_names, ext_fns)
+        let analysis_started = Instant::now();
+        if let Some(arr) = disabled_modules {
+            let disabled: Vec<String> = arr.to_vec()?;
+            check_disabled_modules(&code, &disabled)?;
+        }
+
+        let warnings = unused_declarations(&code, &input_names, &ext_fns);
+        let declared_inputs = input_names.clone();
+        let mut declared = ext_fns.clone();
+        if intrinsics {
+            for name in intrinsics::NAMES {
+                if ext_fns.iter().any(|f| f == name) {
+                    let ruby = Ruby::get().expect("Ruby runtime not available");
+                    return Err(Error::new(
+                        ruby.exception_arg_error(),
+                        format!("external function '{name}' clashes with an intrinsic"),
+                    ));
+                }
+                declared.push(name.to_string());
+            }
+        }
+        let analysis = analysis_started.elapsed();
+        let compile_started = Instant::now();
+        let monty_run = MontyRun::new(code, &script_name, input_names, declared)
             .map_err(map_monty_exception)?;
+        let compile_stats = CompileStats {
+            analysis,
+            compile: compile_started.elapsed(),
+        };
 
         Ok(Self {
-            inner: RefCell::new(Some(monty_run)),
+            fingerprint: OnceLock::new(),
+            inner: RwLock::new(Some(monty_run)),
+            script_name: Some(script_name),
+            external_functions: ext_fns,
+            input_names: Some(declared_inputs),
+            compile_stats: Some(compile_stats),
+            warnings,
+            intrinsics,
         })
     }
 
+    /// Shared access for the non-consuming methods, which any number of
+    /// threads may hold at once.
+    fn read_inner(&self) -> RwLockReadGuard<'_, Option<MontyRun>> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Take the MontyRun for a consuming method. Fails rather than waits
+    /// while another thread is executing this Run: waiting with the GVL
+    /// held would stop that thread from ever finishing.
+    fn take_inner(&self) -> Result<MontyRun, Error> {
+        let mut inner = match self.inner.try_write() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                return Err(monty_error(
+                    "this Run is executing on another thread and cannot be consumed".to_string(),
+                ))
+            }
+        };
+        inner.take().ok_or_else(consumed_error)
+    }
+
+    /// Digest of the compiled program, computed the first time a Run is
+    /// compared or hashed, so Runs that are never compared do not
+    /// serialize it. None for a Run consumed before it was ever compared.
+    fn fingerprint(&self) -> Option<[u8; 32]> {
+        if let Some(digest) = self.fingerprint.get() {
+            return Some(*digest);
+        }
+        let inner = self.read_inner();
+        let run = inner.as_ref()?;
+        Some(*self.fingerprint.get_or_init(|| program_fingerprint(run)))
+    }
+
+    /// Runs are equal when they compiled to the same program with the same
+    /// intrinsics setting, so they can be deduplicated and used as cache
+    /// keys. A consumed Run keeps comparing by its program if it was
+    /// compared or hashed before, and otherwise is equal only to itself.
+    fn eq(&self, other: Value) -> bool {
+        let Ok(other) = <&Run>::try_convert(other) else {
+            return false;
+        };
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+        match (self.fingerprint(), other.fingerprint()) {
+            (Some(a), Some(b)) => a == b && self.intrinsics == other.intrinsics,
+            _ => false,
+        }
+    }
+
+    fn hash(&self) -> i64 {
+        let mut hasher = DefaultHasher::new();
+        match self.fingerprint() {
+            Some(digest) => (digest, self.intrinsics).hash(&mut hasher),
+            None => (self as *const Run as usize).hash(&mut hasher),
+        }
+        hasher.finish() as i64
+    }
+
     /// Get the source code
     fn code(&self) -> Result<String, Error> {
-        let inner = self.inner.borrow();
+        let inner = self.read_inner();
         let run = inner.as_ref().ok_or_else(consumed_error)?;
         Ok(run.code().to_string())
     }
 
+    /// Names defined at module level, found by static analysis.
+    /// Returns an Array of Hashes with :name, :kind and :line keys.
+    fn defined_names(&self) -> Result<RArray, Error> {
+        let inner = self.read_inner();
+        let run = inner.as_ref().ok_or_else(consumed_error)?;
+        defined_names_to_ruby(run.code())
+    }
+
+    /// Non-fatal problems found when the code was compiled, as Hashes
+    /// shaped like `Monty.diagnostics` entries with severity :warning.
+    fn warnings(&self) -> Result<RArray, Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let arr = ruby.ary_new_capa(self.warnings.len());
+        for message in &self.warnings {
+            arr.push(warning_hash(ruby, message.clone())?)?;
+        }
+        Ok(arr)
+    }
+
+    /// Time spent creating the Run, as a Hash with :analysis_duration (the
+    /// bindings' static checks) and :compile_duration (parsing and
+    /// compiling in the interpreter), in seconds. Nil for loaded Runs.
+    fn compile_stats(&self) -> Result<Option<RHash>, Error> {
+        let Some(stats) = &self.compile_stats else {
+            return Ok(None);
+        };
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let hash = ruby.hash_new();
+        hash.aset(
+            ruby.to_symbol("analysis_duration"),
+            stats.analysis.as_secs_f64(),
+        )?;
+        hash.aset(
+            ruby.to_symbol("compile_duration"),
+            stats.compile.as_secs_f64(),
+        )?;
+        Ok(Some(hash))
+    }
+
+    /// What the script could do, found by static analysis. Returns a Hash
+    /// with :modules, :builtins, :os_calls and :external_functions Arrays
+    /// and :loops and :recursion booleans.
+    fn capabilities(&self) -> Result<RHash, Error> {
+        let inner = self.read_inner();
+        let run = inner.as_ref().ok_or_else(consumed_error)?;
+        capabilities_to_ruby(run.code(), &self.external_functions)
+    }
+
+    /// Probable type errors found by comparing literals with type hints,
+    /// without running the script. Returns an Array of Hashes with
+    /// :severity, :message and :line keys.
+    fn typecheck(&self) -> Result<RArray, Error> {
+        let inner = self.read_inner();
+        let run = inner.as_ref().ok_or_else(consumed_error)?;
+        type_issues_to_ruby(run.code())
+    }
+
+    /// Which functions call which, found by static analysis.
+    /// Returns a Hash of caller name => {functions:, externals:}.
+    fn call_graph(&self) -> Result<RHash, Error> {
+        let inner = self.read_inner();
+        let run = inner.as_ref().ok_or_else(consumed_error)?;
+        call_graph_to_ruby(run.code(), &self.external_functions)
+    }
+
+    /// Order a Hash of input name => value (String or Symbol keys) as the
+    /// positional Array the run methods take, raising ArgumentError that
+    /// lists any missing or unexpected names.
+    fn named_inputs(&self, inputs: RHash) -> Result<RArray, Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let Some(names) = &self.input_names else {
+            return Err(Error::new(
+                ruby.exception_arg_error(),
+                "input names are not known for a loaded Run; pass inputs positionally",
+            ));
+        };
+
+        let mut given: Vec<(String, Value)> = Vec::with_capacity(inputs.len());
+        inputs.foreach(|key: Value, value: Value| {
+            given.push((key.funcall::<_, _, String>("to_s", ())?, value));
+            Ok(ForEach::Continue)
+        })?;
+
+        let missing: Vec<&str> = names
+            .iter()
+            .filter(|name| !given.iter().any(|(key, _)| key == *name))
+            .map(String::as_str)
+            .collect();
+        let unexpected: Vec<&str> = given
+            .iter()
+            .filter(|(key, _)| !names.contains(key))
+            .map(|(key, _)| key.as_str())
+            .collect();
+        if !missing.is_empty() || !unexpected.is_empty() {
+            let mut problems = Vec::new();
+            if !missing.is_empty() {
+                problems.push(format!("missing inputs: {}", missing.join(", ")));
+            }
+            if !unexpected.is_empty() {
+                problems.push(format!("unexpected inputs: {}", unexpected.join(", ")));
+            }
+            return Err(Error::new(ruby.exception_arg_error(), problems.join("; ")));
+        }
+
+        let values = names.iter().filter_map(|name| {
+            given
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| *value)
+        });
+        Ok(ruby.ary_from_iter(values))
+    }
+
+    /// Intrinsics are answered only while a run is driven call by call,
+    /// so the one-shot entry points refuse a Run that declares them
+    /// instead of failing on the first intrinsic call.
+    fn check_one_shot(&self) -> Result<(), Error> {
+        if !self.intrinsics {
+            return Ok(());
+        }
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        Err(Error::new(
+            ruby.exception_arg_error(),
+            "a Run with intrinsics: true must be driven with #start, #execute or #call_with_externals",
+        ))
+    }
+
     /// Execute the Python code with inputs, no resource limits.
     /// Prints to stdout directly.
     fn run(&self, inputs: RArray) -> Result<Value, Error> {
-        let inner = self.inner.borrow();
+        check_accepting()?;
+        self.check_one_shot()?;
+        let abort_epoch = current_epoch();
+        let active = register_run(self.script_name.as_deref());
+        let inner = self.read_inner();
         let run = inner.as_ref().ok_or_else(consumed_error)?;
 
         let monty_inputs = ruby_array_to_monty_vec(inputs)?;
-        let result = run
-            .run(monty_inputs, NoLimitTracker, &mut StdPrint)
-            .map_err(map_monty_exception)?;
+        let result =
+            run_stoppable(run, monty_inputs, None, &mut StdPrint, abort_epoch, &active)?
+                .map_err(map_monty_exception)?;
 
         monty_to_ruby(result)
     }
@@ -90,33 +380,48 @@ impl Run {
     /// Execute the Python code with inputs and resource limits.
     /// Prints to stdout directly.
     fn run_with_limits(&self, inputs: RArray, limits: RHash) -> Result<Value, Error> {
-        let inner = self.inner.borrow();
+        check_accepting()?;
+        self.check_one_shot()?;
+        let abort_epoch = current_epoch();
+        let active = register_run(self.script_name.as_deref());
+        let inner = self.read_inner();
         let run = inner.as_ref().ok_or_else(consumed_error)?;
 
         let monty_inputs = ruby_array_to_monty_vec(inputs)?;
         let resource_limits = parse_limits_hash(&limits)?;
-        let tracker = LimitedTracker::new(resource_limits);
-
-        let result = run
-            .run(monty_inputs, tracker, &mut StdPrint)
-            .map_err(map_monty_exception)?;
-
+        let result_limits = parse_result_limits(&limits)?;
+
+        let result = run_stoppable(
+            run,
+            monty_inputs,
+            Some(resource_limits),
+            &mut StdPrint,
+            abort_epoch,
+            &active,
+        )?
+        .map_err(map_monty_exception)?;
+
+        result_limits.check(&result)?;
         monty_to_ruby(result)
     }
 
     /// Execute the Python code and capture stdout output.
     /// Returns a Hash with :result and :output keys.
     fn run_capturing(&self, inputs: RArray) -> Result<Value, Error> {
+        check_accepting()?;
+        self.check_one_shot()?;
+        let abort_epoch = current_epoch();
+        let active = register_run(self.script_name.as_deref());
         let ruby = Ruby::get().expect("Ruby runtime not available");
-        let inner = self.inner.borrow();
+        let inner = self.read_inner();
         let run = inner.as_ref().ok_or_else(consumed_error)?;
 
         let monty_inputs = ruby_array_to_monty_vec(inputs)?;
         let mut print = CollectStringPrint::new();
 
-        let result = run
-            .run(monty_inputs, NoLimitTracker, &mut print)
-            .map_err(map_monty_exception)?;
+        let result =
+            run_stoppable(run, monty_inputs, None, &mut print, abort_epoch, &active)?
+                .map_err(map_monty_exception)?;
 
         let hash = ruby.hash_new();
         hash.aset(ruby.to_symbol("result"), monty_to_ruby(result)?)?;
@@ -130,19 +435,30 @@ impl Run {
     /// Execute the Python code with resource limits and capture stdout.
     /// Returns a Hash with :result and :output keys.
     fn run_capturing_with_limits(&self, inputs: RArray, limits: RHash) -> Result<Value, Error> {
+        check_accepting()?;
+        self.check_one_shot()?;
+        let abort_epoch = current_epoch();
+        let active = register_run(self.script_name.as_deref());
         let ruby = Ruby::get().expect("Ruby runtime not available");
-        let inner = self.inner.borrow();
+        let inner = self.read_inner();
         let run = inner.as_ref().ok_or_else(consumed_error)?;
 
         let monty_inputs = ruby_array_to_monty_vec(inputs)?;
         let resource_limits = parse_limits_hash(&limits)?;
-        let tracker = LimitedTracker::new(resource_limits);
+        let result_limits = parse_result_limits(&limits)?;
         let mut print = CollectStringPrint::new();
 
-        let result = run
-            .run(monty_inputs, tracker, &mut print)
-            .map_err(map_monty_exception)?;
-
+        let result = run_stoppable(
+            run,
+            monty_inputs,
+            Some(resource_limits),
+            &mut print,
+            abort_epoch,
+            &active,
+        )?
+        .map_err(map_monty_exception)?;
+
+        result_limits.check(&result)?;
         let hash = ruby.hash_new();
         hash.aset(ruby.to_symbol("result"), monty_to_ruby(result)?)?;
         hash.aset(
@@ -152,62 +468,312 @@ impl Run {
         Ok(hash.as_value())
     }
 
-    /// Start iterative execution (for external function calls).
-    /// Consumes the Run — it cannot be used again after this.
-    fn start(&self, inputs: RArray) -> Result<Progress, Error> {
-        let monty_run = self
-            .inner
-            .borrow_mut()
-            .take()
-            .ok_or_else(consumed_error)?;
+    /// Execute the Python code with inputs and optional limits, passing
+    /// each line printed to `callback` as it is written.
+    ///
+    /// The GVL is released while the script runs and re-acquired for each
+    /// line. `Monty.abort_all!` and `Monty.cancel_run` take effect at the
+    /// next line printed.
+    fn run_streaming(
+        &self,
+        inputs: RArray,
+        limits: Option<RHash>,
+        callback: Value,
+    ) -> Result<Value, Error> {
+        check_accepting()?;
+        self.check_one_shot()?;
+        let abort_epoch = current_epoch();
+        let active = register_run(self.script_name.as_deref());
+        let inner = self.read_inner();
+        let run = inner.as_ref().ok_or_else(consumed_error)?;
+
+        let monty_inputs = ruby_array_to_monty_vec(inputs)?;
+        let (resource_limits, result_limits) = match &limits {
+            Some(limits) => (
+                Some(parse_limits_hash(limits)?),
+                parse_result_limits(limits)?,
+            ),
+            None => (None, Default::default()),
+        };
+        let mut print = CallbackPrint::new(callback, abort_epoch, active.clone());
+
+        let result = run_stoppable(
+            run,
+            monty_inputs,
+            resource_limits,
+            &mut print,
+            abort_epoch,
+            &active,
+        )?;
+        let result = print.finish(result.map_err(map_monty_exception))?;
+
+        result_limits.check(&result)?;
+        monty_to_ruby(result)
+    }
+
+    /// Execute the code once per input set on a pool of native threads,
+    /// with the GVL released while the scripts run.
+    ///
+    /// Arguments:
+    ///   input_sets  - Array of input Arrays
+    ///   item_limits - Array with a limits Hash (or nil) per input set
+    ///   concurrency - number of threads
+    ///   deadline    - seconds from now after which no further sets start;
+    ///                 running ones get at most the remaining time
+    ///
+    /// Without a block, returns an Array holding each result, or the
+    /// Monty::Error instance for entries that failed, in input order.
+    /// With a block, yields `(index, result_or_error, stats)` as each
+    /// execution finishes and returns nil.
+    fn map(
+        &self,
+        input_sets: RArray,
+        item_limits: RArray,
+        concurrency: usize,
+        deadline: Option<f64>,
+    ) -> Result<Value, Error> {
+        check_accepting()?;
+        self.check_one_shot()?;
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let inner = self.read_inner();
+        let run = inner.as_ref().ok_or_else(consumed_error)?;
+
+        let sets = input_sets.to_vec::<RArray>()?;
+        let item_limits = item_limits.to_vec::<Option<RHash>>()?;
+        let mut jobs = Vec::with_capacity(sets.len());
+        let mut result_limits = Vec::with_capacity(sets.len());
+        for (set, limits) in sets.into_iter().zip(item_limits) {
+            let (tracker_limits, max_duration, item_result_limits) = match limits {
+                Some(limits) => (
+                    Some(parse_limits_hash(&limits)?),
+                    parse_max_duration(&limits)?,
+                    parse_result_limits(&limits)?,
+                ),
+                None => (None, None, Default::default()),
+            };
+            jobs.push(BatchJob {
+                inputs: ruby_array_to_monty_vec(set)?,
+                limits: tracker_limits,
+                max_duration,
+            });
+            result_limits.push(item_result_limits);
+        }
+        let deadline = batch_deadline(deadline)?;
+
+        let streaming = ruby.block_given();
+        let results = ruby.ary_new_from_values(&vec![ruby.qnil().as_value(); jobs.len()]);
+
+        let on_complete = |completed: Completed| {
+            let value = completed
+                .result
+                .map_err(map_monty_exception)
+                .and_then(|obj| result_limits[completed.index].check(&obj).map(|()| obj))
+                .and_then(monty_to_ruby)
+                .or_else(|err| err.value().map(|e| e.as_value()).ok_or(err))?;
+
+            if streaming {
+                let stats = ruby.hash_new();
+                stats.aset(ruby.to_symbol("duration"), completed.duration.as_secs_f64())?;
+                ruby.yield_values::<_, Value>((completed.index, value, stats))?;
+                Ok(())
+            } else {
+                results.store(completed.index as isize, value)
+            }
+        };
+        let active = register_run(self.script_name.as_deref());
+        run_batch(run, &active, jobs, concurrency, deadline, on_complete)?;
+
+        Ok(if streaming {
+            ruby.qnil().as_value()
+        } else {
+            results.as_value()
+        })
+    }
+
+    /// Run the code `iterations` times with random inputs built from
+    /// `generators` (one spec per input), collecting the runs that failed.
+    ///
+    /// Returns an Array of Hashes with :iteration, :inputs and :error
+    /// (the Monty::Error instance). Output printed by the script is
+    /// discarded. The same seed always produces the same inputs.
+    ///
+    /// Each iteration runs with the GVL released. An interrupt,
+    /// `Monty.abort_all!` or `Monty.cancel_run` stops the iteration in
+    /// progress.
+    fn fuzz(
+        &self,
+        generators: RArray,
+        iterations: usize,
+        limits: Option<RHash>,
+        seed: u64,
+    ) -> Result<RArray, Error> {
+        check_accepting()?;
+        self.check_one_shot()?;
+        let abort_epoch = current_epoch();
+        let active = register_run(self.script_name.as_deref());
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let inner = self.read_inner();
+        let run = inner.as_ref().ok_or_else(consumed_error)?;
 
+        let generators = parse_generators(generators)?;
+        let (resource_limits, result_limits) = match &limits {
+            Some(limits) => (
+                Some(parse_limits_hash(limits)?),
+                parse_result_limits(limits)?,
+            ),
+            None => (None, Default::default()),
+        };
+        let mut rng = Rng::new(seed);
+
+        let failures = ruby.ary_new();
+        for iteration in 0..iterations {
+            check_not_stopped(abort_epoch, &active)?;
+            let inputs: Vec<MontyObject> =
+                generators.iter().map(|g| g.generate(&mut rng)).collect();
+            let result = run_stoppable(
+                run,
+                inputs.clone(),
+                resource_limits.clone(),
+                &mut CollectStringPrint::new(),
+                abort_epoch,
+                &active,
+            )?;
+            let Err(err) = result
+                .map_err(map_monty_exception)
+                .and_then(|obj| result_limits.check(&obj))
+            else {
+                continue;
+            };
+
+            let failure = ruby.hash_new();
+            failure.aset(ruby.to_symbol("iteration"), iteration)?;
+            let ruby_inputs = ruby.ary_new_capa(inputs.len());
+            for input in inputs {
+                ruby_inputs.push(monty_to_ruby(input)?)?;
+            }
+            failure.aset(ruby.to_symbol("inputs"), ruby_inputs)?;
+            failure.aset(ruby.to_symbol("error"), err.value().ok_or(err)?)?;
+            failures.push(failure)?;
+        }
+
+        Ok(failures)
+    }
+
+    /// Start iterative execution (for external function calls).
+    /// Consumes the Run — it cannot be used again after this. The inputs
+    /// are converted first, so a bad input leaves the Run usable.
+    fn start(&self, inputs: RArray, trace: bool) -> Result<Progress, Error> {
+        check_accepting()?;
+
+        let context = ProgressContext {
+            abort_epoch: current_epoch(),
+            run: Some(register_run(self.script_name.as_deref())),
+            intrinsics: self.intrinsics,
+            timeline: trace.then(Timeline::new),
+            ..ProgressContext::default()
+        };
+        let converting = Instant::now();
         let monty_inputs = ruby_array_to_monty_vec(inputs)?;
+        context.trace("convert inputs", "conversion", converting);
+        let monty_run = self.take_inner()?;
         let mut print = CollectStringPrint::new();
+        let started = Instant::now();
 
         let progress = monty_run
             .start(monty_inputs, NoLimitTracker, &mut print)
             .map_err(map_monty_exception)?;
 
-        Progress::from_run_progress_no_limit(progress, print.into_output())
+        Progress::from_run_progress_no_limit(
+            progress,
+            print.into_output(),
+            context.after_segment(started),
+        )?
+        .settle_intrinsics()
     }
 
     /// Start iterative execution with resource limits.
-    /// Consumes the Run — it cannot be used again after this.
-    fn start_with_limits(&self, inputs: RArray, limits: RHash) -> Result<Progress, Error> {
-        let monty_run = self
-            .inner
-            .borrow_mut()
-            .take()
-            .ok_or_else(consumed_error)?;
+    /// Consumes the Run — it cannot be used again after this. The limits
+    /// and inputs are checked first, so a bad option leaves the Run
+    /// usable.
+    fn start_with_limits(
+        &self,
+        inputs: RArray,
+        limits: RHash,
+        trace: bool,
+    ) -> Result<Progress, Error> {
+        check_accepting()?;
 
-        let monty_inputs = ruby_array_to_monty_vec(inputs)?;
         let resource_limits = parse_limits_hash(&limits)?;
         let tracker = LimitedTracker::new(resource_limits);
+        let context = ProgressContext {
+            max_pending_futures: parse_max_pending_futures(&limits)?,
+            max_tool_result_bytes: parse_max_tool_result_bytes(&limits)?,
+            result_limits: parse_result_limits(&limits)?,
+            abort_epoch: current_epoch(),
+            run: Some(register_run(self.script_name.as_deref())),
+            intrinsics: self.intrinsics,
+            timeline: trace.then(Timeline::new),
+            ..ProgressContext::default()
+        };
+        let converting = Instant::now();
+        let monty_inputs = ruby_array_to_monty_vec(inputs)?;
+        context.trace("convert inputs", "conversion", converting);
+        let monty_run = self.take_inner()?;
         let mut print = CollectStringPrint::new();
+        let started = Instant::now();
 
         let progress = monty_run
             .start(monty_inputs, tracker, &mut print)
             .map_err(map_monty_exception)?;
 
-        Progress::from_run_progress_limited(progress, print.into_output())
+        Progress::from_run_progress_limited(
+            progress,
+            print.into_output(),
+            context.after_segment(started),
+        )?
+        .settle_intrinsics()
+    }
+
+    /// Start the run and answer every external call by yielding
+    /// `(name, args, kwargs)` to the block, resuming with its return value
+    /// until the run completes. Consumes the Run like `start`.
+    fn execute(&self, inputs: RArray, limits: Option<RHash>) -> Result<Value, Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        if !ruby.block_given() {
+            return Err(Error::new(
+                ruby.exception_arg_error(),
+                "a block is required",
+            ));
+        }
+
+        let progress = match limits {
+            Some(limits) => self.start_with_limits(inputs, limits, false)?,
+            None => self.start(inputs, false)?,
+        };
+        drive(progress, |call| {
+            ruby.yield_values((call.function_name(), call.args()?, call.kwargs()?))
+        })
     }
 
-    /// Serialize the Run to bytes
-    fn dump(&self) -> Result<Vec<u8>, Error> {
-        let inner = self.inner.borrow();
+    /// Serialize the Run to bytes, optionally embedding an absolute
+    /// expiry time (Unix seconds) checked by `load`.
+    fn dump(&self, expires_at: Option<f64>) -> Result<Vec<u8>, Error> {
+        let inner = self.read_inner();
         let run = inner.as_ref().ok_or_else(consumed_error)?;
-        run.dump().map_err(|e| {
+        let bytes = run.dump().map_err(|e| {
             let ruby = Ruby::get().expect("Ruby runtime not available");
             Error::new(
                 ruby.exception_runtime_error(),
                 format!("serialization error: {e}"),
             )
-        })
+        })?;
+        Ok(wrap_with_expiry(bytes, expires_at))
     }
 
     /// Deserialize a Run from bytes
     fn load(bytes: Vec<u8>) -> Result<Self, Error> {
-        let monty_run = MontyRun::load(&bytes).map_err(|e| {
+        let payload = unwrap_checking_expiry(&bytes)?;
+        let monty_run = MontyRun::load(payload).map_err(|e| {
             let ruby = Ruby::get().expect("Ruby runtime not available");
             Error::new(
                 ruby.exception_runtime_error(),
@@ -216,18 +782,74 @@ impl Run {
         })?;
 
         Ok(Self {
-            inner: RefCell::new(Some(monty_run)),
+            fingerprint: OnceLock::new(),
+            inner: RwLock::new(Some(monty_run)),
+            script_name: None,
+            external_functions: Vec::new(),
+            input_names: None,
+            warnings: Vec::new(),
+            compile_stats: None,
+            intrinsics: false,
         })
     }
 }
 
+/// The instant after which `map` starts no further input sets. Nil and
+/// infinity mean no deadline, as does one too far away to represent;
+/// negative and NaN values raise ArgumentError.
+fn batch_deadline(secs: Option<f64>) -> Result<Option<Instant>, Error> {
+    let Some(secs) = secs.filter(|secs| *secs != f64::INFINITY) else {
+        return Ok(None);
+    };
+    if secs.is_nan() || secs < 0.0 {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        return Err(Error::new(
+            ruby.exception_arg_error(),
+            format!("deadline must be a non-negative number of seconds, got {secs}"),
+        ));
+    }
+    Ok(Duration::try_from_secs_f64(secs)
+        .ok()
+        .and_then(|remaining| Instant::now().checked_add(remaining)))
+}
+
+/// Reject scripts importing a module from the disabled feature set. This
+/// only sees `import` and `from ... import` statements, so it is a lint
+/// rather than a sandbox boundary: `__import__` and `importlib` calls are
+/// not checked.
+fn check_disabled_modules(code: &str, disabled: &[String]) -> Result<(), Error> {
+    for (module, line) in imported_modules(code) {
+        if disabled.contains(&module) {
+            return Err(python_error(
+                "ModuleNotFoundError",
+                format!("ModuleNotFoundError: module '{module}' is disabled (line {line})"),
+            ));
+        }
+    }
+    Ok(())
+}
+
 pub fn define_run_class(ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
     let class = module.define_class("Run", ruby.class_object())?;
+    class.const_set(
+        "INTRINSICS",
+        ruby.ary_from_iter(intrinsics::NAMES.iter().copied()),
+    )?;
 
-    class.define_singleton_method("_new", function!(Run::new, 4))?;
+    class.define_singleton_method("_new", function!(Run::new, 6))?;
     class.define_singleton_method("_load", function!(Run::load, 1))?;
 
     class.define_method("code", method!(Run::code, 0))?;
+    class.define_method("==", method!(Run::eq, 1))?;
+    class.define_method("eql?", method!(Run::eq, 1))?;
+    class.define_method("hash", method!(Run::hash, 0))?;
+    class.define_method("defined_names", method!(Run::defined_names, 0))?;
+    class.define_method("call_graph", method!(Run::call_graph, 0))?;
+    class.define_method("typecheck", method!(Run::typecheck, 0))?;
+    class.define_method("capabilities", method!(Run::capabilities, 0))?;
+    class.define_method("warnings", method!(Run::warnings, 0))?;
+    class.define_method("compile_stats", method!(Run::compile_stats, 0))?;
+    class.define_method("_named_inputs", method!(Run::named_inputs, 1))?;
     class.define_method("_run", method!(Run::run, 1))?;
     class.define_method("_run_with_limits", method!(Run::run_with_limits, 2))?;
     class.define_method("_run_capturing", method!(Run::run_capturing, 1))?;
@@ -235,9 +857,13 @@ pub fn define_run_class(ruby: &Ruby, module: &magnus::RModule) -> Result<(), Err
         "_run_capturing_with_limits",
         method!(Run::run_capturing_with_limits, 2),
     )?;
-    class.define_method("_start", method!(Run::start, 1))?;
-    class.define_method("_start_with_limits", method!(Run::start_with_limits, 2))?;
-    class.define_method("_dump", method!(Run::dump, 0))?;
+    class.define_method("_run_streaming", method!(Run::run_streaming, 3))?;
+    class.define_method("_map", method!(Run::map, 4))?;
+    class.define_method("_fuzz", method!(Run::fuzz, 4))?;
+    class.define_method("_start", method!(Run::start, 2))?;
+    class.define_method("_start_with_limits", method!(Run::start_with_limits, 3))?;
+    class.define_method("_execute", method!(Run::execute, 2))?;
+    class.define_method("_dump", method!(Run::dump, 1))?;
 
     Ok(())
 }
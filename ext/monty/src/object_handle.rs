@@ -0,0 +1,371 @@
+use magnus::{method, Error, Module, Ruby, TryConvert, Value};
+use monty_lang::MontyObject;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::errors::python_error;
+use crate::monty_object::{monty_to_ruby, ruby_to_monty};
+
+/// Ruby wrapper holding an unconverted MontyObject.
+///
+/// Returned by `Complete#object` so large results can be kept on the Rust
+/// side until the host actually needs them as Ruby values. Equality and
+/// hashing follow Python semantics, so handles can be used as Hash keys.
+#[magnus::wrap(class = "Monty::Object", free_immediately, size)]
+pub struct ObjectHandle {
+    inner: MontyObject,
+}
+
+impl ObjectHandle {
+    pub fn new(inner: MontyObject) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &MontyObject {
+        &self.inner
+    }
+
+    /// Convert the wrapped object to a Ruby value.
+    fn to_ruby(&self) -> Result<Value, Error> {
+        monty_to_ruby(self.inner.clone())
+    }
+
+    /// Subscript the wrapped object (`obj[key]`) without converting it.
+    fn aref(&self, key: Value) -> Result<ObjectHandle, Error> {
+        let key = ruby_to_monty(key)?;
+        getitem(&self.inner, &key).map(ObjectHandle::new)
+    }
+
+    /// Read a named tuple field or dataclass attribute.
+    fn attr(&self, name: String) -> Result<ObjectHandle, Error> {
+        getattr(&self.inner, &name).map(ObjectHandle::new)
+    }
+
+    fn eq(&self, other: Value) -> bool {
+        match <&ObjectHandle>::try_convert(other) {
+            Ok(other) => py_eq(&self.inner, &other.inner),
+            Err(_) => false,
+        }
+    }
+
+    fn hash(&self) -> i64 {
+        let mut hasher = DefaultHasher::new();
+        py_hash(&self.inner, &mut hasher);
+        hasher.finish() as i64
+    }
+}
+
+fn getitem(obj: &MontyObject, key: &MontyObject) -> Result<MontyObject, Error> {
+    match obj {
+        MontyObject::List(items)
+        | MontyObject::Tuple(items)
+        | MontyObject::NamedTuple { values: items, .. } => {
+            let index = sequence_index(obj, key, items.len())?;
+            Ok(items[index].clone())
+        }
+        MontyObject::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let index = sequence_index(obj, key, chars.len())?;
+            Ok(MontyObject::String(chars[index].to_string()))
+        }
+        MontyObject::Bytes(b) => {
+            let index = sequence_index(obj, key, b.len())?;
+            Ok(MontyObject::Int(b[index] as i64))
+        }
+        MontyObject::Dict(pairs) => pairs
+            .into_iter()
+            .find(|(k, _)| py_eq(k, key))
+            .map(|(_, v)| v.clone())
+            .ok_or_else(|| python_error("KeyError", format!("KeyError: {}", short_repr(key)))),
+        _ => Err(python_error(
+            "TypeError",
+            format!(
+                "TypeError: '{}' object is not subscriptable",
+                type_name(obj)
+            ),
+        )),
+    }
+}
+
+fn sequence_index(obj: &MontyObject, key: &MontyObject, len: usize) -> Result<usize, Error> {
+    let index = match key {
+        MontyObject::Int(i) => *i,
+        MontyObject::Bool(b) => *b as i64,
+        _ => {
+            return Err(python_error(
+                "TypeError",
+                format!(
+                    "TypeError: {} indices must be integers, not {}",
+                    type_name(obj),
+                    type_name(key)
+                ),
+            ))
+        }
+    };
+    let resolved = if index < 0 { index + len as i64 } else { index };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(python_error(
+            "IndexError",
+            format!("IndexError: {} index out of range", type_name(obj)),
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+fn getattr(obj: &MontyObject, name: &str) -> Result<MontyObject, Error> {
+    let found = match obj {
+        MontyObject::NamedTuple {
+            field_names,
+            values,
+            ..
+        } => field_names
+            .iter()
+            .position(|field| field == name)
+            .map(|i| values[i].clone()),
+        MontyObject::Dataclass { attrs, .. } => attrs
+            .into_iter()
+            .find(|(k, _)| matches!(k, MontyObject::String(s) if s == name))
+            .map(|(_, v)| v.clone()),
+        _ => None,
+    };
+
+    found.ok_or_else(|| {
+        python_error(
+            "AttributeError",
+            format!(
+                "AttributeError: '{}' object has no attribute '{name}'",
+                type_name(obj)
+            ),
+        )
+    })
+}
+
+/// Python type name of a MontyObject, for error messages.
+pub fn type_name(obj: &MontyObject) -> &'static str {
+    match obj {
+        MontyObject::None => "NoneType",
+        MontyObject::Bool(_) => "bool",
+        MontyObject::Int(_) | MontyObject::BigInt(_) => "int",
+        MontyObject::Float(_) => "float",
+        MontyObject::String(_) => "str",
+        MontyObject::Bytes(_) => "bytes",
+        MontyObject::List(_) => "list",
+        MontyObject::Tuple(_) | MontyObject::NamedTuple { .. } => "tuple",
+        MontyObject::Dict(_) => "dict",
+        MontyObject::Set(_) => "set",
+        MontyObject::FrozenSet(_) => "frozenset",
+        MontyObject::Dataclass { .. } => "dataclass",
+        MontyObject::Ellipsis => "ellipsis",
+        MontyObject::Type(_) => "type",
+        MontyObject::BuiltinFunction(_) => "builtin_function_or_method",
+        MontyObject::Path(_) => "PosixPath",
+        MontyObject::Exception { .. } => "Exception",
+        MontyObject::Repr(_) | MontyObject::Cycle(..) => "object",
+    }
+}
+
+fn short_repr(obj: &MontyObject) -> String {
+    match obj {
+        MontyObject::String(s) => format!("'{s}'"),
+        MontyObject::Int(i) => i.to_string(),
+        other => type_name(other).to_string(),
+    }
+}
+
+/// Numeric view of a MontyObject used to compare int, bool and float the way
+/// Python does (`1 == 1.0 == True`).
+enum Number {
+    Int(num_bigint::BigInt),
+    Float(f64),
+}
+
+fn as_number(obj: &MontyObject) -> Option<Number> {
+    match obj {
+        MontyObject::Bool(b) => Some(Number::Int(num_bigint::BigInt::from(*b as i64))),
+        MontyObject::Int(i) => Some(Number::Int(num_bigint::BigInt::from(*i))),
+        MontyObject::BigInt(bi) => Some(Number::Int(bi.clone())),
+        MontyObject::Float(f) => match integral_float(*f) {
+            Some(bi) => Some(Number::Int(bi)),
+            None => Some(Number::Float(*f)),
+        },
+        _ => None,
+    }
+}
+
+/// Returns the float as an exact integer if it has no fractional part.
+fn integral_float(f: f64) -> Option<num_bigint::BigInt> {
+    if f.is_finite() && f.fract() == 0.0 {
+        format!("{f:.0}").parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Python `==` over MontyObjects.
+pub fn py_eq(a: &MontyObject, b: &MontyObject) -> bool {
+    if let (Some(x), Some(y)) = (as_number(a), as_number(b)) {
+        return match (x, y) {
+            (Number::Int(x), Number::Int(y)) => x == y,
+            (Number::Float(x), Number::Float(y)) => x == y,
+            _ => false,
+        };
+    }
+
+    match (a, b) {
+        (MontyObject::None, MontyObject::None) => true,
+        (MontyObject::Ellipsis, MontyObject::Ellipsis) => true,
+        (MontyObject::String(x), MontyObject::String(y)) => x == y,
+        (MontyObject::Bytes(x), MontyObject::Bytes(y)) => x == y,
+        (MontyObject::Path(x), MontyObject::Path(y)) => x == y,
+        (MontyObject::List(x), MontyObject::List(y)) => seq_eq(x, y),
+        (
+            MontyObject::Tuple(x) | MontyObject::NamedTuple { values: x, .. },
+            MontyObject::Tuple(y) | MontyObject::NamedTuple { values: y, .. },
+        ) => seq_eq(x, y),
+        (
+            MontyObject::Set(x) | MontyObject::FrozenSet(x),
+            MontyObject::Set(y) | MontyObject::FrozenSet(y),
+        ) => x.len() == y.len() && x.iter().all(|item| y.iter().any(|o| py_eq(item, o))),
+        (MontyObject::Dict(x), MontyObject::Dict(y)) => pairs_eq(x, y),
+        (
+            MontyObject::Dataclass {
+                name: x_name,
+                attrs: x_attrs,
+                ..
+            },
+            MontyObject::Dataclass {
+                name: y_name,
+                attrs: y_attrs,
+                ..
+            },
+        ) => {
+            x_name == y_name && pairs_eq(x_attrs, y_attrs)
+        }
+        (MontyObject::Type(x), MontyObject::Type(y)) => format!("{x:?}") == format!("{y:?}"),
+        (MontyObject::BuiltinFunction(x), MontyObject::BuiltinFunction(y)) => {
+            format!("{x:?}") == format!("{y:?}")
+        }
+        (MontyObject::Repr(x), MontyObject::Repr(y)) => x == y,
+        (MontyObject::Cycle(_, x), MontyObject::Cycle(_, y)) => x == y,
+        _ => false,
+    }
+}
+
+fn seq_eq(a: &[MontyObject], b: &[MontyObject]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| py_eq(x, y))
+}
+
+fn pairs_eq<'a, P>(a: &'a P, b: &'a P) -> bool
+where
+    &'a P: IntoIterator<Item = &'a (MontyObject, MontyObject)>,
+{
+    a.into_iter().count() == b.into_iter().count()
+        && a.into_iter().all(|(k, v)| {
+            b.into_iter()
+                .find(|(other_k, _)| py_eq(k, other_k))
+                .is_some_and(|(_, other_v)| py_eq(v, other_v))
+        })
+}
+
+/// Hash consistent with `py_eq`: objects that compare equal hash equally.
+/// Unordered containers combine item hashes with a commutative sum.
+pub fn py_hash<H: Hasher>(obj: &MontyObject, state: &mut H) {
+    if let Some(number) = as_number(obj) {
+        match number {
+            Number::Int(i) => {
+                0u8.hash(state);
+                i.hash(state);
+            }
+            Number::Float(f) => {
+                1u8.hash(state);
+                f.to_bits().hash(state);
+            }
+        }
+        return;
+    }
+
+    match obj {
+        MontyObject::None => 2u8.hash(state),
+        MontyObject::Ellipsis => 3u8.hash(state),
+        MontyObject::String(s) => {
+            4u8.hash(state);
+            s.hash(state);
+        }
+        MontyObject::Bytes(b) => {
+            5u8.hash(state);
+            b.hash(state);
+        }
+        MontyObject::Path(s) => {
+            6u8.hash(state);
+            s.hash(state);
+        }
+        MontyObject::List(items) => {
+            7u8.hash(state);
+            for item in items {
+                py_hash(item, state);
+            }
+        }
+        MontyObject::Tuple(items) | MontyObject::NamedTuple { values: items, .. } => {
+            8u8.hash(state);
+            for item in items {
+                py_hash(item, state);
+            }
+        }
+        MontyObject::Set(items) | MontyObject::FrozenSet(items) => {
+            9u8.hash(state);
+            unordered_hash(items.iter().map(item_hash)).hash(state);
+        }
+        MontyObject::Dict(pairs) => {
+            10u8.hash(state);
+            unordered_hash(pairs.into_iter().map(pair_hash)).hash(state);
+        }
+        MontyObject::Dataclass { name, attrs, .. } => {
+            11u8.hash(state);
+            name.hash(state);
+            unordered_hash(attrs.into_iter().map(pair_hash)).hash(state);
+        }
+        MontyObject::Type(t) => {
+            12u8.hash(state);
+            format!("{t:?}").hash(state);
+        }
+        MontyObject::BuiltinFunction(f) => {
+            13u8.hash(state);
+            format!("{f:?}").hash(state);
+        }
+        MontyObject::Repr(s) | MontyObject::Cycle(_, s) => {
+            14u8.hash(state);
+            s.hash(state);
+        }
+        _ => 15u8.hash(state),
+    }
+}
+
+fn item_hash(obj: &MontyObject) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    py_hash(obj, &mut hasher);
+    hasher.finish()
+}
+
+fn pair_hash((k, v): &(MontyObject, MontyObject)) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    py_hash(k, &mut hasher);
+    py_hash(v, &mut hasher);
+    hasher.finish()
+}
+
+fn unordered_hash(hashes: impl Iterator<Item = u64>) -> u64 {
+    hashes.fold(0u64, |acc, h| acc.wrapping_add(h))
+}
+
+pub fn define_object_class(ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    let class = module.define_class("Object", ruby.class_object())?;
+
+    class.define_method("to_ruby", method!(ObjectHandle::to_ruby, 0))?;
+    class.define_method("[]", method!(ObjectHandle::aref, 1))?;
+    class.define_method("attr", method!(ObjectHandle::attr, 1))?;
+    class.define_method("==", method!(ObjectHandle::eq, 1))?;
+    class.define_method("eql?", method!(ObjectHandle::eq, 1))?;
+    class.define_method("hash", method!(ObjectHandle::hash, 0))?;
+
+    Ok(())
+}
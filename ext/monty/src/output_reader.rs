@@ -0,0 +1,94 @@
+use magnus::{method, Class, Error, Module, RString, Ruby};
+use std::cell::Cell;
+use std::sync::Arc;
+
+/// Read-only, IO-like view of a completed run's captured output.
+///
+/// Shares the Rust buffer with the Complete it came from, so large output
+/// can be streamed to storage in chunks without first being copied into
+/// one Ruby String. Only the chunks handed out are allocated in Ruby.
+#[magnus::wrap(class = "Monty::OutputReader", free_immediately, size)]
+pub struct OutputReader {
+    buffer: Arc<String>,
+    /// Byte offset of the next read.
+    pos: Cell<usize>,
+}
+
+impl OutputReader {
+    pub fn new(buffer: Arc<String>) -> Self {
+        Self {
+            buffer,
+            pos: Cell::new(0),
+        }
+    }
+
+    fn remaining(&self) -> &[u8] {
+        &self.buffer.as_bytes()[self.pos.get()..]
+    }
+
+    /// Backs `read`: with no length, the rest of the output as a UTF-8
+    /// String ("" at the end); with a length, up to that many bytes as a
+    /// binary String (nil at the end), which may split a character.
+    fn read(&self, length: Option<usize>) -> Option<RString> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let rest = self.remaining();
+        let Some(length) = length else {
+            self.pos.set(self.buffer.len());
+            return Some(ruby.enc_str_new(rest, ruby.utf8_encoding()));
+        };
+        if rest.is_empty() && length > 0 {
+            return None;
+        }
+        let chunk = &rest[..length.min(rest.len())];
+        self.pos.set(self.pos.get() + chunk.len());
+        Some(ruby.str_from_slice(chunk))
+    }
+
+    /// Like `IO#gets`: the next line including its newline, or nil at the
+    /// end.
+    fn gets(&self) -> Option<RString> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let rest = self.remaining();
+        if rest.is_empty() {
+            return None;
+        }
+        let end = rest
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(rest.len(), |newline| newline + 1);
+        self.pos.set(self.pos.get() + end);
+        Some(ruby.enc_str_new(&rest[..end], ruby.utf8_encoding()))
+    }
+
+    fn is_eof(&self) -> bool {
+        self.remaining().is_empty()
+    }
+
+    fn pos(&self) -> usize {
+        self.pos.get()
+    }
+
+    fn rewind(&self) -> usize {
+        self.pos.set(0);
+        0
+    }
+
+    /// Total size of the output in bytes.
+    fn size(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+pub fn define_output_reader_class(ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    let class = module.define_class("OutputReader", ruby.class_object())?;
+    class.undef_default_alloc_func();
+
+    class.define_method("_read", method!(OutputReader::read, 1))?;
+    class.define_method("gets", method!(OutputReader::gets, 0))?;
+    class.define_method("eof?", method!(OutputReader::is_eof, 0))?;
+    class.define_method("pos", method!(OutputReader::pos, 0))?;
+    class.define_method("rewind", method!(OutputReader::rewind, 0))?;
+    class.define_method("size", method!(OutputReader::size, 0))?;
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use magnus::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::{monty_error, snapshot_expired_error};
+
+/// Header marking a dump that carries an absolute expiry time.
+const EXPIRY_MAGIC: &[u8; 8] = b"MONTYTTL";
+
+/// Prefix serialized bytes with an expiry header when `expires_at` (Unix
+/// seconds) is given; otherwise return them unchanged.
+pub fn wrap_with_expiry(bytes: Vec<u8>, expires_at: Option<f64>) -> Vec<u8> {
+    let Some(expires_at) = expires_at else {
+        return bytes;
+    };
+
+    let mut wrapped = Vec::with_capacity(EXPIRY_MAGIC.len() + 8 + bytes.len());
+    wrapped.extend_from_slice(EXPIRY_MAGIC);
+    wrapped.extend_from_slice(&expires_at.to_be_bytes());
+    wrapped.extend_from_slice(&bytes);
+    wrapped
+}
+
+/// Strip an expiry header, raising Monty::SnapshotExpiredError if the
+/// expiry has passed, or Monty::Error if the header is cut short. Dumps
+/// without a header never expire.
+pub fn unwrap_checking_expiry(bytes: &[u8]) -> Result<&[u8], Error> {
+    let Some(rest) = bytes.strip_prefix(EXPIRY_MAGIC.as_slice()) else {
+        return Ok(bytes);
+    };
+    let Some((expiry, payload)) = rest.split_first_chunk::<8>() else {
+        return Err(monty_error(
+            "truncated snapshot: expiry header is incomplete".to_string(),
+        ));
+    };
+    let expires_at = f64::from_be_bytes(*expiry);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+    if now >= expires_at {
+        return Err(snapshot_expired_error(format!(
+            "snapshot expired {:.0}s ago",
+            now - expires_at
+        )));
+    }
+
+    Ok(payload)
+}
@@ -0,0 +1,45 @@
+use magnus::typed_data::Obj;
+use magnus::value::ReprValue;
+use magnus::{gc, DataTypeFunctions, Error, Module, Ruby, TypedData, Value};
+use std::cell::RefCell;
+
+/// Ruby values that Rust-side state refers to by address.
+///
+/// Raw `VALUE`s kept outside the Ruby heap are invisible to the GC, and
+/// compaction may move the objects behind them, after which another
+/// object can take the old address. While a Pins object is reachable
+/// (e.g. held in a local during a conversion), everything pinned in it is
+/// marked without allowing it to move, so addresses stay valid and unique.
+///
+/// Handles such as `Monty::Object` and `Monty::SharedData` own converted
+/// `MontyObject`s rather than Ruby values and need no marking.
+#[derive(Default, TypedData)]
+#[magnus(class = "Monty::Pins", free_immediately, mark)]
+pub struct Pins(RefCell<Vec<Value>>);
+
+impl DataTypeFunctions for Pins {
+    fn mark(&self, marker: &gc::Marker) {
+        for value in self.0.borrow().iter() {
+            marker.mark(*value);
+        }
+    }
+}
+
+impl Pins {
+    pub fn new(ruby: &Ruby) -> Obj<Self> {
+        ruby.obj_wrap(Self::default())
+    }
+
+    /// Keep `value` alive and at its current address while these pins are.
+    pub fn pin(&self, value: Value) {
+        self.0.borrow_mut().push(value);
+    }
+}
+
+pub fn define_pins_class(ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    let class = module.define_class("Pins", ruby.class_object())?;
+    class.undef_default_alloc_func();
+    module.funcall::<_, _, Value>("private_constant", (ruby.to_symbol("Pins"),))?;
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use magnus::{function, Error, Object, RHash, Ruby};
+
+use crate::source_analysis::{capabilities, complexity, imported_modules};
+use crate::type_check::missing_type_hints;
+
+/// What Monty::Policy checks a script against, gathered in one pass so
+/// rules can be evaluated in Ruby without re-lexing the source.
+fn policy_facts(code: String, external_functions: Vec<String>) -> Result<RHash, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let facts = ruby.hash_new();
+
+    let imports = ruby.ary_new();
+    for (module, line) in imported_modules(&code) {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("module"), module)?;
+        hash.aset(ruby.to_symbol("line"), line)?;
+        imports.push(hash)?;
+    }
+    facts.aset(ruby.to_symbol("imports"), imports)?;
+
+    let os_calls = capabilities(&code, &external_functions).os_calls;
+    facts.aset(ruby.to_symbol("os_calls"), os_calls)?;
+
+    let functions = ruby.ary_new();
+    for measured in complexity(&code) {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("function"), measured.function)?;
+        hash.aset(ruby.to_symbol("line"), measured.line)?;
+        hash.aset(ruby.to_symbol("complexity"), measured.complexity)?;
+        functions.push(hash)?;
+    }
+    facts.aset(ruby.to_symbol("complexity"), functions)?;
+
+    let hints = ruby.ary_new();
+    for issue in missing_type_hints(&code) {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("message"), issue.message)?;
+        hash.aset(ruby.to_symbol("line"), issue.line)?;
+        hints.push(hash)?;
+    }
+    facts.aset(ruby.to_symbol("missing_type_hints"), hints)?;
+
+    Ok(facts)
+}
+
+pub fn define_policy(_ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    module.define_singleton_method("_policy_facts", function!(policy_facts, 2))?;
+
+    Ok(())
+}
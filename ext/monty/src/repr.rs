@@ -0,0 +1,305 @@
+use magnus::{function, Error, Object, Ruby, Value};
+use monty_lang::MontyObject;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::monty_object::ruby_to_monty;
+
+/// Default for `Monty.max_repr_length`.
+const DEFAULT_MAX_REPR_LENGTH: usize = 200;
+
+static MAX_REPR_LENGTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_REPR_LENGTH);
+
+/// Characters kept past `Monty.max_repr_length` when a repr is captured,
+/// so a secret starting in the shown part reaches `Monty.redactor` whole.
+/// Ruby cuts the text to the limit after redacting it.
+const REDACTION_HEADROOM: usize = 1024;
+
+/// Longest repr the binding puts in logs and inspect output, in
+/// characters. Full values stay available through `args`, `kwargs` and
+/// `value`.
+pub fn max_repr_length() -> usize {
+    MAX_REPR_LENGTH.load(Ordering::Relaxed)
+}
+
+/// Longest repr captured for logs before it is redacted and cut to
+/// `Monty.max_repr_length` on the Ruby side.
+pub fn capture_limit() -> usize {
+    max_repr_length().saturating_add(REDACTION_HEADROOM)
+}
+
+fn set_max_repr_length(length: usize) -> usize {
+    MAX_REPR_LENGTH.store(length, Ordering::Relaxed);
+    length
+}
+
+/// Python `repr()` of a MontyObject, for logs and messages shown to
+/// script authors.
+pub fn py_repr(obj: &MontyObject) -> String {
+    let mut out = ReprWriter::new(usize::MAX);
+    out.write(obj);
+    out.finish()
+}
+
+/// `py_repr` cut to at most `max_chars` characters, marked with "...".
+/// Stops walking `obj` once the limit is reached, so the repr of a huge
+/// value costs no more than the part that is kept.
+pub fn py_repr_bounded(obj: &MontyObject, max_chars: usize) -> String {
+    let mut out = ReprWriter::new(max_chars);
+    out.write(obj);
+    out.finish()
+}
+
+/// `Monty.repr`: how a Ruby value will look inside a script, converted
+/// exactly as an input would be. Not truncated.
+fn repr_of_ruby(value: Value) -> Result<String, Error> {
+    Ok(py_repr(&ruby_to_monty(value)?))
+}
+
+/// Cut `text` to at most `max_chars` characters, marking the cut with "...".
+pub fn truncate(text: String, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((cut, _)) => format!("{}...", &text[..cut]),
+        None => text,
+    }
+}
+
+/// Text buffer that stops growing after `limit` characters.
+pub struct ReprWriter {
+    text: String,
+    chars: usize,
+    limit: usize,
+    cut: bool,
+}
+
+impl ReprWriter {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            text: String::new(),
+            chars: 0,
+            limit,
+            cut: false,
+        }
+    }
+
+    /// Whether anything has been dropped; later writes are ignored.
+    pub fn is_full(&self) -> bool {
+        self.cut
+    }
+
+    pub fn push(&mut self, c: char) {
+        if self.chars == self.limit {
+            self.cut = true;
+        } else if !self.cut {
+            self.text.push(c);
+            self.chars += 1;
+        }
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        for c in s.chars() {
+            if self.cut {
+                return;
+            }
+            self.push(c);
+        }
+    }
+
+    /// Append the Python repr of `obj`.
+    pub fn write(&mut self, obj: &MontyObject) {
+        write_repr(obj, self);
+    }
+
+    /// The text written, followed by "..." if anything was cut.
+    pub fn finish(mut self) -> String {
+        if self.cut {
+            self.text.push_str("...");
+        }
+        self.text
+    }
+}
+
+fn write_repr(obj: &MontyObject, out: &mut ReprWriter) {
+    if out.is_full() {
+        return;
+    }
+    match obj {
+        MontyObject::None => out.push_str("None"),
+        MontyObject::Bool(true) => out.push_str("True"),
+        MontyObject::Bool(false) => out.push_str("False"),
+        MontyObject::Int(i) => out.push_str(&i.to_string()),
+        MontyObject::BigInt(bi) => out.push_str(&bi.to_string()),
+        MontyObject::Float(f) => out.push_str(&float_repr(*f)),
+        MontyObject::String(s) => write_str(s, out),
+        MontyObject::Bytes(b) => write_bytes(b, out),
+        MontyObject::List(items) => write_items("[", items, "]", out),
+        MontyObject::Tuple(items) if items.len() == 1 => write_items("(", items, ",)", out),
+        MontyObject::Tuple(items) => write_items("(", items, ")", out),
+        MontyObject::NamedTuple {
+            field_names,
+            values,
+            ..
+        } => {
+            out.push('(');
+            for (i, (name, value)) in field_names.iter().zip(values).enumerate() {
+                if out.is_full() {
+                    return;
+                }
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                out.push_str(name);
+                out.push('=');
+                write_repr(value, out);
+            }
+            out.push(')');
+        }
+        MontyObject::Dict(pairs) => {
+            out.push('{');
+            for (i, (key, value)) in pairs.into_iter().enumerate() {
+                if out.is_full() {
+                    return;
+                }
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_repr(key, out);
+                out.push_str(": ");
+                write_repr(value, out);
+            }
+            out.push('}');
+        }
+        MontyObject::Set(items) if items.is_empty() => out.push_str("set()"),
+        MontyObject::Set(items) => write_items("{", items, "}", out),
+        MontyObject::FrozenSet(items) if items.is_empty() => out.push_str("frozenset()"),
+        MontyObject::FrozenSet(items) => write_items("frozenset({", items, "})", out),
+        MontyObject::Dataclass { name, attrs, .. } => {
+            out.push_str(name);
+            out.push('(');
+            for (i, (key, value)) in attrs.into_iter().enumerate() {
+                if out.is_full() {
+                    return;
+                }
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                match key {
+                    MontyObject::String(key) => out.push_str(key),
+                    other => write_repr(other, out),
+                }
+                out.push('=');
+                write_repr(value, out);
+            }
+            out.push(')');
+        }
+        MontyObject::Ellipsis => out.push_str("Ellipsis"),
+        MontyObject::Type(t) => out.push_str(&format!("<class '{t:?}'>")),
+        MontyObject::BuiltinFunction(f) => out.push_str(&format!("<built-in function {f:?}>")),
+        MontyObject::Path(s) => {
+            out.push_str("PosixPath(");
+            write_str(s, out);
+            out.push(')');
+        }
+        MontyObject::Repr(s) | MontyObject::Cycle(_, s) => out.push_str(s),
+        MontyObject::Exception { exc_type, arg } => {
+            out.push_str(&format!("{exc_type:?}("));
+            if let Some(arg) = arg {
+                write_str(arg, out);
+            }
+            out.push(')');
+        }
+    }
+}
+
+fn write_items(open: &str, items: &[MontyObject], close: &str, out: &mut ReprWriter) {
+    out.push_str(open);
+    for (i, item) in items.iter().enumerate() {
+        if out.is_full() {
+            return;
+        }
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_repr(item, out);
+    }
+    out.push_str(close);
+}
+
+/// Single-quoted unless the text contains a single quote and no double
+/// quote, as Python does.
+fn write_str(s: &str, out: &mut ReprWriter) {
+    let quote = if s.contains('\'') && !s.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    out.push(quote);
+    for c in s.chars() {
+        if out.is_full() {
+            return;
+        }
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push(quote);
+}
+
+fn write_bytes(bytes: &[u8], out: &mut ReprWriter) {
+    out.push_str("b'");
+    for &b in bytes {
+        if out.is_full() {
+            return;
+        }
+        match b {
+            b'\\' => out.push_str("\\\\"),
+            b'\'' => out.push_str("\\'"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            0x20..=0x7e => out.push(b as char),
+            _ => out.push_str(&format!("\\x{b:02x}")),
+        }
+    }
+    out.push('\'');
+}
+
+/// Shortest round-tripping digits, like Python's `repr(float)`: exponent
+/// form (`1e+20`, `1e-07`) from 1e16 up and below 1e-4, a trailing `.0`
+/// on integral values otherwise.
+fn float_repr(f: f64) -> String {
+    if f.is_nan() {
+        "nan".to_string()
+    } else if f.is_infinite() {
+        if f > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else if f != 0.0 && (f.abs() >= 1e16 || f.abs() < 1e-4) {
+        let formatted = format!("{f:e}");
+        let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+        let exponent: i32 = exponent.parse().unwrap_or(0);
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{mantissa}e{sign}{:02}", exponent.abs())
+    } else {
+        let formatted = f.to_string();
+        if formatted.contains('.') {
+            formatted
+        } else {
+            format!("{formatted}.0")
+        }
+    }
+}
+
+pub fn define_repr_settings(_ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    module.define_singleton_method("max_repr_length", function!(max_repr_length, 0))?;
+    module.define_singleton_method("max_repr_length=", function!(set_max_repr_length, 1))?;
+    module.define_singleton_method("repr", function!(repr_of_ruby, 1))?;
+
+    Ok(())
+}
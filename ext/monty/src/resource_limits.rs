@@ -1,7 +1,10 @@
+use magnus::r_hash::ForEach;
 use magnus::value::ReprValue;
 use magnus::{function, Error, Module, Object, RHash, Ruby, TryConvert, Value};
 use std::time::Duration;
 
+use crate::monty_object::ResultLimits;
+
 /// Ruby wrapper for monty_lang::ResourceLimits
 #[magnus::wrap(class = "Monty::ResourceLimits", free_immediately, size)]
 pub struct ResourceLimits {
@@ -11,48 +14,73 @@ pub struct ResourceLimits {
 
 impl ResourceLimits {
     fn new(options: Option<RHash>) -> Result<Self, Error> {
-        let mut limits = monty_lang::ResourceLimits::new();
-
-        if let Some(opts) = options {
-            if let Some(val) = get_optional_usize(&opts, "max_allocations")? {
-                limits = limits.max_allocations(val);
-            }
-            if let Some(val) = get_optional_f64(&opts, "max_duration")? {
-                limits = limits.max_duration(Duration::from_secs_f64(val));
-            }
-            if let Some(val) = get_optional_usize(&opts, "max_memory")? {
-                limits = limits.max_memory(val);
-            }
-            if let Some(val) = get_optional_usize(&opts, "gc_interval")? {
-                limits = limits.gc_interval(val);
-            }
-            if let Some(val) = get_optional_usize(&opts, "max_recursion_depth")? {
-                limits = limits.max_recursion_depth(Some(val));
-            }
-        }
+        let limits = match options {
+            Some(opts) => parse_limits_hash(&opts)?,
+            None => monty_lang::ResourceLimits::new(),
+        };
 
         Ok(Self { inner: limits })
     }
 }
 
+/// Keys holding a byte size, which accept a String with a unit.
+const BYTE_SIZE_KEYS: &[&str] = &["max_memory", "max_tool_result_bytes", "max_result_bytes"];
+
+/// `Monty._normalize_limits`: a copy of a limits hash with `max_duration`
+/// in seconds and every byte size in bytes, so scopes written with
+/// different units ("1GB", 2_000_000_000, "500ms", 2.minutes) compare
+/// numerically.
+fn normalize_limits(opts: RHash) -> Result<RHash, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let normalized: RHash = opts.funcall("dup", ())?;
+    if let Some(seconds) = get_optional_duration(&opts, "max_duration")? {
+        normalized.aset(ruby.to_symbol("max_duration"), seconds)?;
+    }
+    for key in BYTE_SIZE_KEYS {
+        if let Some(bytes) = get_optional_byte_size(&opts, key)? {
+            normalized.aset(ruby.to_symbol(key), bytes)?;
+        }
+    }
+    Ok(normalized)
+}
+
 pub fn define_resource_limits_class(ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
     let class = module.define_class("ResourceLimits", ruby.class_object())?;
 
     class.define_singleton_method("new", function!(ResourceLimits::new, 1))?;
+    module.define_singleton_method("_normalize_limits", function!(normalize_limits, 1))?;
 
     Ok(())
 }
 
+/// Keys accepted in a limits hash. Anything else raises ArgumentError
+/// unless the hash contains `strict: false`.
+const LIMIT_KEYS: &[&str] = &[
+    "max_allocations",
+    "max_duration",
+    "max_memory",
+    "gc_interval",
+    "max_recursion_depth",
+    "max_pending_futures",
+    "max_tool_result_bytes",
+    "max_result_bytes",
+    "max_result_items",
+    "result_integer_overflow",
+    "strict",
+];
+
 pub fn parse_limits_hash(opts: &RHash) -> Result<monty_lang::ResourceLimits, Error> {
+    check_unknown_keys(opts)?;
+
     let mut limits = monty_lang::ResourceLimits::new();
 
     if let Some(val) = get_optional_usize(opts, "max_allocations")? {
         limits = limits.max_allocations(val);
     }
-    if let Some(val) = get_optional_f64(opts, "max_duration")? {
-        limits = limits.max_duration(Duration::from_secs_f64(val));
+    if let Some(val) = parse_max_duration(opts)? {
+        limits = limits.max_duration(val);
     }
-    if let Some(val) = get_optional_usize(opts, "max_memory")? {
+    if let Some(val) = get_optional_byte_size(opts, "max_memory")? {
         limits = limits.max_memory(val);
     }
     if let Some(val) = get_optional_usize(opts, "gc_interval")? {
@@ -65,6 +93,92 @@ pub fn parse_limits_hash(opts: &RHash) -> Result<monty_lang::ResourceLimits, Err
     Ok(limits)
 }
 
+/// Read `max_duration` on its own, for callers that need to combine it
+/// with another time budget.
+pub fn parse_max_duration(opts: &RHash) -> Result<Option<Duration>, Error> {
+    get_optional_duration(opts, "max_duration")?
+        .map(|secs| {
+            Duration::try_from_secs_f64(secs).map_err(|_| {
+                let ruby = Ruby::get().expect("Ruby runtime not available");
+                Error::new(
+                    ruby.exception_arg_error(),
+                    format!("invalid duration for max_duration: {secs}s is out of range"),
+                )
+            })
+        })
+        .transpose()
+}
+
+/// Read `max_pending_futures`, which the binding enforces itself when
+/// execution pauses on pending futures.
+pub fn parse_max_pending_futures(opts: &RHash) -> Result<Option<usize>, Error> {
+    get_optional_usize(opts, "max_pending_futures")
+}
+
+/// Read `max_tool_result_bytes`, which the binding checks on values
+/// passed to `resume` before they enter the sandbox.
+pub fn parse_max_tool_result_bytes(opts: &RHash) -> Result<Option<usize>, Error> {
+    get_optional_byte_size(opts, "max_tool_result_bytes")
+}
+
+/// Read `max_result_bytes`, `max_result_items` and
+/// `result_integer_overflow`, checked by the binding before a result is
+/// converted to Ruby.
+pub fn parse_result_limits(opts: &RHash) -> Result<ResultLimits, Error> {
+    Ok(ResultLimits {
+        max_bytes: get_optional_byte_size(opts, "max_result_bytes")?,
+        max_items: get_optional_usize(opts, "max_result_items")?,
+        bounded_ints: parse_result_integer_overflow(opts)?,
+    })
+}
+
+/// `result_integer_overflow: :promote` (the default) returns ints of any
+/// size; `:raise` rejects a result holding an int that does not fit in 64
+/// bits. Only the final result is checked: inputs, values passed to and
+/// from external functions and intermediate values are not bounded.
+fn parse_result_integer_overflow(opts: &RHash) -> Result<bool, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let val: Value = opts.aref(ruby.to_symbol("result_integer_overflow"))?;
+    if val.is_nil() {
+        return Ok(false);
+    }
+    match val.funcall::<_, _, String>("to_s", ())?.as_str() {
+        "promote" => Ok(false),
+        "raise" => Ok(true),
+        other => Err(Error::new(
+            ruby.exception_arg_error(),
+            format!("result_integer_overflow must be :promote or :raise, got :{other}"),
+        )),
+    }
+}
+
+fn check_unknown_keys(opts: &RHash) -> Result<(), Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let strict: Value = opts.aref(ruby.to_symbol("strict"))?;
+    if strict.is_kind_of(ruby.class_false_class()) {
+        return Ok(());
+    }
+
+    let mut unknown = Vec::new();
+    opts.foreach(|key: Value, _: Value| {
+        let known = key.is_kind_of(ruby.class_symbol())
+            && LIMIT_KEYS.contains(&key.funcall::<_, _, String>("to_s", ())?.as_str());
+        if !known {
+            unknown.push(key.inspect());
+        }
+        Ok(ForEach::Continue)
+    })?;
+
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ruby.exception_arg_error(),
+            format!("unknown limit keys: {}", unknown.join(", ")),
+        ))
+    }
+}
+
 fn get_optional_usize(hash: &RHash, key: &str) -> Result<Option<usize>, Error> {
     let ruby = Ruby::get().expect("Ruby runtime not available");
     let sym = ruby.to_symbol(key);
@@ -76,13 +190,95 @@ fn get_optional_usize(hash: &RHash, key: &str) -> Result<Option<usize>, Error> {
     }
 }
 
-fn get_optional_f64(hash: &RHash, key: &str) -> Result<Option<f64>, Error> {
+/// Seconds as a number, a String with a unit ("500ms", "2.5s", "1m",
+/// "1h"), or anything responding to `to_f` such as ActiveSupport::Duration.
+/// Negative, NaN and infinite values raise ArgumentError.
+fn get_optional_duration(hash: &RHash, key: &str) -> Result<Option<f64>, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let sym = ruby.to_symbol(key);
+    let val: Value = hash.aref(sym)?;
+    let secs = if val.is_nil() {
+        return Ok(None);
+    } else if val.is_kind_of(ruby.class_string()) {
+        let s = String::try_convert(val)?;
+        parse_duration(&s).ok_or_else(|| {
+            Error::new(
+                ruby.exception_arg_error(),
+                format!("invalid duration for {key}: {s:?}"),
+            )
+        })?
+    } else if val.is_kind_of(ruby.class_numeric()) {
+        f64::try_convert(val)?
+    } else {
+        val.funcall("to_f", ())?
+    };
+    if !secs.is_finite() || secs < 0.0 {
+        return Err(Error::new(
+            ruby.exception_arg_error(),
+            format!("invalid duration for {key}: {secs} is not a finite, non-negative number of seconds"),
+        ));
+    }
+    Ok(Some(secs))
+}
+
+/// Bytes as an Integer or a String with a unit ("512MB", "1.5GiB").
+fn get_optional_byte_size(hash: &RHash, key: &str) -> Result<Option<usize>, Error> {
     let ruby = Ruby::get().expect("Ruby runtime not available");
     let sym = ruby.to_symbol(key);
     let val: Value = hash.aref(sym)?;
     if val.is_nil() {
         Ok(None)
+    } else if val.is_kind_of(ruby.class_string()) {
+        let s = String::try_convert(val)?;
+        parse_byte_size(&s).map(Some).ok_or_else(|| {
+            Error::new(
+                ruby.exception_arg_error(),
+                format!("invalid byte size for {key}: {s:?}"),
+            )
+        })
     } else {
-        Ok(Some(f64::try_convert(val)?))
+        Ok(Some(usize::try_convert(val)?))
+    }
+}
+
+/// Split "2.5s" into (2.5, "s"), allowing whitespace before the unit.
+fn split_quantity(s: &str) -> Option<(f64, String)> {
+    let s = s.trim();
+    let unit_start = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_'))
+        .unwrap_or(s.len());
+    let number: f64 = s[..unit_start].replace('_', "").parse().ok()?;
+    if number < 0.0 {
+        return None;
     }
+    Some((number, s[unit_start..].trim().to_ascii_lowercase()))
+}
+
+fn parse_duration(s: &str) -> Option<f64> {
+    let (number, unit) = split_quantity(s)?;
+    let scale = match unit.as_str() {
+        "ms" => 0.001,
+        "" | "s" | "sec" | "secs" | "seconds" => 1.0,
+        "m" | "min" | "mins" | "minutes" => 60.0,
+        "h" | "hr" | "hours" => 3600.0,
+        _ => return None,
+    };
+    Some(number * scale)
+}
+
+/// Decimal units (KB, MB, GB) are powers of 1000 and binary units
+/// (KiB, MiB, GiB) are powers of 1024.
+fn parse_byte_size(s: &str) -> Option<usize> {
+    let (number, unit) = split_quantity(s)?;
+    let scale: f64 = match unit.as_str() {
+        "" | "b" => 1.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * scale) as usize)
 }
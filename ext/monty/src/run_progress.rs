@@ -1,13 +1,29 @@
 use magnus::value::ReprValue;
-use magnus::{method, Error, Module, RArray, Ruby, Value};
+use magnus::{function, method, Error, Module, Object, RArray, RHash, RString, Ruby, Value};
 use monty_lang::{
     CollectStringPrint, ExternalResult, FutureSnapshot, LimitedTracker, MontyObject, NoLimitTracker,
     RunProgress, Snapshot,
 };
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::errors::{consumed_error, map_monty_exception, monty_error};
-use crate::monty_object::{monty_to_ruby, ruby_to_monty};
+use crate::control::{aborted_since, current_epoch, register_run, ActiveRun};
+use crate::errors::{
+    consumed_error, interrupted_error, map_monty_exception, monty_error, resource_error,
+};
+use crate::intrinsics;
+use crate::monty_object::{
+    fits_in_bytes, monty_to_ruby, rows_to_columns, ruby_to_monty, ResultLimits,
+};
+use crate::object_handle::ObjectHandle;
+use crate::output_reader::OutputReader;
+use crate::persistence::{unwrap_checking_expiry, wrap_with_expiry};
+use crate::repr::{capture_limit, py_repr_bounded, truncate, ReprWriter};
+use crate::resource_limits::{
+    parse_max_pending_futures, parse_max_tool_result_bytes, parse_result_limits,
+};
 
 /// Ruby wrapper for RunProgress - represents the state of iterative execution.
 ///
@@ -20,11 +36,12 @@ pub struct FunctionCall {
     kwargs: Vec<(MontyObject, MontyObject)>,
     call_id: u32,
     output: String,
+    context: ProgressContext,
     state: RefCell<Option<SnapshotState>>,
 }
 
 impl FunctionCall {
-    fn function_name(&self) -> String {
+    pub fn function_name(&self) -> String {
         self.function_name.clone()
     }
 
@@ -32,11 +49,32 @@ impl FunctionCall {
         self.call_id
     }
 
+    /// Id of the run in `Monty.active_runs`.
+    fn run_id(&self) -> Option<u64> {
+        self.context.run_id()
+    }
+
+    /// The call's arguments as Python source (`'x', retries=2`), for
+    /// logging. Captured with headroom past `Monty.max_repr_length`; the
+    /// Ruby wrapper redacts it and then cuts it to the limit.
+    fn args_repr(&self) -> String {
+        call_args_repr(&self.args, &self.kwargs)
+    }
+
     fn output(&self) -> String {
         self.output.clone()
     }
 
-    fn args(&self) -> Result<Value, Error> {
+    fn stats(&self) -> Result<RHash, Error> {
+        self.context.stats.to_hash()
+    }
+
+    /// Timeline of the run so far, or nil unless started with trace: true.
+    fn trace_events(&self) -> Result<Option<RArray>, Error> {
+        self.context.trace_events()
+    }
+
+    pub fn args(&self) -> Result<Value, Error> {
         let ruby = Ruby::get().expect("Ruby runtime not available");
         let arr = ruby.ary_new_capa(self.args.len());
         for arg in &self.args {
@@ -45,7 +83,7 @@ impl FunctionCall {
         Ok(arr.as_value())
     }
 
-    fn kwargs(&self) -> Result<Value, Error> {
+    pub fn kwargs(&self) -> Result<Value, Error> {
         let ruby = Ruby::get().expect("Ruby runtime not available");
         let hash = ruby.hash_new();
         for (k, v) in &self.kwargs {
@@ -54,34 +92,94 @@ impl FunctionCall {
         Ok(hash.as_value())
     }
 
+    /// Serialize the paused call, optionally embedding an absolute expiry
+    /// time (Unix seconds) checked by `load`. Does not consume the call.
+    fn dump(&self, expires_at: Option<f64>) -> Result<Vec<u8>, Error> {
+        let state = self.state.borrow();
+        let state = state.as_ref().ok_or_else(consumed_error)?;
+        let dumped = DumpedCall {
+            function_name: self.function_name.clone(),
+            args: self.args.clone(),
+            kwargs: self.kwargs.clone(),
+            call_id: self.call_id,
+            output: self.output.clone(),
+            intrinsics: self.context.intrinsics,
+            state,
+        };
+        let bytes = postcard::to_allocvec(&dumped).map_err(|e| {
+            let ruby = Ruby::get().expect("Ruby runtime not available");
+            Error::new(
+                ruby.exception_runtime_error(),
+                format!("serialization error: {e}"),
+            )
+        })?;
+        Ok(wrap_with_expiry(bytes, expires_at))
+    }
+
+    /// Rebuild a paused call from `dump` output. Interpreter limits travel
+    /// with the snapshot; the binding-side ones (max_pending_futures,
+    /// max_tool_result_bytes, max_result_bytes, max_result_items) are read
+    /// from `limits`.
+    fn load(bytes: Vec<u8>, limits: Option<RHash>) -> Result<Self, Error> {
+        let payload = unwrap_checking_expiry(&bytes)?;
+        let dumped: DumpedCall<SnapshotState> = postcard::from_bytes(payload).map_err(|e| {
+            let ruby = Ruby::get().expect("Ruby runtime not available");
+            Error::new(
+                ruby.exception_runtime_error(),
+                format!("deserialization error: {e}"),
+            )
+        })?;
+
+        let mut context = ProgressContext {
+            abort_epoch: current_epoch(),
+            run: Some(register_run(None)),
+            intrinsics: dumped.intrinsics,
+            ..ProgressContext::default()
+        };
+        if let Some(limits) = &limits {
+            context.max_pending_futures = parse_max_pending_futures(limits)?;
+            context.max_tool_result_bytes = parse_max_tool_result_bytes(limits)?;
+            context.result_limits = parse_result_limits(limits)?;
+        }
+        if let Some(run) = &context.run {
+            run.set_running(false);
+        }
+
+        Ok(Self {
+            function_name: dumped.function_name,
+            args: dumped.args,
+            kwargs: dumped.kwargs,
+            call_id: dumped.call_id,
+            output: dumped.output,
+            context,
+            state: RefCell::new(Some(dumped.state)),
+        })
+    }
+
     /// Resume execution by providing the return value of the external function.
-    /// Consumes this FunctionCall — it cannot be used again.
+    /// Consumes this FunctionCall — it cannot be used again, unless the
+    /// value was rejected (e.g. by max_tool_result_bytes).
     fn resume(&self, result: Value) -> Result<Progress, Error> {
+        if self.state.borrow().is_none() {
+            return Err(consumed_error());
+        }
+        // Converted and size-checked before the snapshot is taken, so a
+        // rejected value leaves the call resumable with another one.
+        let converting = Instant::now();
+        let monty_result = self.context.convert_tool_result(result)?;
         let snapshot = self
             .state
             .borrow_mut()
             .take()
             .ok_or_else(consumed_error)?;
-
-        let monty_result = ruby_to_monty(result)?;
-        let mut print = CollectStringPrint::new();
-
-        let progress = match snapshot {
-            SnapshotState::NoLimit(snapshot) => {
-                let progress = snapshot
-                    .run(monty_result, &mut print)
-                    .map_err(map_monty_exception)?;
-                Progress::from_run_progress_no_limit(progress, print.into_output())
-            }
-            SnapshotState::Limited(snapshot) => {
-                let progress = snapshot
-                    .run(monty_result, &mut print)
-                    .map_err(map_monty_exception)?;
-                Progress::from_run_progress_limited(progress, print.into_output())
-            }
-        }?;
-
-        Ok(progress)
+        self.context.begin_segment(&self.output)?;
+        self.context.trace_wait(&self.function_name);
+        self.context
+            .trace("convert result", "conversion", converting);
+        self.context
+            .log_result(py_repr_bounded(&monty_result, capture_limit()));
+        self.continue_run(snapshot, ExternalResult::Return(monty_result))?
+            .settle_intrinsics()
     }
 
     /// Resume execution by raising an exception in the Python code.
@@ -92,35 +190,123 @@ impl FunctionCall {
             .borrow_mut()
             .take()
             .ok_or_else(consumed_error)?;
+        self.context.begin_segment(&self.output)?;
+        self.context.trace_wait(&self.function_name);
 
+        self.context
+            .log_result(format!("raise RuntimeError({message:?})"));
         let exc =
             monty_lang::MontyException::new(monty_lang::ExcType::RuntimeError, Some(message));
+        self.continue_run(snapshot, ExternalResult::Error(exc))?
+            .settle_intrinsics()
+    }
+
+    /// Resume execution leaving this call pending as a future, to be
+    /// resolved later through `PendingFutures#resume` with its call_id.
+    /// Consumes this FunctionCall — it cannot be used again.
+    fn defer(&self) -> Result<Progress, Error> {
+        let snapshot = self
+            .state
+            .borrow_mut()
+            .take()
+            .ok_or_else(consumed_error)?;
+        self.context.begin_segment(&self.output)?;
+        self.context.trace_wait(&self.function_name);
+
+        self.context.log_result("<future>".to_string());
+        self.continue_run(snapshot, ExternalResult::Future)?
+            .settle_intrinsics()
+    }
+
+    /// Answer this call in Rust if it names an intrinsic the run opted
+    /// into, returning the progress that follows; None otherwise.
+    fn answer_intrinsic(&self) -> Option<Result<Progress, Error>> {
+        if !self.context.intrinsics {
+            return None;
+        }
+        let answer = intrinsics::call(&self.function_name, &self.args, &self.kwargs)?;
+        Some(self.resume_intrinsic(answer))
+    }
+
+    fn resume_intrinsic(
+        &self,
+        answer: Result<MontyObject, intrinsics::Failure>,
+    ) -> Result<Progress, Error> {
+        let snapshot = self
+            .state
+            .borrow_mut()
+            .take()
+            .ok_or_else(consumed_error)?;
+        self.context.begin_segment(&self.output)?;
+        self.context.trace_wait(&self.function_name);
+
+        let result = match answer {
+            Ok(value) => {
+                self.context
+                    .log_result(py_repr_bounded(&value, capture_limit()));
+                ExternalResult::Return(value)
+            }
+            Err(failure) => {
+                self.context.log_result(failure.describe());
+                ExternalResult::Error(failure.into_exception())
+            }
+        };
+        self.continue_run(snapshot, result)
+    }
+
+    /// Run the interpreter from `snapshot` with the call's `result`.
+    fn continue_run(
+        &self,
+        snapshot: SnapshotState,
+        result: ExternalResult,
+    ) -> Result<Progress, Error> {
         let mut print = CollectStringPrint::new();
+        let started = Instant::now();
 
-        let progress = match snapshot {
+        match snapshot {
             SnapshotState::NoLimit(snapshot) => {
                 let progress = snapshot
-                    .run(ExternalResult::Error(exc), &mut print)
+                    .run(result, &mut print)
                     .map_err(map_monty_exception)?;
-                Progress::from_run_progress_no_limit(progress, print.into_output())
+                Progress::from_run_progress_no_limit(
+                    progress,
+                    print.into_output(),
+                    self.context.after_segment(started),
+                )
             }
             SnapshotState::Limited(snapshot) => {
                 let progress = snapshot
-                    .run(ExternalResult::Error(exc), &mut print)
+                    .run(result, &mut print)
                     .map_err(map_monty_exception)?;
-                Progress::from_run_progress_limited(progress, print.into_output())
+                Progress::from_run_progress_limited(
+                    progress,
+                    print.into_output(),
+                    self.context.after_segment(started),
+                )
             }
-        }?;
-
-        Ok(progress)
+        }
     }
 }
 
+/// Serialized form of a paused FunctionCall; `S` is the snapshot, borrowed
+/// when dumping and owned when loading.
+#[derive(Serialize, Deserialize)]
+struct DumpedCall<S> {
+    function_name: String,
+    args: Vec<MontyObject>,
+    kwargs: Vec<(MontyObject, MontyObject)>,
+    call_id: u32,
+    output: String,
+    intrinsics: bool,
+    state: S,
+}
+
 /// Represents pending async futures that need resolution
 #[magnus::wrap(class = "Monty::PendingFutures", free_immediately, size)]
 pub struct PendingFutures {
     pending_call_ids: Vec<u32>,
     output: String,
+    context: ProgressContext,
     state: RefCell<Option<FutureSnapshotState>>,
 }
 
@@ -138,16 +324,29 @@ impl PendingFutures {
         self.output.clone()
     }
 
+    fn stats(&self) -> Result<RHash, Error> {
+        self.context.stats.to_hash()
+    }
+
+    /// Timeline of the run so far, or nil unless started with trace: true.
+    fn trace_events(&self) -> Result<Option<RArray>, Error> {
+        self.context.trace_events()
+    }
+
+    /// Id of the run in `Monty.active_runs`.
+    fn run_id(&self) -> Option<u64> {
+        self.context.run_id()
+    }
+
     /// Resume execution by providing results for pending futures.
     /// `results` is an Array of [call_id, value] pairs.
     /// Consumes this PendingFutures — it cannot be used again.
     fn resume(&self, results: RArray) -> Result<Progress, Error> {
-        let snapshot = self
-            .state
-            .borrow_mut()
-            .take()
-            .ok_or_else(consumed_error)?;
+        if self.state.borrow().is_none() {
+            return Err(consumed_error());
+        }
 
+        let converting = Instant::now();
         let mut resolved = Vec::with_capacity(results.len());
         for i in 0..results.len() {
             let pair: RArray = results.entry(i as isize)?;
@@ -158,28 +357,49 @@ impl PendingFutures {
             }
             let call_id: u32 = pair.entry(0)?;
             let value: Value = pair.entry(1)?;
-            let monty_value = ruby_to_monty(value)?;
+            let monty_value = self.context.convert_tool_result(value)?;
             resolved.push((call_id, ExternalResult::Return(monty_value)));
         }
+        // Taken only once every result converted and fit its limit, so a
+        // rejected batch can be retried.
+        let snapshot = self
+            .state
+            .borrow_mut()
+            .take()
+            .ok_or_else(consumed_error)?;
+        self.context.begin_segment(&self.output)?;
+        self.context.trace_wait("pending futures");
+        self.context
+            .trace("convert results", "conversion", converting);
+        self.context.log_result(futures_repr(&resolved));
 
         let mut print = CollectStringPrint::new();
+        let started = Instant::now();
 
         let progress = match snapshot {
             FutureSnapshotState::NoLimit(snapshot) => {
                 let progress = snapshot
                     .resume(resolved, &mut print)
                     .map_err(map_monty_exception)?;
-                Progress::from_run_progress_no_limit(progress, print.into_output())
+                Progress::from_run_progress_no_limit(
+                    progress,
+                    print.into_output(),
+                    self.context.after_segment(started),
+                )
             }
             FutureSnapshotState::Limited(snapshot) => {
                 let progress = snapshot
                     .resume(resolved, &mut print)
                     .map_err(map_monty_exception)?;
-                Progress::from_run_progress_limited(progress, print.into_output())
+                Progress::from_run_progress_limited(
+                    progress,
+                    print.into_output(),
+                    self.context.after_segment(started),
+                )
             }
         }?;
 
-        Ok(progress)
+        progress.settle_intrinsics()
     }
 }
 
@@ -187,21 +407,94 @@ impl PendingFutures {
 #[magnus::wrap(class = "Monty::Complete", free_immediately, size)]
 pub struct Complete {
     result: RefCell<Option<MontyObject>>,
-    output: String,
+    /// Shared with any OutputReaders handed out.
+    output: Arc<String>,
+    context: ProgressContext,
 }
 
 impl Complete {
+    /// Every segment of the run in order, as Hashes with :function and
+    /// :args (the external call the segment paused at, nil for the last
+    /// one), :result (repr of what the host returned), :output and
+    /// :duration. Reprs are captured with headroom past
+    /// `Monty.max_repr_length`; the Ruby wrapper redacts and cuts them.
+    fn segments(&self) -> Result<RArray, Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let history = self.context.history.lock().unwrap();
+        let arr = ruby.ary_new_capa(history.len());
+        for record in history.iter() {
+            arr.push(record.to_hash()?)?;
+        }
+        Ok(arr)
+    }
+
     fn value(&self) -> Result<Value, Error> {
         let obj = self
             .result
             .borrow_mut()
             .take()
             .ok_or_else(consumed_error)?;
-        monty_to_ruby(obj)
+        self.context.result_limits.check(&obj)?;
+        let converting = Instant::now();
+        let value = monty_to_ruby(obj);
+        self.context
+            .trace("convert value", "conversion", converting);
+        value
+    }
+
+    /// Convert a list-of-dicts result into a Hash of column Arrays.
+    /// Consumes the result like `value`.
+    fn to_columns(&self) -> Result<RHash, Error> {
+        let obj = self
+            .result
+            .borrow_mut()
+            .take()
+            .ok_or_else(consumed_error)?;
+        self.context.result_limits.check(&obj)?;
+        rows_to_columns(obj)
+    }
+
+    /// Encode a list-of-dicts result as Arrow IPC stream bytes.
+    /// Consumes the result like `value`.
+    #[cfg(feature = "arrow")]
+    fn to_arrow_ipc(&self) -> Result<RString, Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let obj = self
+            .result
+            .borrow_mut()
+            .take()
+            .ok_or_else(consumed_error)?;
+        self.context.result_limits.check(&obj)?;
+        let bytes = crate::arrow_export::rows_to_arrow_ipc(obj)?;
+        Ok(ruby.str_from_slice(&bytes))
+    }
+
+    /// Take the result as an unconverted Monty::Object handle.
+    fn object(&self) -> Result<ObjectHandle, Error> {
+        let obj = self
+            .result
+            .borrow_mut()
+            .take()
+            .ok_or_else(consumed_error)?;
+        Ok(ObjectHandle::new(obj))
     }
 
     fn output(&self) -> String {
-        self.output.clone()
+        self.output.to_string()
+    }
+
+    /// The output as a read-only IO-like object sharing this buffer.
+    fn output_reader(&self) -> OutputReader {
+        OutputReader::new(Arc::clone(&self.output))
+    }
+
+    fn stats(&self) -> Result<RHash, Error> {
+        self.context.stats.to_hash()
+    }
+
+    /// Timeline of the run so far, or nil unless started with trace: true.
+    fn trace_events(&self) -> Result<Option<RArray>, Error> {
+        self.context.trace_events()
     }
 }
 
@@ -213,103 +506,493 @@ pub enum Progress {
 }
 
 impl Progress {
+    /// Answer intrinsic calls until the run pauses on a host function,
+    /// pending futures or completion. Looping here rather than inside
+    /// `continue_run` keeps long runs of intrinsic calls off the stack.
+    pub fn settle_intrinsics(mut self) -> Result<Self, Error> {
+        while let Progress::FunctionCall(call) = &self {
+            match call.answer_intrinsic() {
+                Some(next) => self = next?,
+                None => break,
+            }
+        }
+        Ok(self)
+    }
+
+    /// Append the segment that produced this progress to the run's history.
+    fn log_segment(&self) {
+        let (context, call, output) = match self {
+            Progress::FunctionCall(fc) => (
+                &fc.context,
+                Some((fc.function_name.clone(), call_args_repr(&fc.args, &fc.kwargs))),
+                &fc.output,
+            ),
+            Progress::PendingFutures(pf) => (&pf.context, None, &pf.output),
+            Progress::Complete(c) => (&c.context, None, &*c.output),
+        };
+        context.history.lock().unwrap().push(SegmentRecord {
+            call,
+            result: None,
+            output: output.clone(),
+            duration: context.stats.last_segment_duration,
+        });
+    }
+
     pub fn from_run_progress_no_limit(
         progress: RunProgress<NoLimitTracker>,
         output: String,
+        context: ProgressContext,
     ) -> Result<Self, Error> {
-        match progress {
+        let progress = match progress {
             RunProgress::FunctionCall {
                 function_name,
                 args,
                 kwargs,
                 call_id,
                 state,
-            } => Ok(Progress::FunctionCall(FunctionCall {
+            } => Progress::FunctionCall(FunctionCall {
                 function_name,
                 args,
                 kwargs,
                 call_id,
                 output,
+                context,
                 state: RefCell::new(Some(SnapshotState::NoLimit(state))),
-            })),
+            }),
             RunProgress::OsCall {
                 function,
                 args,
                 kwargs,
                 call_id,
                 state,
-            } => Ok(Progress::FunctionCall(FunctionCall {
+            } => Progress::FunctionCall(FunctionCall {
                 function_name: format!("os:{function:?}"),
                 args,
                 kwargs,
                 call_id,
                 output,
+                context,
                 state: RefCell::new(Some(SnapshotState::NoLimit(state))),
-            })),
+            }),
             RunProgress::ResolveFutures(snapshot) => {
                 let pending_ids = snapshot.pending_call_ids().to_vec();
-                Ok(Progress::PendingFutures(PendingFutures {
+                context.check_pending_futures(pending_ids.len())?;
+                Progress::PendingFutures(PendingFutures {
                     pending_call_ids: pending_ids,
                     output,
+                    context,
                     state: RefCell::new(Some(FutureSnapshotState::NoLimit(snapshot))),
-                }))
+                })
             }
-            RunProgress::Complete(obj) => Ok(Progress::Complete(Complete {
+            RunProgress::Complete(obj) => Progress::Complete(Complete {
                 result: RefCell::new(Some(obj)),
-                output,
-            })),
-        }
+                output: Arc::new(output),
+                context: context.finished(),
+            }),
+        };
+        progress.log_segment();
+        Ok(progress)
     }
 
     pub fn from_run_progress_limited(
         progress: RunProgress<LimitedTracker>,
         output: String,
+        context: ProgressContext,
     ) -> Result<Self, Error> {
-        match progress {
+        let progress = match progress {
             RunProgress::FunctionCall {
                 function_name,
                 args,
                 kwargs,
                 call_id,
                 state,
-            } => Ok(Progress::FunctionCall(FunctionCall {
+            } => Progress::FunctionCall(FunctionCall {
                 function_name,
                 args,
                 kwargs,
                 call_id,
                 output,
+                context,
                 state: RefCell::new(Some(SnapshotState::Limited(state))),
-            })),
+            }),
             RunProgress::OsCall {
                 function,
                 args,
                 kwargs,
                 call_id,
                 state,
-            } => Ok(Progress::FunctionCall(FunctionCall {
+            } => Progress::FunctionCall(FunctionCall {
                 function_name: format!("os:{function:?}"),
                 args,
                 kwargs,
                 call_id,
                 output,
+                context,
                 state: RefCell::new(Some(SnapshotState::Limited(state))),
-            })),
+            }),
             RunProgress::ResolveFutures(snapshot) => {
                 let pending_ids = snapshot.pending_call_ids().to_vec();
-                Ok(Progress::PendingFutures(PendingFutures {
+                context.check_pending_futures(pending_ids.len())?;
+                Progress::PendingFutures(PendingFutures {
                     pending_call_ids: pending_ids,
                     output,
+                    context,
                     state: RefCell::new(Some(FutureSnapshotState::Limited(snapshot))),
-                }))
+                })
             }
-            RunProgress::Complete(obj) => Ok(Progress::Complete(Complete {
+            RunProgress::Complete(obj) => Progress::Complete(Complete {
                 result: RefCell::new(Some(obj)),
-                output,
-            })),
+                output: Arc::new(output),
+                context: context.finished(),
+            }),
+        };
+        progress.log_segment();
+        Ok(progress)
+    }
+}
+
+/// Resume `progress` with the values `handler` returns for each external
+/// call until the run completes, returning the converted result.
+///
+/// Runs that pause on pending futures cannot be driven this way and raise
+/// Monty::Error.
+pub fn drive(
+    mut progress: Progress,
+    mut handler: impl FnMut(&FunctionCall) -> Result<Value, Error>,
+) -> Result<Value, Error> {
+    loop {
+        progress = match progress {
+            Progress::FunctionCall(call) => {
+                let value = handler(&call)?;
+                call.resume(value)?
+            }
+            Progress::PendingFutures(_) => {
+                return Err(monty_error(
+                    "async futures are not supported by execute".to_string(),
+                ))
+            }
+            Progress::Complete(complete) => return complete.value(),
+        };
+    }
+}
+
+/// Host-side options carried from `start` through every resumed progress.
+#[derive(Clone, Default)]
+pub struct ProgressContext {
+    pub max_pending_futures: Option<usize>,
+    pub max_tool_result_bytes: Option<usize>,
+    pub result_limits: ResultLimits,
+    /// `control::current_epoch()` when the run started.
+    pub abort_epoch: u64,
+    /// Registry entry listing the run in `Monty.active_runs` while it is
+    /// paused or executing.
+    pub run: Option<Arc<ActiveRun>>,
+    /// Answer calls to `intrinsics::NAMES` without returning to the host.
+    pub intrinsics: bool,
+    /// Shared by every progress object of the run when tracing is on.
+    pub timeline: Option<Arc<Mutex<Timeline>>>,
+    /// Shared by every progress object of the run; see `Complete#segments`.
+    history: Arc<Mutex<Vec<SegmentRecord>>>,
+    stats: ExecutionStats,
+}
+
+impl ProgressContext {
+    /// Record an interpreter segment that began at `started` and return the
+    /// context to hand to the resulting progress object.
+    pub fn after_segment(&self, started: Instant) -> Self {
+        let mut next = self.clone();
+        next.stats.record_segment(started);
+        next.trace(
+            format!("segment {}", next.stats.segments),
+            "interpreter",
+            started,
+        );
+        if let Some(run) = &next.run {
+            run.set_running(false);
+        }
+        next
+    }
+
+    /// Drop the registry entry once execution has completed.
+    fn finished(mut self) -> Self {
+        self.run = None;
+        self
+    }
+
+    /// Called before resuming: raise Monty::Interrupted if the run was
+    /// cancelled or `Monty.abort_all!` was called since it started,
+    /// otherwise mark it running again.
+    fn begin_segment(&self, output: &str) -> Result<(), Error> {
+        let message = if aborted_since(self.abort_epoch) {
+            "run aborted by Monty.abort_all!"
+        } else if self.run.as_ref().is_some_and(|run| run.is_cancelled()) {
+            "run cancelled by Monty.cancel_run"
+        } else {
+            if let Some(run) = &self.run {
+                run.set_running(true);
+            }
+            return Ok(());
+        };
+        Err(interrupted_error(
+            message.to_string(),
+            output.to_string(),
+            self.stats.to_hash()?,
+        ))
+    }
+
+    fn run_id(&self) -> Option<u64> {
+        self.run.as_ref().map(|run| run.id())
+    }
+
+    /// Add a span from `started` until now to the timeline, if tracing.
+    pub fn trace(&self, name: impl Into<String>, category: &'static str, started: Instant) {
+        if let Some(timeline) = &self.timeline {
+            timeline
+                .lock()
+                .unwrap()
+                .record(name.into(), category, started);
+        }
+    }
+
+    /// Trace the time the host spent before resuming, labelled `name`.
+    fn trace_wait(&self, name: &str) {
+        if let Some(paused_at) = self.stats.paused_at {
+            self.trace(name, "host", paused_at);
+        }
+    }
+
+    fn trace_events(&self) -> Result<Option<RArray>, Error> {
+        self.timeline
+            .as_ref()
+            .map(|timeline| timeline.lock().unwrap().to_ruby())
+            .transpose()
+    }
+
+    /// Record what the host answered to the pause ending the last segment.
+    fn log_result(&self, result: String) {
+        if let Some(last) = self.history.lock().unwrap().last_mut() {
+            last.result = Some(truncate(result, capture_limit()));
+        }
+    }
+
+    /// Convert a value passed to `resume`, enforcing `max_tool_result_bytes`.
+    ///
+    /// An oversized value is handed to `Monty.tool_result_summarizer`, if
+    /// one is set, together with the limit; whatever it returns must fit.
+    fn convert_tool_result(&self, value: Value) -> Result<MontyObject, Error> {
+        let obj = ruby_to_monty(value)?;
+        let Some(limit) = self.max_tool_result_bytes else {
+            return Ok(obj);
+        };
+        if fits_in_bytes(&obj, limit) {
+            return Ok(obj);
+        }
+
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let summarizer: Value = ruby
+            .define_module("Monty")?
+            .funcall("tool_result_summarizer", ())?;
+        if summarizer.is_nil() {
+            return Err(resource_error(format!(
+                "tool result too large: more than {limit} bytes"
+            )));
+        }
+        let summary = ruby_to_monty(summarizer.funcall("call", (value, limit))?)?;
+        if fits_in_bytes(&summary, limit) {
+            Ok(summary)
+        } else {
+            Err(resource_error(format!(
+                "tool result too large: more than {limit} bytes after summarizing"
+            )))
+        }
+    }
+
+    fn check_pending_futures(&self, pending: usize) -> Result<(), Error> {
+        match self.max_pending_futures {
+            Some(limit) if pending > limit => Err(resource_error(format!(
+                "pending futures limit exceeded: {pending} pending (limit: {limit})"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Resources consumed so far, accumulated across start/resume segments.
+///
+/// Timing is measured by the binding around each interpreter segment; the
+/// interpreter does not expose its tracker counters on paused snapshots.
+/// `duration` only counts time spent in the interpreter, while time between
+/// a pause and the host resuming is kept separately as suspended time.
+#[derive(Clone, Default)]
+struct ExecutionStats {
+    duration: Duration,
+    last_segment_duration: Duration,
+    suspended_duration: Duration,
+    segments: u32,
+    paused_at: Option<Instant>,
+}
+
+impl ExecutionStats {
+    fn record_segment(&mut self, started: Instant) {
+        if let Some(paused_at) = self.paused_at {
+            self.suspended_duration += started.saturating_duration_since(paused_at);
+        }
+        let elapsed = started.elapsed();
+        self.duration += elapsed;
+        self.last_segment_duration = elapsed;
+        self.segments += 1;
+        self.paused_at = Some(Instant::now());
+    }
+
+    fn to_hash(&self) -> Result<RHash, Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("duration"), self.duration.as_secs_f64())?;
+        hash.aset(
+            ruby.to_symbol("last_segment_duration"),
+            self.last_segment_duration.as_secs_f64(),
+        )?;
+        hash.aset(
+            ruby.to_symbol("suspended_duration"),
+            self.suspended_duration.as_secs_f64(),
+        )?;
+        hash.aset(ruby.to_symbol("segments"), self.segments)?;
+        Ok(hash)
+    }
+}
+
+/// One interpreter segment of an iterative run: the external call it
+/// paused at (if any), what the host returned, and what it printed.
+struct SegmentRecord {
+    call: Option<(String, String)>,
+    result: Option<String>,
+    output: String,
+    duration: Duration,
+}
+
+impl SegmentRecord {
+    fn to_hash(&self) -> Result<RHash, Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let (function, args) = match &self.call {
+            Some((function, args)) => (Some(function.as_str()), Some(args.as_str())),
+            None => (None, None),
+        };
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("function"), function)?;
+        hash.aset(ruby.to_symbol("args"), args)?;
+        hash.aset(ruby.to_symbol("result"), self.result.as_deref())?;
+        hash.aset(ruby.to_symbol("output"), self.output.as_str())?;
+        hash.aset(ruby.to_symbol("duration"), self.duration.as_secs_f64())?;
+        Ok(hash)
+    }
+}
+
+/// Results handed to `PendingFutures#resume`, as `{call_id: value}`.
+fn futures_repr(resolved: &[(u32, ExternalResult)]) -> String {
+    let mut out = ReprWriter::new(capture_limit());
+    out.push('{');
+    for (i, (call_id, result)) in resolved.iter().enumerate() {
+        if out.is_full() {
+            break;
+        }
+        if i > 0 {
+            out.push_str(", ");
+        }
+        out.push_str(&format!("{call_id}: "));
+        match result {
+            ExternalResult::Return(value) => out.write(value),
+            _ => out.push_str("<error>"),
+        }
+    }
+    out.push('}');
+    out.finish()
+}
+
+/// Arguments of an external call as they would be written in Python,
+/// e.g. `1, 'a', key=2`.
+fn call_args_repr(args: &[MontyObject], kwargs: &[(MontyObject, MontyObject)]) -> String {
+    let mut out = ReprWriter::new(capture_limit());
+    for (i, value) in args.iter().enumerate() {
+        if out.is_full() {
+            break;
+        }
+        if i > 0 {
+            out.push_str(", ");
+        }
+        out.write(value);
+    }
+    for (i, (key, value)) in kwargs.iter().enumerate() {
+        if out.is_full() {
+            break;
+        }
+        if i > 0 || !args.is_empty() {
+            out.push_str(", ");
+        }
+        match key {
+            MontyObject::String(name) => out.push_str(name),
+            other => out.write(other),
+        }
+        out.push('=');
+        out.write(value);
+    }
+    out.finish()
+}
+
+/// Spans recorded for `Run#start(trace: true)`, exported in the Chrome
+/// trace event format (complete "X" events, microsecond timestamps).
+///
+/// Covers what the binding can observe: interpreter segments, host time
+/// between a pause and the resume, and Ruby <-> Monty conversions. The
+/// interpreter's own garbage collection happens inside segments and is
+/// not broken out.
+pub struct Timeline {
+    origin: Instant,
+    events: Vec<TraceEvent>,
+}
+
+struct TraceEvent {
+    name: String,
+    category: &'static str,
+    start: Duration,
+    duration: Duration,
+}
+
+impl Timeline {
+    pub fn new() -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            origin: Instant::now(),
+            events: Vec::new(),
+        }))
+    }
+
+    fn record(&mut self, name: String, category: &'static str, started: Instant) {
+        self.events.push(TraceEvent {
+            name,
+            category,
+            start: started.saturating_duration_since(self.origin),
+            duration: started.elapsed(),
+        });
+    }
+
+    fn to_ruby(&self) -> Result<RArray, Error> {
+        let ruby = Ruby::get().expect("Ruby runtime not available");
+        let arr = ruby.ary_new_capa(self.events.len());
+        for event in &self.events {
+            let hash = ruby.hash_new();
+            hash.aset(ruby.to_symbol("name"), event.name.as_str())?;
+            hash.aset(ruby.to_symbol("cat"), event.category)?;
+            hash.aset(ruby.to_symbol("ph"), "X")?;
+            hash.aset(ruby.to_symbol("ts"), event.start.as_micros() as u64)?;
+            hash.aset(ruby.to_symbol("dur"), event.duration.as_micros() as u64)?;
+            hash.aset(ruby.to_symbol("pid"), 1)?;
+            hash.aset(ruby.to_symbol("tid"), 1)?;
+            arr.push(hash)?;
         }
+        Ok(arr)
     }
 }
 
+#[derive(Serialize, Deserialize)]
 enum SnapshotState {
     NoLimit(Snapshot<NoLimitTracker>),
     Limited(Snapshot<LimitedTracker>),
@@ -335,14 +1018,21 @@ pub fn define_progress_classes(ruby: &Ruby, module: &magnus::RModule) -> Result<
     let fc_class = module.define_class("FunctionCall", ruby.class_object())?;
     fc_class.define_method("function_name", method!(FunctionCall::function_name, 0))?;
     fc_class.define_method("call_id", method!(FunctionCall::call_id, 0))?;
+    fc_class.define_method("run_id", method!(FunctionCall::run_id, 0))?;
     fc_class.define_method("args", method!(FunctionCall::args, 0))?;
     fc_class.define_method("kwargs", method!(FunctionCall::kwargs, 0))?;
+    fc_class.define_method("_args_repr", method!(FunctionCall::args_repr, 0))?;
     fc_class.define_method("output", method!(FunctionCall::output, 0))?;
+    fc_class.define_method("stats", method!(FunctionCall::stats, 0))?;
+    fc_class.define_method("trace_events", method!(FunctionCall::trace_events, 0))?;
+    fc_class.define_singleton_method("_load", function!(FunctionCall::load, 2))?;
+    fc_class.define_method("_dump", method!(FunctionCall::dump, 1))?;
     fc_class.define_method("resume", method!(FunctionCall::resume, 1))?;
     fc_class.define_method(
         "resume_with_error",
         method!(FunctionCall::resume_with_error, 1),
     )?;
+    fc_class.define_method("defer", method!(FunctionCall::defer, 0))?;
 
     // PendingFutures class
     let pf_class = module.define_class("PendingFutures", ruby.class_object())?;
@@ -351,12 +1041,23 @@ pub fn define_progress_classes(ruby: &Ruby, module: &magnus::RModule) -> Result<
         method!(PendingFutures::pending_call_ids, 0),
     )?;
     pf_class.define_method("output", method!(PendingFutures::output, 0))?;
+    pf_class.define_method("stats", method!(PendingFutures::stats, 0))?;
+    pf_class.define_method("trace_events", method!(PendingFutures::trace_events, 0))?;
+    pf_class.define_method("run_id", method!(PendingFutures::run_id, 0))?;
     pf_class.define_method("resume", method!(PendingFutures::resume, 1))?;
 
     // Complete class
     let complete_class = module.define_class("Complete", ruby.class_object())?;
     complete_class.define_method("value", method!(Complete::value, 0))?;
+    complete_class.define_method("object", method!(Complete::object, 0))?;
+    complete_class.define_method("to_columns", method!(Complete::to_columns, 0))?;
+    #[cfg(feature = "arrow")]
+    complete_class.define_method("to_arrow_ipc", method!(Complete::to_arrow_ipc, 0))?;
     complete_class.define_method("output", method!(Complete::output, 0))?;
+    complete_class.define_method("output_reader", method!(Complete::output_reader, 0))?;
+    complete_class.define_method("stats", method!(Complete::stats, 0))?;
+    complete_class.define_method("trace_events", method!(Complete::trace_events, 0))?;
+    complete_class.define_method("_segments", method!(Complete::segments, 0))?;
 
     Ok(())
 }
@@ -0,0 +1,41 @@
+use magnus::{function, method, Error, Module, Object, Ruby, Value};
+use monty_lang::MontyObject;
+
+use crate::monty_object::{monty_to_ruby, ruby_to_monty};
+
+/// Ruby value converted to a MontyObject once and reused as an input.
+///
+/// Passing a SharedData to a run skips the Ruby-side walk of the value,
+/// which dominates the cost of large reference tables. The interpreter
+/// takes its inputs by value, so the converted object is still copied
+/// (a plain Rust clone) into every run that receives it.
+#[magnus::wrap(class = "Monty::SharedData", free_immediately, size)]
+pub struct SharedData {
+    inner: MontyObject,
+}
+
+impl SharedData {
+    fn new(value: Value) -> Result<Self, Error> {
+        Ok(Self {
+            inner: ruby_to_monty(value)?,
+        })
+    }
+
+    pub fn inner(&self) -> &MontyObject {
+        &self.inner
+    }
+
+    /// Convert the shared value back to a Ruby value.
+    fn to_ruby(&self) -> Result<Value, Error> {
+        monty_to_ruby(self.inner.clone())
+    }
+}
+
+pub fn define_shared_data_class(ruby: &Ruby, module: &magnus::RModule) -> Result<(), Error> {
+    let class = module.define_class("SharedData", ruby.class_object())?;
+
+    class.define_singleton_method("new", function!(SharedData::new, 1))?;
+    class.define_method("to_ruby", method!(SharedData::to_ruby, 0))?;
+
+    Ok(())
+}
@@ -0,0 +1,852 @@
+use magnus::{Error, RArray, RHash, Ruby};
+use std::collections::HashSet;
+
+/// A lexical token from Python source. String literals are kept only as
+/// markers since static checks never need their contents.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Name(String),
+    Op(String),
+    Str,
+    Number(String),
+}
+
+/// One logical line of source: bracketed continuations and backslash
+/// joins are folded in, comments and blank lines are dropped.
+#[derive(Debug)]
+pub struct LogicalLine {
+    pub line: usize,
+    pub indent: usize,
+    pub tokens: Vec<Token>,
+}
+
+impl LogicalLine {
+    pub fn name_at(&self, index: usize) -> Option<&str> {
+        match self.tokens.get(index) {
+            Some(Token::Name(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The statements on this line: split at `;`, and after the header of
+    /// a compound statement whose body shares the line (`if ok: import
+    /// json`). Headers are returned as statements of their own.
+    pub fn statements(&self) -> Vec<&[Token]> {
+        let mut statements = Vec::new();
+        let mut start = 0;
+        let mut depth = 0usize;
+        for (i, token) in self.tokens.iter().enumerate() {
+            let Token::Op(op) = token else { continue };
+            let ends_header = op == ":"
+                && matches!(
+                    self.tokens.get(start),
+                    Some(Token::Name(name)) if COMPOUND_KEYWORDS.contains(&name.as_str())
+                );
+            match op.as_str() {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                ";" if depth == 0 => {
+                    statements.push(&self.tokens[start..i]);
+                    start = i + 1;
+                }
+                ":" if depth == 0 && ends_header => {
+                    statements.push(&self.tokens[start..=i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        if start < self.tokens.len() {
+            statements.push(&self.tokens[start..]);
+        }
+        statements
+    }
+}
+
+/// Keywords opening a compound statement, whose header ends at a colon.
+const COMPOUND_KEYWORDS: &[&str] = &[
+    "if", "elif", "else", "for", "while", "try", "except", "finally", "with", "def", "class",
+    "async", "match", "case",
+];
+
+const MULTI_CHAR_OPS: &[&str] = &[
+    "**=", "//=", ">>=", "<<=", "...", "==", "!=", "<=", ">=", ":=", "->", "**", "//", "<<", ">>",
+    "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "@=",
+];
+
+/// Split Python source into logical lines of tokens.
+///
+/// This is a best-effort lexer for static inspection, not a validator:
+/// the interpreter's own parser has already accepted the code.
+pub fn logical_lines(code: &str) -> Vec<LogicalLine> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut lines = Vec::new();
+    let mut current: Option<LogicalLine> = None;
+    let mut depth = 0usize;
+    let mut line = 1usize;
+    let mut at_line_start = true;
+    let mut indent = 0usize;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if at_line_start {
+            if c == ' ' || c == '\t' {
+                indent += 1;
+                i += 1;
+                continue;
+            }
+            at_line_start = false;
+        }
+
+        match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+                if depth == 0 {
+                    if let Some(done) = current.take() {
+                        lines.push(done);
+                    }
+                    at_line_start = true;
+                    indent = 0;
+                }
+            }
+            '\\' if chars.get(i + 1) == Some(&'\n') => {
+                line += 1;
+                i += 2;
+            }
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            c if c.is_whitespace() => i += 1,
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let token = if is_string_prefix(&word) && matches!(chars.get(i), Some('"' | '\'')) {
+                    i = skip_string(&chars, i, &mut line);
+                    Token::Str
+                } else {
+                    Token::Name(word)
+                };
+                push_token(&mut current, line, indent, token);
+            }
+            '"' | '\'' => {
+                let start_line = line;
+                i = skip_string(&chars, i, &mut line);
+                push_token(&mut current, start_line, indent, Token::Str);
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) =>
+            {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || chars[i] == '.' || chars[i] == '_')
+                {
+                    let exponent = matches!(chars[i], 'e' | 'E');
+                    i += 1;
+                    if exponent && matches!(chars.get(i), Some('+' | '-')) {
+                        i += 1;
+                    }
+                }
+                let literal: String = chars[start..i].iter().collect();
+                push_token(&mut current, line, indent, Token::Number(literal));
+            }
+            _ => {
+                let op = MULTI_CHAR_OPS
+                    .iter()
+                    .find(|op| chars[i..].starts_with(&op.chars().collect::<Vec<_>>()))
+                    .map(|op| op.to_string())
+                    .unwrap_or_else(|| c.to_string());
+                match op.as_str() {
+                    "(" | "[" | "{" => depth += 1,
+                    ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                i += op.chars().count();
+                push_token(&mut current, line, indent, Token::Op(op));
+            }
+        }
+    }
+
+    if let Some(done) = current.take() {
+        lines.push(done);
+    }
+    lines
+}
+
+fn push_token(current: &mut Option<LogicalLine>, line: usize, indent: usize, token: Token) {
+    current
+        .get_or_insert_with(|| LogicalLine {
+            line,
+            indent,
+            tokens: Vec::new(),
+        })
+        .tokens
+        .push(token);
+}
+
+fn is_string_prefix(word: &str) -> bool {
+    word.len() <= 2 && word.chars().all(|c| "rRbBuUfF".contains(c))
+}
+
+/// Skip a string literal starting at the opening quote, returning the
+/// index just past its closing quote.
+fn skip_string(chars: &[char], start: usize, line: &mut usize) -> usize {
+    let quote = chars[start];
+    let triple = chars.get(start + 1) == Some(&quote) && chars.get(start + 2) == Some(&quote);
+    let mut i = if triple { start + 3 } else { start + 1 };
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                if chars.get(i + 1) == Some(&'\n') {
+                    *line += 1;
+                }
+                i += 2;
+            }
+            '\n' => {
+                *line += 1;
+                i += 1;
+                if !triple {
+                    return i;
+                }
+            }
+            c if c == quote => {
+                if !triple {
+                    return i + 1;
+                }
+                if chars.get(i + 1) == Some(&quote) && chars.get(i + 2) == Some(&quote) {
+                    return i + 3;
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    i
+}
+
+/// A name bound at module level.
+pub struct DefinedName {
+    pub name: String,
+    pub kind: &'static str,
+    pub line: usize,
+}
+
+/// Functions, classes and variables the script defines at module level.
+pub fn defined_names(code: &str) -> Vec<DefinedName> {
+    let mut names = Vec::new();
+
+    for logical in logical_lines(code).iter().filter(|l| l.indent == 0) {
+        let offset = usize::from(logical.name_at(0) == Some("async"));
+        match (logical.name_at(offset), logical.name_at(offset + 1)) {
+            (Some("def"), Some(name)) => names.push(DefinedName {
+                name: name.to_string(),
+                kind: "function",
+                line: logical.line,
+            }),
+            (Some("class"), Some(name)) => names.push(DefinedName {
+                name: name.to_string(),
+                kind: "class",
+                line: logical.line,
+            }),
+            _ => {
+                for name in assignment_targets(logical) {
+                    if !names.iter().any(|n| n.name == name) {
+                        names.push(DefinedName {
+                            name,
+                            kind: "variable",
+                            line: logical.line,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    names
+}
+
+/// Names on the left of a plain or annotated assignment (`a = ...`,
+/// `a, b = ...`, `a: int = ...`).
+fn assignment_targets(logical: &LogicalLine) -> Vec<String> {
+    let mut targets = Vec::new();
+    for (i, token) in logical.tokens.iter().enumerate() {
+        match token {
+            // A name followed by `(` is a call, so any `=` later on belongs
+            // to a keyword argument.
+            Token::Name(_)
+                if matches!(logical.tokens.get(i + 1), Some(Token::Op(op)) if op == "(") =>
+            {
+                return Vec::new()
+            }
+            Token::Name(name) => targets.push(name.clone()),
+            Token::Op(op) if op == "," || op == "(" || op == ")" => {}
+            Token::Op(op) if op == "=" => return targets,
+            Token::Op(op) if op == ":" && i == 1 => return targets,
+            _ => return Vec::new(),
+        }
+    }
+    Vec::new()
+}
+
+/// Calls made from one function body, or from module-level code.
+pub struct CallSite {
+    pub caller: String,
+    pub functions: Vec<String>,
+    pub externals: Vec<String>,
+}
+
+/// Name of the pseudo-caller holding calls made at module level.
+pub const MODULE_CALLER: &str = "<module>";
+
+/// Which module-level functions and external functions each function
+/// calls. Methods are named `Class.method` and nested functions
+/// `outer.inner`; only plain-name calls are resolved, so calls through
+/// attributes (`self.helper()`) are not recorded.
+pub fn call_graph(code: &str, external_functions: &[String]) -> Vec<CallSite> {
+    let lines = logical_lines(code);
+    let functions: Vec<String> = defined_names(code)
+        .into_iter()
+        .filter(|defined| defined.kind == "function")
+        .map(|defined| defined.name)
+        .collect();
+
+    let mut sites = vec![CallSite {
+        caller: MODULE_CALLER.to_string(),
+        functions: Vec::new(),
+        externals: Vec::new(),
+    }];
+    // Enclosing defs and classes as (name, indent, site index or None for a class).
+    let mut scopes: Vec<(String, usize, Option<usize>)> = Vec::new();
+
+    for logical in &lines {
+        while scopes.last().is_some_and(|(_, indent, _)| logical.indent <= *indent) {
+            scopes.pop();
+        }
+
+        let offset = usize::from(logical.name_at(0) == Some("async"));
+        let definition = match (logical.name_at(offset), logical.name_at(offset + 1)) {
+            (Some("def"), Some(name)) => Some((name, true)),
+            (Some("class"), Some(name)) => Some((name, false)),
+            _ => None,
+        };
+        if let Some((name, is_function)) = definition {
+            let qualified = match scopes.last() {
+                Some((outer, _, _)) => format!("{outer}.{name}"),
+                None => name.to_string(),
+            };
+            let site = is_function.then(|| {
+                sites.push(CallSite {
+                    caller: qualified.clone(),
+                    functions: Vec::new(),
+                    externals: Vec::new(),
+                });
+                sites.len() - 1
+            });
+            scopes.push((qualified, logical.indent, site));
+            continue;
+        }
+
+        let site = scopes.iter().rev().find_map(|(_, _, site)| *site).unwrap_or(0);
+        for callee in called_names(logical) {
+            let (list, known) = if functions.contains(&callee) {
+                (&mut sites[site].functions, true)
+            } else {
+                (&mut sites[site].externals, external_functions.contains(&callee))
+            };
+            if known && !list.contains(&callee) {
+                list.push(callee);
+            }
+        }
+    }
+
+    sites
+}
+
+/// Plain names immediately followed by `(`, skipping attribute calls.
+fn called_names(logical: &LogicalLine) -> Vec<String> {
+    let mut names = Vec::new();
+    for (i, token) in logical.tokens.iter().enumerate() {
+        let Token::Name(name) = token else { continue };
+        let is_call = matches!(logical.tokens.get(i + 1), Some(Token::Op(op)) if op == "(");
+        let is_attribute =
+            i > 0 && matches!(&logical.tokens[i - 1], Token::Op(op) if op == ".");
+        if is_call && !is_attribute {
+            names.push(name.clone());
+        }
+    }
+    names
+}
+
+/// Warnings for declared inputs and external functions the script never
+/// mentions, in declaration order.
+pub fn unused_declarations(
+    code: &str,
+    inputs: &[String],
+    external_functions: &[String],
+) -> Vec<String> {
+    let mentioned: HashSet<String> = logical_lines(code)
+        .into_iter()
+        .flat_map(|logical| logical.tokens)
+        .filter_map(|token| match token {
+            Token::Name(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+
+    let unused_inputs = inputs
+        .iter()
+        .filter(|name| !mentioned.contains(*name))
+        .map(|name| format!("input '{name}' is never used"));
+    let unused_externals = external_functions
+        .iter()
+        .filter(|name| !mentioned.contains(*name))
+        .map(|name| format!("external function '{name}' is never called"));
+    unused_inputs.chain(unused_externals).collect()
+}
+
+/// Top-level module names imported anywhere in the script, with the line
+/// of each import. Relative imports are skipped.
+pub fn imported_modules(code: &str) -> Vec<(String, usize)> {
+    let mut modules = Vec::new();
+
+    for logical in logical_lines(code) {
+        for statement in logical.statements() {
+            match statement {
+                [Token::Name(keyword), rest @ ..] if keyword == "import" => {
+                    let mut expect_module = true;
+                    for token in rest {
+                        match token {
+                            Token::Name(name) if expect_module => {
+                                modules.push((name.clone(), logical.line));
+                                expect_module = false;
+                            }
+                            Token::Op(op) if op == "," => expect_module = true,
+                            _ => {}
+                        }
+                    }
+                }
+                [Token::Name(keyword), Token::Name(name), ..] if keyword == "from" => {
+                    modules.push((name.clone(), logical.line));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    modules
+}
+
+/// Float literals, `float(...)` calls and true division (`/`, which
+/// always yields a float, unlike `//`), with the line of each, for scripts
+/// that must stay clear of binary floating point.
+pub fn float_uses(code: &str) -> Vec<(String, usize)> {
+    let mut uses = Vec::new();
+
+    for logical in logical_lines(code) {
+        for (i, token) in logical.tokens.iter().enumerate() {
+            match token {
+                Token::Number(literal) if is_float_literal(literal) => {
+                    uses.push((literal.clone(), logical.line));
+                }
+                Token::Name(name)
+                    if name == "float"
+                        && matches!(logical.tokens.get(i + 1), Some(Token::Op(op)) if op == "(") =>
+                {
+                    uses.push(("float()".to_string(), logical.line));
+                }
+                Token::Op(op) if op == "/" || op == "/=" => {
+                    uses.push((format!("'{op}' division"), logical.line));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    uses
+}
+
+pub fn is_float_literal(literal: &str) -> bool {
+    let lower = literal.to_ascii_lowercase();
+    if lower.starts_with("0x") || lower.starts_with("0o") || lower.starts_with("0b") {
+        return false;
+    }
+    !lower.ends_with('j') && (lower.contains('.') || lower.contains('e'))
+}
+
+/// Builtin functions and types a script may refer to.
+const BUILTINS: &[&str] = &[
+    "abs", "all", "any", "ascii", "bin", "bool", "bytes", "callable", "chr", "dict", "dir",
+    "divmod", "enumerate", "filter", "float", "format", "frozenset", "getattr", "hasattr", "hash",
+    "hex", "id", "input", "int", "isinstance", "issubclass", "iter", "len", "list", "map", "max",
+    "min", "next", "object", "oct", "open", "ord", "pow", "print", "range", "repr", "reversed",
+    "round", "set", "setattr", "slice", "sorted", "str", "sum", "super", "tuple", "type", "zip",
+];
+
+/// `pathlib.Path` methods that reach the filesystem.
+const PATH_METHODS: &[&str] = &[
+    "exists", "is_file", "is_dir", "read_text", "read_bytes", "write_text", "write_bytes",
+    "iterdir", "glob", "stat", "mkdir", "unlink", "rmdir", "rename", "resolve", "touch",
+];
+
+/// What a script could do, for review tools to gate on.
+pub struct Capabilities {
+    pub modules: Vec<String>,
+    pub builtins: Vec<String>,
+    /// Calls that pause the run as an OS call, e.g. `os.getenv` or
+    /// `Path.read_text`.
+    pub os_calls: Vec<String>,
+    pub external_functions: Vec<String>,
+    pub loops: bool,
+    pub recursion: bool,
+}
+
+/// Summarize a script's capabilities from its source. Like the other
+/// checks here this is best-effort: it reports what the code mentions,
+/// not what a given execution reaches.
+pub fn capabilities(code: &str, external_functions: &[String]) -> Capabilities {
+    let lines = logical_lines(code);
+    let mut modules: Vec<String> = Vec::new();
+    for (module, _) in imported_modules(code) {
+        if !modules.contains(&module) {
+            modules.push(module);
+        }
+    }
+
+    let defined: HashSet<String> = defined_names(code)
+        .into_iter()
+        .map(|defined| defined.name)
+        .collect();
+    let os_names = os_bindings(&lines);
+    let uses_pathlib = modules.iter().any(|m| m == "pathlib");
+
+    let mut builtins = Vec::new();
+    let mut os_calls = Vec::new();
+    let mut loops = false;
+    for logical in &lines {
+        for statement in logical.statements() {
+            let is_import =
+                matches!(statement.first(), Some(Token::Name(k)) if k == "import" || k == "from");
+            for (i, token) in statement.iter().enumerate() {
+                let Token::Name(name) = token else { continue };
+                let after_dot = i > 0 && matches!(&statement[i - 1], Token::Op(op) if op == ".");
+                let next = statement.get(i + 1);
+                let is_call = matches!(next, Some(Token::Op(op)) if op == "(");
+                let is_keyword_arg = matches!(next, Some(Token::Op(op)) if op == "=")
+                    && i > 0
+                    && matches!(&statement[i - 1], Token::Op(op) if op == "(" || op == ",");
+
+                if matches!(name.as_str(), "for" | "while") {
+                    loops = true;
+                }
+                if is_import || is_keyword_arg {
+                    continue;
+                }
+                if !after_dot && BUILTINS.contains(&name.as_str()) && !defined.contains(name) {
+                    push_unique(&mut builtins, name.clone());
+                }
+                if !after_dot {
+                    if let Some((_, target)) = os_names.iter().find(|(local, _)| local == name) {
+                        let call = match os_attribute(&statement[i + 1..]) {
+                            Some(attribute) => Some(format!("{target}.{attribute}")),
+                            None if is_call && target != "os" => Some(target.clone()),
+                            None => None,
+                        };
+                        if let Some(call) = call {
+                            push_unique(&mut os_calls, call);
+                        }
+                    }
+                }
+                if is_call && after_dot && uses_pathlib && PATH_METHODS.contains(&name.as_str()) {
+                    push_unique(&mut os_calls, format!("Path.{name}"));
+                }
+            }
+        }
+    }
+
+    let graph = call_graph(code, external_functions);
+    let mut externals = Vec::new();
+    for site in &graph {
+        for name in &site.externals {
+            push_unique(&mut externals, name.clone());
+        }
+    }
+
+    Capabilities {
+        modules,
+        builtins,
+        os_calls,
+        external_functions: externals,
+        loops,
+        recursion: has_recursion(&graph),
+    }
+}
+
+fn push_unique(list: &mut Vec<String>, item: String) {
+    if !list.contains(&item) {
+        list.push(item);
+    }
+}
+
+/// Local names bound to `os` or something inside it, with the dotted
+/// path each stands for: `o` for `os` after `import os as o`, `ge` for
+/// `os.getenv` after `from os import getenv as ge`. `os` itself is always
+/// included, so uses without a visible import are still reported.
+fn os_bindings(lines: &[LogicalLine]) -> Vec<(String, String)> {
+    let mut bindings = vec![("os".to_string(), "os".to_string())];
+    for logical in lines {
+        for statement in logical.statements() {
+            let (from, clauses) = match statement {
+                [Token::Name(keyword), rest @ ..] if keyword == "import" => (None, rest),
+                [Token::Name(keyword), rest @ ..] if keyword == "from" => {
+                    let Some(at) = rest
+                        .iter()
+                        .position(|t| matches!(t, Token::Name(n) if n == "import"))
+                    else {
+                        continue;
+                    };
+                    (Some(dotted_name(&rest[..at])), &rest[at + 1..])
+                }
+                _ => continue,
+            };
+            for clause in clauses.split(|t| matches!(t, Token::Op(op) if op == ",")) {
+                let clause: Vec<&Token> = clause
+                    .iter()
+                    .filter(|t| !matches!(t, Token::Op(op) if op == "(" || op == ")"))
+                    .collect();
+                let (path, alias) = match clause
+                    .iter()
+                    .position(|t| matches!(t, Token::Name(n) if n == "as"))
+                {
+                    Some(at) => (
+                        dotted_name(clause[..at].iter().copied()),
+                        match clause.get(at + 1) {
+                            Some(Token::Name(alias)) => Some(alias.clone()),
+                            _ => None,
+                        },
+                    ),
+                    None => (dotted_name(clause.iter().copied()), None),
+                };
+                if path.is_empty() {
+                    continue;
+                }
+                let (local, target) = match &from {
+                    Some(module) => (
+                        alias.unwrap_or_else(|| path.clone()),
+                        format!("{module}.{path}"),
+                    ),
+                    None => match alias {
+                        Some(alias) => (alias, path),
+                        None => {
+                            let root = path.split('.').next().unwrap_or_default().to_string();
+                            (root.clone(), root)
+                        }
+                    },
+                };
+                if target == "os" || target.starts_with("os.") {
+                    bindings.retain(|(name, _)| *name != local);
+                    bindings.push((local, target));
+                }
+            }
+        }
+    }
+    bindings
+}
+
+/// `a.b.c` from the tokens of a dotted name; empty if they are not one.
+fn dotted_name<'a>(tokens: impl IntoIterator<Item = &'a Token>) -> String {
+    let mut name = String::new();
+    for token in tokens {
+        match token {
+            Token::Name(part) => name.push_str(part),
+            Token::Op(op) if op == "." => name.push('.'),
+            _ => return String::new(),
+        }
+    }
+    name
+}
+
+/// The dotted attribute path after `os`, e.g. `path.exists` for
+/// `os.path.exists(...)`.
+fn os_attribute(tokens: &[Token]) -> Option<String> {
+    let mut parts = Vec::new();
+    let mut rest = tokens;
+    while let [Token::Op(dot), Token::Name(part), tail @ ..] = rest {
+        if dot != "." {
+            break;
+        }
+        parts.push(part.as_str());
+        rest = tail;
+    }
+    (!parts.is_empty()).then(|| parts.join("."))
+}
+
+/// Whether any function can reach itself through calls to module-level
+/// functions. Calls from nested functions and methods count as calls from
+/// the outermost definition.
+fn has_recursion(graph: &[CallSite]) -> bool {
+    let mut edges: Vec<(&str, Vec<&str>)> = Vec::new();
+    for site in graph.iter().filter(|site| site.caller != MODULE_CALLER) {
+        let outer = site.caller.split('.').next().unwrap_or(&site.caller);
+        let callees = site.functions.iter().map(String::as_str);
+        match edges.iter_mut().find(|(caller, _)| *caller == outer) {
+            Some((_, existing)) => existing.extend(callees),
+            None => edges.push((outer, callees.collect())),
+        }
+    }
+
+    edges.iter().any(|(start, _)| {
+        let mut seen: Vec<&str> = Vec::new();
+        let mut stack = vec![*start];
+        while let Some(caller) = stack.pop() {
+            let Some((_, callees)) = edges.iter().find(|(name, _)| *name == caller) else {
+                continue;
+            };
+            for callee in callees {
+                if callee == start {
+                    return true;
+                }
+                if !seen.contains(callee) {
+                    seen.push(callee);
+                    stack.push(callee);
+                }
+            }
+        }
+        false
+    })
+}
+
+/// Cyclomatic complexity of one function, or of module-level code.
+pub struct Complexity {
+    pub function: String,
+    pub line: usize,
+    pub complexity: usize,
+}
+
+/// Keywords that add a branch: statements, comprehension clauses,
+/// conditional expressions and boolean operators alike.
+const BRANCH_KEYWORDS: &[&str] = &["if", "elif", "for", "while", "except", "case", "and", "or"];
+
+/// McCabe complexity of module-level code and of each function: one plus
+/// the number of branch keywords in its body. Functions are named as in
+/// the call graph, and a nested function's branches count only toward
+/// the nested function.
+pub fn complexity(code: &str) -> Vec<Complexity> {
+    let mut functions = vec![Complexity {
+        function: MODULE_CALLER.to_string(),
+        line: 1,
+        complexity: 1,
+    }];
+    // Enclosing defs and classes as (name, indent, index or None for a class).
+    let mut scopes: Vec<(String, usize, Option<usize>)> = Vec::new();
+
+    for logical in logical_lines(code) {
+        while scopes
+            .last()
+            .is_some_and(|(_, indent, _)| logical.indent <= *indent)
+        {
+            scopes.pop();
+        }
+
+        let offset = usize::from(logical.name_at(0) == Some("async"));
+        let definition = match (logical.name_at(offset), logical.name_at(offset + 1)) {
+            (Some("def"), Some(name)) => Some((name, true)),
+            (Some("class"), Some(name)) => Some((name, false)),
+            _ => None,
+        };
+        if let Some((name, is_function)) = definition {
+            let qualified = match scopes.last() {
+                Some((outer, _, _)) => format!("{outer}.{name}"),
+                None => name.to_string(),
+            };
+            let index = is_function.then(|| {
+                functions.push(Complexity {
+                    function: qualified.clone(),
+                    line: logical.line,
+                    complexity: 1,
+                });
+                functions.len() - 1
+            });
+            scopes.push((qualified, logical.indent, index));
+            continue;
+        }
+
+        let index = scopes
+            .iter()
+            .rev()
+            .find_map(|(_, _, index)| *index)
+            .unwrap_or(0);
+        functions[index].complexity += logical
+            .tokens
+            .iter()
+            .filter(|token| match token {
+                Token::Name(name) => BRANCH_KEYWORDS.contains(&name.as_str()),
+                _ => false,
+            })
+            .count();
+    }
+
+    functions
+}
+
+pub fn float_uses_to_ruby(code: String) -> Result<RArray, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let uses = float_uses(&code);
+    let arr = ruby.ary_new_capa(uses.len());
+    for (text, line) in uses {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("text"), text)?;
+        hash.aset(ruby.to_symbol("line"), line)?;
+        arr.push(hash)?;
+    }
+    Ok(arr)
+}
+
+pub fn defined_names_to_ruby(code: &str) -> Result<RArray, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let names = defined_names(code);
+    let arr = ruby.ary_new_capa(names.len());
+    for defined in names {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("name"), defined.name)?;
+        hash.aset(ruby.to_symbol("kind"), ruby.to_symbol(defined.kind))?;
+        hash.aset(ruby.to_symbol("line"), defined.line)?;
+        arr.push(hash)?;
+    }
+    Ok(arr)
+}
+
+pub fn call_graph_to_ruby(code: &str, external_functions: &[String]) -> Result<RHash, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let graph = ruby.hash_new();
+    for site in call_graph(code, external_functions) {
+        let edges = ruby.hash_new();
+        edges.aset(ruby.to_symbol("functions"), site.functions)?;
+        edges.aset(ruby.to_symbol("externals"), site.externals)?;
+        graph.aset(site.caller, edges)?;
+    }
+    Ok(graph)
+}
+
+pub fn capabilities_to_ruby(code: &str, external_functions: &[String]) -> Result<RHash, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let found = capabilities(code, external_functions);
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("modules"), found.modules)?;
+    hash.aset(ruby.to_symbol("builtins"), found.builtins)?;
+    hash.aset(ruby.to_symbol("os_calls"), found.os_calls)?;
+    hash.aset(
+        ruby.to_symbol("external_functions"),
+        found.external_functions,
+    )?;
+    hash.aset(ruby.to_symbol("loops"), found.loops)?;
+    hash.aset(ruby.to_symbol("recursion"), found.recursion)?;
+    Ok(hash)
+}
@@ -0,0 +1,125 @@
+use magnus::Error;
+use monty_lang::{
+    LimitedTracker, MontyException, MontyObject, MontyRun, NoLimitTracker, PrintWriter,
+    ResourceError, ResourceLimits, ResourceTracker,
+};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::control::{aborted_since, check_not_stopped, ActiveRun};
+use crate::gvl::without_gvl;
+
+/// Why a run should stop early: an interrupt of the thread waiting on it,
+/// `Monty.abort_all!` since it started, or `Monty.cancel_run` on `run`.
+pub struct StopSignal<'a> {
+    started: Instant,
+    abort_epoch: u64,
+    run: &'a ActiveRun,
+    interrupted: AtomicBool,
+}
+
+impl<'a> StopSignal<'a> {
+    pub fn new(abort_epoch: u64, run: &'a ActiveRun) -> Self {
+        Self {
+            started: Instant::now(),
+            abort_epoch,
+            run,
+            interrupted: AtomicBool::new(false),
+        }
+    }
+
+    /// Called by Ruby's unblock function when the waiting thread is
+    /// interrupted (Thread#raise, Thread#kill, Timeout, Ctrl-C).
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.interrupted.load(Ordering::Relaxed)
+            || aborted_since(self.abort_epoch)
+            || self.run.is_cancelled()
+    }
+}
+
+/// Resource tracker that fails the interpreter's next time check once its
+/// `StopSignal` is set, and otherwise defers to the wrapped tracker.
+///
+/// The failure surfaces as a time limit error with a zero limit; callers
+/// check the signal and report the stop instead.
+pub struct StopTracker<'a, T> {
+    inner: T,
+    signal: &'a StopSignal<'a>,
+}
+
+impl<'a, T> StopTracker<'a, T> {
+    pub fn new(inner: T, signal: &'a StopSignal<'a>) -> Self {
+        Self { inner, signal }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for StopTracker<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StopTracker")
+            .field("inner", &self.inner)
+            .field("stopped", &self.signal.is_stopped())
+            .finish()
+    }
+}
+
+impl<T: ResourceTracker> ResourceTracker for StopTracker<'_, T> {
+    fn on_allocate(&mut self, get_size: impl FnOnce() -> usize) -> Result<(), ResourceError> {
+        self.inner.on_allocate(get_size)
+    }
+
+    fn on_free(&mut self, get_size: impl FnOnce() -> usize) {
+        self.inner.on_free(get_size)
+    }
+
+    fn check_time(&self) -> Result<(), ResourceError> {
+        if self.signal.is_stopped() {
+            return Err(ResourceError::Time {
+                limit: Duration::ZERO,
+                elapsed: self.signal.started.elapsed(),
+            });
+        }
+        self.inner.check_time()
+    }
+
+    fn check_recursion_depth(&self, current_depth: usize) -> Result<(), ResourceError> {
+        self.inner.check_recursion_depth(current_depth)
+    }
+
+    fn check_large_result(&self, estimated_bytes: usize) -> Result<(), ResourceError> {
+        self.inner.check_large_result(estimated_bytes)
+    }
+}
+
+/// Run the script once with the GVL released. An interrupt,
+/// `Monty.abort_all!` since `abort_epoch` or `Monty.cancel_run` on
+/// `active` stops the interpreter at its next time check and is returned
+/// as the error (Monty::Interrupted for an abort or cancel), with the
+/// script's result dropped.
+pub fn run_stoppable<P: PrintWriter>(
+    run: &MontyRun,
+    inputs: Vec<MontyObject>,
+    limits: Option<ResourceLimits>,
+    print: &mut P,
+    abort_epoch: u64,
+    active: &ActiveRun,
+) -> Result<Result<MontyObject, MontyException>, Error> {
+    let signal = StopSignal::new(abort_epoch, active);
+    let result = without_gvl(
+        || match limits {
+            Some(limits) => run.run(
+                inputs,
+                StopTracker::new(LimitedTracker::new(limits), &signal),
+                print,
+            ),
+            None => run.run(inputs, StopTracker::new(NoLimitTracker, &signal), print),
+        },
+        || signal.interrupt(),
+    )?;
+    check_not_stopped(abort_epoch, active)?;
+    Ok(result)
+}
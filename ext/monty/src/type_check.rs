@@ -0,0 +1,596 @@
+use magnus::{Error, RArray, Ruby};
+use std::collections::HashMap;
+
+use crate::source_analysis::{is_float_literal, logical_lines, LogicalLine, Token};
+
+/// A probable type error found without running the script.
+pub struct TypeIssue {
+    pub message: String,
+    pub line: usize,
+}
+
+/// The type of a literal expression.
+#[derive(Clone, Copy, PartialEq)]
+enum Literal {
+    Int,
+    Float,
+    Bool,
+    Str,
+    None,
+    List,
+    Dict,
+}
+
+impl Literal {
+    fn name(self) -> &'static str {
+        match self {
+            Literal::Int => "int",
+            Literal::Float => "float",
+            Literal::Bool => "bool",
+            Literal::Str => "str",
+            Literal::None => "None",
+            Literal::List => "list",
+            Literal::Dict => "dict",
+        }
+    }
+}
+
+/// A type hint simple enough to check literals against, e.g. `int`,
+/// `list[str]` or `Optional[float]`.
+struct Annotation {
+    name: String,
+    optional: bool,
+}
+
+impl Annotation {
+    fn accepts(&self, literal: Literal) -> bool {
+        let name = self.name.as_str();
+        match literal {
+            Literal::None => self.optional || name == "None",
+            Literal::Int => matches!(name, "int" | "float" | "complex"),
+            Literal::Bool => matches!(name, "bool" | "int" | "float" | "complex"),
+            Literal::Float => matches!(name, "float" | "complex"),
+            // The lexer does not tell bytes literals from str literals.
+            Literal::Str => matches!(name, "str" | "bytes"),
+            Literal::List => name == "list",
+            // `{...}` may be a set display.
+            Literal::Dict => matches!(name, "dict" | "set"),
+        }
+    }
+
+    fn describe(&self) -> String {
+        if self.optional {
+            format!("{} | None", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+const CHECKED_TYPES: &[&str] = &[
+    "int", "float", "complex", "bool", "str", "bytes", "list", "dict", "set", "tuple", "None",
+];
+
+/// Operators binding tighter than `+` and `-`, so a literal next to one is
+/// not the whole operand.
+const TIGHTER_OPS: &[&str] = &["*", "/", "//", "%", "**", "@", ".", "(", "["];
+
+struct Param {
+    name: String,
+    annotation: Option<Annotation>,
+    /// Whether the parameter has a hint at all, simple or not.
+    hinted: bool,
+}
+
+/// A `def` line. `*args` and a bare `*` both appear in `params` as a
+/// parameter named `*`; `**kwargs` and `/` are left out.
+struct Def<'a> {
+    name: String,
+    /// Each parameter with the tokens of its default value.
+    params: Vec<(Param, &'a [Token])>,
+    returns: Option<Annotation>,
+    return_hinted: bool,
+}
+
+/// The parameters of a module-level function that can be matched against
+/// call arguments.
+struct Signature {
+    positional: Vec<Param>,
+    keyword_only: Vec<Param>,
+    /// Whether `*args` absorbs extra positional arguments.
+    var_positional: bool,
+}
+
+/// Probable type errors in the script, found by comparing literal values
+/// with simple type hints: annotated assignments, parameter defaults,
+/// arguments to module-level functions, return values, and `str` mixed
+/// with numbers around `+` and `-`.
+///
+/// This is a best-effort pass over the token stream, not a type checker:
+/// anything other than a literal is assumed to have the right type, so it
+/// reports few false positives but misses most errors.
+pub fn type_issues(code: &str) -> Vec<TypeIssue> {
+    let lines = logical_lines(code);
+    let signatures = module_signatures(&lines);
+    let mut issues = Vec::new();
+    // Enclosing defs as (indent, return annotation).
+    let mut scopes: Vec<(usize, Option<Annotation>)> = Vec::new();
+
+    for logical in &lines {
+        while scopes
+            .last()
+            .is_some_and(|(indent, _)| logical.indent <= *indent)
+        {
+            scopes.pop();
+        }
+
+        if let Some(def) = parse_def(logical) {
+            for (param, default) in def.params {
+                check_default(logical, &param, default, &mut issues);
+            }
+            scopes.push((logical.indent, def.returns));
+            continue;
+        }
+
+        if logical.name_at(0) == Some("return") {
+            if let Some((_, Some(expected))) = scopes.last() {
+                let literal = match &logical.tokens[1..] {
+                    [] => Some(Literal::None),
+                    value => literal_type(value),
+                };
+                if let Some(got) = literal.filter(|got| !expected.accepts(*got)) {
+                    issues.push(TypeIssue {
+                        message: format!(
+                            "Incompatible return value type (got \"{}\", expected \"{}\")",
+                            got.name(),
+                            expected.describe()
+                        ),
+                        line: logical.line,
+                    });
+                }
+            }
+        }
+
+        check_annotated_assignment(logical, &mut issues);
+        check_calls(logical, &signatures, &mut issues);
+        check_operands(logical, &mut issues);
+    }
+
+    issues
+}
+
+/// Function parameters and return values without a type hint, in mypy's
+/// `--disallow-untyped-defs` wording. `self` and `cls` need no hint, nor
+/// does the return value of `__init__`.
+pub fn missing_type_hints(code: &str) -> Vec<TypeIssue> {
+    let mut issues = Vec::new();
+    for logical in logical_lines(code) {
+        let Some(def) = parse_def(&logical) else {
+            continue;
+        };
+        for (param, _) in &def.params {
+            if !param.hinted && !matches!(param.name.as_str(), "self" | "cls") {
+                issues.push(TypeIssue {
+                    message: format!(
+                        "Function \"{}\" is missing a type annotation for argument \"{}\"",
+                        def.name, param.name
+                    ),
+                    line: logical.line,
+                });
+            }
+        }
+        if !def.return_hinted && def.name != "__init__" {
+            issues.push(TypeIssue {
+                message: format!(
+                    "Function \"{}\" is missing a return type annotation",
+                    def.name
+                ),
+                line: logical.line,
+            });
+        }
+    }
+    issues
+}
+
+/// Signatures of functions defined once at module level; names defined
+/// more than once are left out since calls to them are ambiguous.
+fn module_signatures(lines: &[LogicalLine]) -> HashMap<String, Signature> {
+    let mut signatures = HashMap::new();
+    let mut redefined = Vec::new();
+
+    for logical in lines.iter().filter(|l| l.indent == 0) {
+        let Some(def) = parse_def(logical) else {
+            continue;
+        };
+        let mut signature = Signature {
+            positional: Vec::new(),
+            keyword_only: Vec::new(),
+            var_positional: false,
+        };
+        for (param, _) in def.params {
+            if param.name == "*" {
+                signature.var_positional = true;
+            } else if signature.var_positional {
+                signature.keyword_only.push(param);
+            } else {
+                signature.positional.push(param);
+            }
+        }
+        if signatures.insert(def.name.clone(), signature).is_some() {
+            redefined.push(def.name);
+        }
+    }
+
+    for name in redefined {
+        signatures.remove(&name);
+    }
+    signatures
+}
+
+fn parse_def(logical: &LogicalLine) -> Option<Def<'_>> {
+    let offset = usize::from(logical.name_at(0) == Some("async"));
+    if logical.name_at(offset) != Some("def") {
+        return None;
+    }
+    let name = logical.name_at(offset + 1)?.to_string();
+    let open = offset + 2;
+    if !is_op(logical.tokens.get(open), "(") {
+        return None;
+    }
+    let close = matching_close(&logical.tokens, open)?;
+
+    let mut params = Vec::new();
+    for piece in split_top_level(&logical.tokens[open + 1..close], ",") {
+        match piece {
+            [Token::Op(op), ..] if op == "*" => params.push((
+                Param {
+                    name: "*".to_string(),
+                    annotation: None,
+                    hinted: true,
+                },
+                &[][..],
+            )),
+            [Token::Name(param), rest @ ..] => {
+                let equals = position_top_level(rest, "=");
+                let (hint, default) = match equals {
+                    Some(i) => (&rest[..i], &rest[i + 1..]),
+                    None => (rest, &[][..]),
+                };
+                let (annotation, hinted) = match hint {
+                    [Token::Op(colon), hint @ ..] if colon == ":" => (parse_annotation(hint), true),
+                    _ => (None, false),
+                };
+                params.push((
+                    Param {
+                        name: param.clone(),
+                        annotation,
+                        hinted,
+                    },
+                    default,
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    let after = &logical.tokens[close + 1..];
+    let returns = match after {
+        [Token::Op(arrow), rest @ ..] if arrow == "->" => {
+            position_top_level(rest, ":").and_then(|end| parse_annotation(&rest[..end]))
+        }
+        _ => None,
+    };
+    let return_hinted = is_op(after.first(), "->");
+
+    Some(Def {
+        name,
+        params,
+        returns,
+        return_hinted,
+    })
+}
+
+fn check_default(
+    logical: &LogicalLine,
+    param: &Param,
+    default: &[Token],
+    issues: &mut Vec<TypeIssue>,
+) {
+    let Some(annotation) = &param.annotation else {
+        return;
+    };
+    if let Some(got) = literal_type(default).filter(|got| !annotation.accepts(*got)) {
+        issues.push(TypeIssue {
+            message: format!(
+                "Incompatible default for argument \"{}\" (default has type \"{}\", argument has type \"{}\")",
+                param.name,
+                got.name(),
+                annotation.describe()
+            ),
+            line: logical.line,
+        });
+    }
+}
+
+/// `name: hint = value` with a literal value the hint does not allow.
+fn check_annotated_assignment(logical: &LogicalLine, issues: &mut Vec<TypeIssue>) {
+    let [Token::Name(_), Token::Op(colon), rest @ ..] = logical.tokens.as_slice() else {
+        return;
+    };
+    if colon != ":" {
+        return;
+    }
+    let Some(equals) = position_top_level(rest, "=") else {
+        return;
+    };
+    let Some(annotation) = parse_annotation(&rest[..equals]) else {
+        return;
+    };
+    if let Some(got) = literal_type(&rest[equals + 1..]).filter(|got| !annotation.accepts(*got)) {
+        issues.push(TypeIssue {
+            message: format!(
+                "Incompatible types in assignment (expression has type \"{}\", variable has type \"{}\")",
+                got.name(),
+                annotation.describe()
+            ),
+            line: logical.line,
+        });
+    }
+}
+
+/// Literal arguments to module-level functions that their parameter hints
+/// do not allow.
+fn check_calls(
+    logical: &LogicalLine,
+    signatures: &HashMap<String, Signature>,
+    issues: &mut Vec<TypeIssue>,
+) {
+    let tokens = &logical.tokens;
+    for (i, token) in tokens.iter().enumerate() {
+        let Token::Name(name) = token else { continue };
+        let Some(signature) = signatures.get(name) else {
+            continue;
+        };
+        let is_attribute = i > 0 && is_op(tokens.get(i - 1), ".");
+        let is_definition = i > 0 && matches!(&tokens[i - 1], Token::Name(kw) if kw == "def");
+        if is_attribute || is_definition || !is_op(tokens.get(i + 1), "(") {
+            continue;
+        }
+        let Some(close) = matching_close(tokens, i + 1) else {
+            continue;
+        };
+
+        let mut position = 0;
+        for arg in split_top_level(&tokens[i + 2..close], ",") {
+            let (label, param, value) = match arg {
+                [] => continue,
+                [Token::Op(op), ..] if op == "*" || op == "**" => break,
+                [Token::Name(keyword), Token::Op(eq), value @ ..] if eq == "=" => {
+                    let param = signature
+                        .positional
+                        .iter()
+                        .chain(&signature.keyword_only)
+                        .find(|p| p.name == *keyword);
+                    (format!("\"{keyword}\""), param, value)
+                }
+                value => {
+                    position += 1;
+                    (
+                        position.to_string(),
+                        signature.positional.get(position - 1),
+                        value,
+                    )
+                }
+            };
+            let Some(annotation) = param.and_then(|p| p.annotation.as_ref()) else {
+                continue;
+            };
+            if let Some(got) = literal_type(value).filter(|got| !annotation.accepts(*got)) {
+                issues.push(TypeIssue {
+                    message: format!(
+                        "Argument {label} to \"{name}\" has incompatible type \"{}\"; expected \"{}\"",
+                        got.name(),
+                        annotation.describe()
+                    ),
+                    line: logical.line,
+                });
+            }
+        }
+    }
+}
+
+/// A str literal added to or subtracted from a number literal, or the
+/// other way round, where neither literal is part of a tighter-binding
+/// expression.
+fn check_operands(logical: &LogicalLine, issues: &mut Vec<TypeIssue>) {
+    let tokens = &logical.tokens;
+    for (i, window) in tokens.windows(3).enumerate() {
+        let [left, Token::Op(op), right] = window else {
+            continue;
+        };
+        if op != "+" && op != "-" {
+            continue;
+        }
+        let standalone_left =
+            i == 0 || !matches!(&tokens[i - 1], Token::Op(o) if TIGHTER_OPS.contains(&o.as_str()));
+        let standalone_right =
+            !matches!(tokens.get(i + 3), Some(Token::Op(o)) if TIGHTER_OPS.contains(&o.as_str()));
+        if !standalone_left || !standalone_right {
+            continue;
+        }
+        let kinds = (
+            literal_type(std::slice::from_ref(left)),
+            literal_type(std::slice::from_ref(right)),
+        );
+        let mixed = match kinds {
+            (Some(Literal::Str), Some(other)) | (Some(other), Some(Literal::Str)) => {
+                matches!(other, Literal::Int | Literal::Float | Literal::Bool)
+            }
+            _ => false,
+        };
+        if mixed {
+            let (Some(left), Some(right)) = kinds else {
+                continue;
+            };
+            issues.push(TypeIssue {
+                message: format!(
+                    "Unsupported operand types for {op} (\"{}\" and \"{}\")",
+                    left.name(),
+                    right.name()
+                ),
+                line: logical.line,
+            });
+        }
+    }
+}
+
+/// The type of an expression made of a single literal, or None for
+/// anything else.
+fn literal_type(tokens: &[Token]) -> Option<Literal> {
+    match tokens {
+        [] => None,
+        [Token::Number(n)] => number_type(n),
+        [Token::Op(minus), Token::Number(n)] if minus == "-" => number_type(n),
+        [Token::Name(name)] => match name.as_str() {
+            "True" | "False" => Some(Literal::Bool),
+            "None" => Some(Literal::None),
+            _ => None,
+        },
+        _ if tokens.iter().all(|t| *t == Token::Str) => Some(Literal::Str),
+        [Token::Op(open), ..] if open == "[" || open == "{" => {
+            if matching_close(tokens, 0) != Some(tokens.len() - 1) {
+                return None;
+            }
+            Some(if open == "[" {
+                Literal::List
+            } else {
+                Literal::Dict
+            })
+        }
+        _ => None,
+    }
+}
+
+fn number_type(literal: &str) -> Option<Literal> {
+    if literal.to_ascii_lowercase().ends_with('j') {
+        None
+    } else if is_float_literal(literal) {
+        Some(Literal::Float)
+    } else {
+        Some(Literal::Int)
+    }
+}
+
+/// Parse a hint naming one of `CHECKED_TYPES` (or its `typing` alias),
+/// optionally subscripted and optionally made nullable with `Optional[...]`
+/// or `| None`.
+fn parse_annotation(tokens: &[Token]) -> Option<Annotation> {
+    if let [Token::Name(optional), Token::Op(open), inner @ .., Token::Op(close)] = tokens {
+        if optional == "Optional" && open == "[" && close == "]" {
+            let inner = parse_annotation(inner)?;
+            return Some(Annotation {
+                optional: true,
+                ..inner
+            });
+        }
+    }
+
+    let parts = split_top_level(tokens, "|");
+    if parts.len() > 1 {
+        let (none, rest): (Vec<&[Token]>, Vec<&[Token]>) = parts
+            .into_iter()
+            .partition(|part| matches!(part, [Token::Name(n)] if n == "None"));
+        return match (none.len(), rest.as_slice()) {
+            (1, [only]) => Some(Annotation {
+                optional: true,
+                ..parse_annotation(only)?
+            }),
+            _ => None,
+        };
+    }
+
+    let [Token::Name(name), subscript @ ..] = tokens else {
+        return None;
+    };
+    let subscripted = matches!(subscript.first(), Some(Token::Op(open)) if open == "[")
+        && matching_close(subscript, 0) == Some(subscript.len() - 1);
+    if !subscript.is_empty() && !subscripted {
+        return None;
+    }
+    let name = match name.as_str() {
+        "List" => "list",
+        "Dict" => "dict",
+        "Set" => "set",
+        "Tuple" => "tuple",
+        other => other,
+    };
+    CHECKED_TYPES.contains(&name).then(|| Annotation {
+        name: name.to_string(),
+        optional: false,
+    })
+}
+
+fn is_op(token: Option<&Token>, op: &str) -> bool {
+    matches!(token, Some(Token::Op(o)) if o == op)
+}
+
+/// Index of the bracket closing the one at `open`.
+fn matching_close(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::Op(op) if matches!(op.as_str(), "(" | "[" | "{") => depth += 1,
+            Token::Op(op) if matches!(op.as_str(), ")" | "]" | "}") => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Index of the first `op` outside any brackets.
+fn position_top_level(tokens: &[Token], op: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Op(o) if matches!(o.as_str(), "(" | "[" | "{") => depth += 1,
+            Token::Op(o) if matches!(o.as_str(), ")" | "]" | "}") => {
+                depth = depth.saturating_sub(1)
+            }
+            Token::Op(o) if depth == 0 && o == op => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split on `sep` outside any brackets.
+fn split_top_level<'a>(tokens: &'a [Token], sep: &str) -> Vec<&'a [Token]> {
+    let mut pieces = Vec::new();
+    let mut rest = tokens;
+    while let Some(i) = position_top_level(rest, sep) {
+        pieces.push(&rest[..i]);
+        rest = &rest[i + 1..];
+    }
+    pieces.push(rest);
+    pieces
+}
+
+/// Issues as Hashes with :severity (always :warning), :message and :line.
+pub fn type_issues_to_ruby(code: &str) -> Result<RArray, Error> {
+    let ruby = Ruby::get().expect("Ruby runtime not available");
+    let issues = type_issues(code);
+    let arr = ruby.ary_new_capa(issues.len());
+    for issue in issues {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("severity"), ruby.to_symbol("warning"))?;
+        hash.aset(ruby.to_symbol("message"), issue.message)?;
+        hash.aset(ruby.to_symbol("line"), issue.line)?;
+        arr.push(hash)?;
+    }
+    Ok(arr)
+}
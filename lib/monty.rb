@@ -11,4 +11,22 @@ rescue LoadError
 end
 
 # Load Ruby class extensions
+require_relative "monty/audit_log"
+require_relative "monty/batch_report"
+require_relative "monty/bytes"
+require_relative "monty/complete"
+require_relative "monty/config"
+require_relative "monty/deprecation"
+require_relative "monty/diagnostics"
+require_relative "monty/error"
+require_relative "monty/function_call"
+require_relative "monty/isolated_run"
+require_relative "monty/output_reader"
+require_relative "monty/policy"
+require_relative "monty/redaction"
 require_relative "monty/run"
+require_relative "monty/scheduler"
+require_relative "monty/tool_results"
+require_relative "monty/trace"
+require_relative "monty/transcript"
+require_relative "monty/tuple"
@@ -0,0 +1,76 @@
+# frozen_string_literal: true
+
+module Monty
+  # Structured record of everything that crossed the sandbox boundary
+  # during a run: inputs, external and OS calls with their results, and
+  # the final result or error.
+  #
+  # Each entry is a Hash with an :event key (:input, :external_call,
+  # :os_call, :external_result, :result or :error) plus event-specific
+  # fields. Values are stored as truncated inspect strings, never as the
+  # live objects, after passing through Monty.redactor.
+  #
+  # @example Compliance review
+  #   audit = Monty::AuditLog.new
+  #   run.call_with_externals(order, audit: audit) { |call| handle(call) }
+  #   AuditRecord.create!(entries: audit.to_a)
+  #
+  class AuditLog
+    include Enumerable
+
+    # @param max_repr_length [Integer, nil] longest stored repr before
+    #   truncation (default: Monty.max_repr_length)
+    def initialize(max_repr_length: nil)
+      @max_repr_length = max_repr_length
+      @entries = []
+    end
+
+    def each(&block)
+      @entries.each(&block)
+    end
+
+    # @return [Array<Hash>] a copy of the recorded entries
+    def to_a
+      @entries.dup
+    end
+
+    # @api private
+    def record_inputs(inputs)
+      inputs.each_with_index { |value, index| record(:input, index: index, value: repr(value)) }
+    end
+
+    # @api private
+    def record_call(call)
+      name = call.function_name
+      event = name.start_with?("os:") ? :os_call : :external_call
+      record(event, name: name, call_id: call.call_id,
+        args: call.args.map { |arg| repr(arg) },
+        kwargs: call.kwargs.transform_values { |value| repr(value) })
+    end
+
+    # @api private
+    def record_external_result(call, value)
+      record(:external_result, call_id: call.call_id, value: repr(value))
+    end
+
+    # @api private
+    def record_result(value)
+      record(:result, value: repr(value))
+    end
+
+    # @api private
+    def record_error(error)
+      record(:error, class: error.class.name, message: error.message)
+    end
+
+    private
+
+    def record(event, **fields)
+      @entries << {event: event, at: Time.now, **fields}
+    end
+
+    def repr(value)
+      Monty.redact_repr(value.inspect, @max_repr_length || Monty.max_repr_length)
+    end
+  end
+end
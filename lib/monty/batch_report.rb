@@ -0,0 +1,63 @@
+# frozen_string_literal: true
+
+module Monty
+  # Outcome of Run#batch: per-entry results, errors and timings, plus
+  # which entries never ran because the batch stopped early.
+  class BatchReport
+    # @return [Array<Object, nil>] results by input index; nil for entries
+    #   that failed or were skipped
+    attr_reader :results
+
+    # @return [Hash{Integer => Monty::Error}] errors by input index
+    attr_reader :errors
+
+    # @return [Hash{Integer => Hash}] stats (:duration) by input index
+    attr_reader :stats
+
+    # @return [Float] wall-clock seconds for the whole batch
+    attr_reader :duration
+
+    # @api private
+    def initialize(size)
+      @size = size
+      @results = Array.new(size)
+      @errors = {}
+      @stats = {}
+      @completed = []
+      @stopped = false
+    end
+
+    # @api private
+    def record(index, result, stats)
+      @completed << index
+      @stats[index] = stats
+      if result.is_a?(Monty::Error)
+        @errors[index] = result
+      else
+        @results[index] = result
+      end
+    end
+
+    # @api private
+    def finish(duration:, stopped:)
+      @duration = duration
+      @stopped = stopped
+      self
+    end
+
+    # @return [Array<Integer>] indices of entries that never ran
+    def skipped
+      (0...@size).to_a - @completed
+    end
+
+    # True when the batch ended early on an error or at the deadline.
+    def stopped?
+      @stopped || skipped.any?
+    end
+
+    # True when every entry ran and none failed.
+    def success?
+      @errors.empty? && skipped.empty?
+    end
+  end
+end
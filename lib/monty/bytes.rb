@@ -0,0 +1,20 @@
+# frozen_string_literal: true
+
+module Monty
+  # Binary data passed to Python as bytes. Plain Strings, binary-encoded
+  # ones included, arrive in Python as str; wrap a String in Monty::Bytes
+  # to pass its raw contents instead.
+  #
+  # @example
+  #   run = Monty::Run.new("isinstance(x, bytes)", inputs: ["x"])
+  #   run.call(Monty::Bytes[File.binread("logo.png")]) # => true
+  class Bytes
+    # Build frozen bytes from a String's contents.
+    #
+    # @param data [String] the raw contents, in any encoding
+    # @return [Monty::Bytes]
+    def self.[](data)
+      new(data.b).freeze
+    end
+  end
+end
@@ -0,0 +1,29 @@
+# frozen_string_literal: true
+
+module Monty
+  class Complete
+    # The session history of an iterative run, one entry per interpreter
+    # segment in order.
+    #
+    # Each entry is a Hash with :function and :args (the external call
+    # the segment paused at, as a name and a Python-style argument list;
+    # nil for the final segment), :result (repr of what the host
+    # returned), :output (what the segment printed) and :duration
+    # (seconds). Reprs are passed through Monty.redactor, then truncated
+    # to Monty.max_repr_length characters; output is redacted only.
+    #
+    # @return [Array<Hash>]
+    #
+    # @example
+    #   complete.segments.first
+    #   # => {function: "fetch", args: "'https://example.com', timeout=5",
+    #   #     result: "{'status': 200}", output: "fetching\n", duration: 0.0004}
+    #
+    def segments
+      _segments.each do |segment|
+        %i[args result].each { |key| segment[key] = Monty.redact_repr(segment[key]) }
+        segment[:output] = Monty.redact(segment[:output])
+      end
+    end
+  end
+end
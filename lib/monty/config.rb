@@ -0,0 +1,101 @@
+# frozen_string_literal: true
+
+module Monty
+  CONFIG_KEY = :__monty_config__
+  private_constant :CONFIG_KEY
+  PARENT_RUN_KEY = :__monty_parent_run__
+  private_constant :PARENT_RUN_KEY
+
+  class << self
+    # Apply configuration to every run started inside the block on the
+    # current fiber. Nested scopes combine, keeping the stricter value of
+    # each limit, and a limit passed directly to a run can only tighten it.
+    #
+    # Only resource limits can be scoped so far. Runs have no run-time OS
+    # access policy or custom converter registry yet for a scope to set;
+    # os_policy: and converters: will be accepted here once they do.
+    #
+    # @param limits [Hash, nil] resource limits applied to each run
+    # @return the block's return value
+    #
+    # @example Enforcing a tenant policy in middleware
+    #   Monty.with_config(limits: { max_duration: 2.0 }) do
+    #     app.call(env)
+    #   end
+    #
+    def with_config(limits: nil)
+      raise ArgumentError, "a block is required" unless block_given?
+
+      previous = Thread.current[CONFIG_KEY]
+      Thread.current[CONFIG_KEY] = {limits: merge_limits(previous&.dig(:limits), limits)}
+      yield
+    ensure
+      Thread.current[CONFIG_KEY] = previous if block_given?
+    end
+
+    # Record +run_id+ as the parent of every run started inside the block
+    # on the current fiber, so Monty.cancel_tree can stop an
+    # orchestrating run together with the runs it delegated to. Runs
+    # started in a nested within_run block for a child become
+    # grandchildren.
+    #
+    # @param run_id [Integer] id of the parent run, e.g. FunctionCall#run_id
+    # @return the block's return value
+    #
+    # @example Delegating from an orchestrating script
+    #   progress = orchestrator.start(task)
+    #   answer = Monty.within_run(progress.run_id) { worker.call(progress.args[0]) }
+    #   # elsewhere: Monty.cancel_tree(progress.run_id)
+    #
+    def within_run(run_id)
+      raise ArgumentError, "a block is required" unless block_given?
+
+      previous = Thread.current[PARENT_RUN_KEY]
+      Thread.current[PARENT_RUN_KEY] = run_id
+      begin
+        yield
+      ensure
+        Thread.current[PARENT_RUN_KEY] = previous
+      end
+    end
+
+    # The configuration in effect for the current fiber.
+    #
+    # @return [Hash] with a :limits key (nil when no scope is active)
+    def current_config
+      Thread.current[CONFIG_KEY] || {limits: nil}
+    end
+
+    # @api private
+    def effective_limits(limits)
+      merge_limits(current_config[:limits], limits)
+    end
+
+    private
+
+    # Durations and byte sizes are normalized first, so a per-call "10GB"
+    # cannot loosen a scope's 1_000_000 bytes by failing to compare. A nil
+    # override keeps the scoped value, and values that still cannot be
+    # compared raise rather than letting either side win.
+    def merge_limits(base, override)
+      return override if base.nil?
+      return base if override.nil?
+
+      _normalize_limits(base).merge(_normalize_limits(override)) do |key, a, b|
+        if b.nil? || a == b
+          a
+        elsif a.nil?
+          b
+        elsif key == :result_integer_overflow
+          [a, b].map(&:to_s).include?("raise") ? :raise : b
+        elsif key == :strict && [a, b].all? { |v| v == true || v == false }
+          a || b
+        elsif a.is_a?(Numeric) && b.is_a?(Numeric)
+          [a, b].min
+        else
+          raise ArgumentError, "cannot merge limit #{key.inspect}: #{a.inspect} and #{b.inspect}"
+        end
+      end
+    end
+  end
+end
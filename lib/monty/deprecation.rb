@@ -0,0 +1,70 @@
+# frozen_string_literal: true
+
+module Monty
+  class << self
+    # Callable receiving +(event, payload)+ for events the binding reports
+    # outside of any single run. Currently only "deprecation.monty" is
+    # emitted. When unset, deprecations are printed with Kernel#warn.
+    #
+    # @return [#call, nil]
+    #
+    # @example Forwarding to ActiveSupport::Notifications
+    #   Monty.instrumenter = ->(event, payload) { ActiveSupport::Notifications.instrument(event, payload) }
+    #
+    attr_accessor :instrumenter
+
+    # Keep +old_name+ working on +owner+ as an alias for +new_name+ that
+    # reports a deprecation the first time it is called in the process.
+    #
+    # The "deprecation.monty" payload has :method and :replacement (both
+    # "Class#name" Strings) and :caller, the first application frame.
+    #
+    # @api private
+    # @param owner [Module] class or module defining +new_name+
+    # @param old_name [Symbol] name kept for compatibility
+    # @param new_name [Symbol] current name
+    def deprecate_method(owner, old_name, new_name)
+      method = "#{owner.name}##{old_name}".freeze
+      replacement = "#{owner.name}##{new_name}".freeze
+      # Shareable, so the alias can also be called from other Ractors.
+      body = Ractor.make_shareable(proc do |*args, **kwargs, &block|
+        Monty.send(:report_deprecation, method, replacement, caller_locations(1, 1).first&.to_s)
+        public_send(new_name, *args, **kwargs, &block)
+      end)
+      owner.define_method(old_name, &body)
+    end
+
+    private
+
+    def report_deprecation(method, replacement, location)
+      return unless first_deprecation_report?(method)
+
+      payload = {method: method, replacement: replacement, caller: location}
+      if (instrumenter = ractor_instrumenter)
+        instrumenter.call("deprecation.monty", payload)
+      else
+        warn "monty: #{method} is deprecated, use #{replacement} instead (called from #{location})"
+      end
+    end
+
+    # Whether +method+ is reported for the first time. A non-main Ractor
+    # cannot reach the process-wide set, so it keeps its own and reports
+    # once per Ractor.
+    def first_deprecation_report?(method)
+      @deprecations_mutex.synchronize { !@deprecations_reported.add?(method).nil? }
+    rescue Ractor::IsolationError
+      !(Ractor.current[:monty_deprecations_reported] ||= Set.new).add?(method).nil?
+    end
+
+    # Monty.instrumenter, or nil in a non-main Ractor when it is not
+    # shareable, so the deprecation is printed instead.
+    def ractor_instrumenter
+      instrumenter
+    rescue Ractor::IsolationError
+      nil
+    end
+  end
+
+  @deprecations_mutex = Mutex.new
+  @deprecations_reported = Set.new
+end
@@ -0,0 +1,23 @@
+# frozen_string_literal: true
+
+module Monty
+  # Compile Python code and return problems as data instead of raising.
+  #
+  # Takes the same arguments as Run.new. Each diagnostic is a Hash with
+  # :severity (:error when the code does not compile, :warning for code
+  # that compiles but declares inputs or external functions it never
+  # uses), :message and :span (a Hash with :line, :column, :end_line and
+  # :end_column, or nil when no location is known).
+  #
+  # @param code [String] Python source code
+  # @return [Array<Hash>] empty when the code compiles with no warnings
+  #
+  # @example Validation endpoint
+  #   diagnostics = Monty.diagnostics(params[:code], inputs: ["order"])
+  #   errors = diagnostics.select { |d| d[:severity] == :error }
+  #   render json: {ok: errors.empty?, diagnostics: diagnostics}
+  #
+  def self.diagnostics(code, script_name: "script.py", inputs: [], external_functions: [])
+    _diagnostics(code, script_name, inputs, external_functions)
+  end
+end
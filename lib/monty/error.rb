@@ -0,0 +1,105 @@
+# frozen_string_literal: true
+
+module Monty
+  class Error
+    # CPython-style traceback rendered by the extension when the error was
+    # raised from Python code, or nil for errors raised by the binding.
+    # Passed through Monty.redactor.
+    #
+    # @return [String, nil]
+    def formatted_traceback
+      Monty.redact(@formatted_traceback)
+    end
+
+    # Stack frames of the Python exception, outermost first, as Hashes
+    # with :file, :line, :function and :source_line keys (the stripped
+    # source text, or nil when unavailable). Source lines are passed
+    # through Monty.redactor. Empty for errors raised by the binding.
+    #
+    # @return [Array<Hash>]
+    #
+    # @example Rendering frames in a UI
+    #   e.traceback.map { |f| "#{f[:file]}:#{f[:line]} in #{f[:function]}" }
+    #
+    def traceback
+      (@traceback || []).map { |frame| frame.merge(source_line: Monty.redact(frame[:source_line])) }
+    end
+
+    # Name of the Python exception class, e.g. "ZeroDivisionError", or nil
+    # for errors raised by the binding.
+    #
+    # @return [String, nil]
+    attr_reader :python_class
+
+    # The error message, passed through Monty.redactor. Exception#message,
+    # #inspect and #full_message are all built from this.
+    def to_s
+      Monty.redact(super)
+    end
+  end
+
+  # Raised when a resource limit is exceeded. The interpreter's limits
+  # raise one of the subclasses below, so hosts can react per limit type;
+  # limits enforced by the binding (max_result_bytes:, etc.) raise this
+  # class itself.
+  class ResourceError
+    # The configured limit, or nil when it was not reported.
+    #
+    # @return [Numeric, nil]
+    attr_reader :limit
+
+    # How much had been used when the limit was hit (bytes, allocations,
+    # recursion depth or seconds), or nil when it was not reported.
+    #
+    # @return [Numeric, nil]
+    attr_reader :used
+  end
+
+  # max_duration: was exceeded. #limit and #elapsed are in seconds.
+  class TimeLimitExceeded
+    # @return [Float, nil] seconds elapsed when the run was stopped
+    alias_method :elapsed, :used
+  end
+
+  # Raised by Run.new(policy:) when the script breaks a Monty::Policy rule.
+  class PolicyError
+    # @return [Array<Hash>] every violation, as returned by Policy#violations
+    attr_reader :violations
+
+    # @param violations [Array<Hash>] as returned by Policy#violations
+    def initialize(violations)
+      @violations = violations
+      lines = violations.map { |v| v[:line] ? "line #{v[:line]}: #{v[:message]}" : v[:message] }
+      super("script violates policy: #{lines.join("; ")}")
+    end
+  end
+
+  # Raised when the host aborts a run (rather than the script failing on
+  # its own), so retry logic can tell the two apart.
+  class Interrupted
+    # @return [String] output printed before the interruption
+    attr_reader :output
+
+    # @return [Hash, nil] stats accumulated before the interruption
+    attr_reader :stats
+  end
+
+  # Format an error as a CPython-style multi-line traceback with source
+  # excerpts, ready for user-facing error displays.
+  #
+  # @param error [Monty::Error]
+  # @return [String]
+  #
+  # @example
+  #   rescue Monty::Error => e
+  #     puts Monty.format_traceback(e)
+  #     # Traceback (most recent call last):
+  #     #   File "script.py", line 1, in <module>
+  #     #     1 / 0
+  #     #     ^^^^^
+  #     # ZeroDivisionError: division by zero
+  #
+  def self.format_traceback(error)
+    error.formatted_traceback || "#{error.class}: #{error.message}"
+  end
+end
@@ -0,0 +1,62 @@
+# frozen_string_literal: true
+
+module Monty
+  class FunctionCall
+    # Restore a paused call previously serialized with #dump, possibly in
+    # another process, so it can be resumed there.
+    #
+    # Interpreter limits (max_duration:, max_memory:, etc.) are part of the
+    # dump. Limits enforced by the bindings (max_pending_futures:,
+    # max_tool_result_bytes:, max_result_bytes:, max_result_items:) are not,
+    # and apply only when given here.
+    #
+    # @param bytes [String, Array<Integer>] serialized bytes
+    # @param limits [Hash, nil] binding-side limits for the rest of the run
+    # @param decrypt [#call, nil] callable turning the stored ciphertext back
+    #   into the binary String passed to +encrypt+ by #dump
+    # @return [Monty::FunctionCall]
+    #
+    # @example Resuming in a background job
+    #   call = Monty::FunctionCall.load(record.snapshot)
+    #   progress = call.resume(fetch(call.args[0]))
+    #
+    def self.load(bytes, limits: nil, decrypt: nil)
+      bytes = decrypt.call(bytes) if decrypt
+      bytes = bytes.bytes if bytes.is_a?(String)
+      _load(bytes, Monty.effective_limits(limits))
+    end
+
+    # Serialize this paused call for later restoration via FunctionCall.load.
+    # The call is not consumed and can still be resumed in this process.
+    #
+    # Expiry and encryption work as for Run#dump.
+    #
+    # @param expires_in [Numeric, nil] seconds from now until the dump expires
+    # @param expires_at [Time, nil] absolute expiry time
+    # @param encrypt [#call, nil] callable encrypting the serialized bytes
+    # @return [String, Array<Integer>] serialized bytes
+    def dump(expires_in: nil, expires_at: nil, encrypt: nil)
+      expires_at ||= Time.now + expires_in if expires_in
+      bytes = _dump(expires_at&.to_f)
+      encrypt ? encrypt.call(bytes.pack("C*")) : bytes
+    end
+
+    # The call's arguments as Python source (+'x', retries=2+), passed
+    # through Monty.redactor and cut to Monty.max_repr_length characters
+    # for logging. Use #args and #kwargs for the full values.
+    #
+    # @return [String]
+    def args_repr
+      Monty.redact_repr(_args_repr)
+    end
+
+    # Shows the call with its arguments cut to Monty.max_repr_length, so
+    # logging a FunctionCall never dumps megabyte-size arguments. Use
+    # #args and #kwargs for the full values.
+    #
+    # @return [String]
+    def inspect
+      "#<#{self.class.name} #{function_name}(#{args_repr})>"
+    end
+  end
+end
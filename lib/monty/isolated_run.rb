@@ -0,0 +1,184 @@
+# frozen_string_literal: true
+
+module Monty
+  # Executes a script in a forked child process for defense in depth.
+  #
+  # The program is compiled in the parent, shipped to the child in the
+  # Run#dump format and executed there under OS resource limits. External
+  # function calls are forwarded back to the parent over a pipe, so the
+  # block runs in the host process while the interpreter does not. If the
+  # child crashes or is killed by an rlimit, the parent raises Monty::Error
+  # instead of going down with it.
+  #
+  # Time limits escalate: the interpreter's own max_duration check aborts
+  # cooperatively first, and if the child has not answered once the
+  # kill_after grace period has also passed, it is killed outright. Both
+  # raise Monty::TimeLimitExceeded, whose message names the path taken.
+  class IsolatedRun
+    # Lightweight stand-in for Monty::FunctionCall passed to the block.
+    Call = Struct.new(:function_name, :args, :kwargs)
+
+    # Signals treated as a host-initiated abort of the child.
+    INTERRUPT_SIGNALS = %w[INT TERM].freeze
+
+    # @param code [String] Python source code
+    # @param script_name [String] filename for error messages (default: "script.py")
+    # @param inputs [Array<String>] input variable names (default: [])
+    # @param external_functions [Array<String>] external function names (default: [])
+    # @param max_address_space [Integer, nil] RLIMIT_AS for the child, in bytes
+    # @param max_cpu_seconds [Integer, nil] RLIMIT_CPU for the child, in seconds
+    # @param kill_after [Float] grace period in seconds after max_duration
+    #   before the child is killed (default: 1.0)
+    def initialize(code, script_name: "script.py", inputs: [], external_functions: [],
+      max_address_space: nil, max_cpu_seconds: nil, kill_after: 1.0)
+      @bytes = Run.new(code, script_name: script_name, inputs: inputs,
+        external_functions: external_functions).dump
+      @rlimits = {
+        Process::RLIMIT_AS => max_address_space,
+        Process::RLIMIT_CPU => max_cpu_seconds
+      }.compact
+      @kill_after = kill_after
+    end
+
+    # Execute the script in a child process.
+    #
+    # Accepts the same options as Run#call. When a block is given, it is
+    # called with a Monty::IsolatedRun::Call for each external function
+    # call and its return value is sent back to the child.
+    #
+    # @return [Object] the Python return value, or Hash if capture_output
+    def call(*inputs, limits: nil, capture_output: false, &handler)
+      raise ::NotImplementedError, "IsolatedRun requires fork" unless Process.respond_to?(:fork)
+
+      limits = Monty.effective_limits(limits)
+      # Normalized before forking, so a "2s" or ActiveSupport::Duration
+      # limit is a number of seconds and a bad one raises in the parent.
+      max_duration = limits && Monty._normalize_limits(limits)[:max_duration]
+      to_child_r, to_child_w = IO.pipe
+      from_child_r, from_child_w = IO.pipe
+
+      pid = fork do
+        to_child_w.close
+        from_child_r.close
+        run_child(inputs, limits, capture_output, handler, to_child_r, from_child_w)
+      end
+
+      to_child_r.close
+      from_child_w.close
+      supervise(pid, handler, from_child_r, to_child_w, max_duration, capture_output)
+    ensure
+      [to_child_r, to_child_w, from_child_r, from_child_w].each { |io| io&.close unless io&.closed? }
+      reap(pid) if pid
+    end
+
+    private
+
+    def run_child(inputs, limits, capture_output, handler, reader, writer)
+      @rlimits.each { |resource, value| Process.setrlimit(resource, value) }
+      run = Run.load(@bytes)
+
+      message = begin
+        result =
+          if handler
+            run.call_with_externals(*inputs, limits: limits, capture_output: capture_output) do |call|
+              write_message(writer, ["call", call.function_name, call.args, call.kwargs])
+              status, value = read_message(reader)
+              raise Monty::Error, value if status == "error"
+
+              value
+            end
+          else
+            run.call(*inputs, limits: limits, capture_output: capture_output)
+          end
+        capture_output ? ["ok", result[:result], result[:output]] : ["ok", result]
+      rescue Monty::Error => e
+        ["raise", e.class.name, e.message]
+      rescue SignalException => e
+        ["raise", "Monty::Interrupted", "isolated run interrupted by SIG#{Signal.signame(e.signo)}"]
+      rescue StandardError, ScriptError, NoMemoryError, SystemStackError => e
+        ["raise", "Monty::Error", "isolated run failed: #{e.class}: #{e.message}"]
+      end
+
+      write_message(writer, message)
+    ensure
+      writer.close
+      exit!(0)
+    end
+
+    def supervise(pid, handler, reader, writer, max_duration, capture_output)
+      loop do
+        unless wait_readable(reader, max_duration)
+          Process.kill(:KILL, pid)
+          raise Monty::TimeLimitExceeded,
+            "time limit exceeded: child killed after #{@kill_after}s grace period (process kill)"
+        end
+
+        message = read_message(reader)
+
+        case message&.first
+        when "call"
+          reply =
+            begin
+              ["ok", handler.call(Call.new(*message[1..]))]
+            rescue => e
+              Process.kill(:KILL, pid)
+              raise e
+            end
+          write_message(writer, reply)
+        when "ok"
+          return capture_output ? {result: message[1], output: message[2]} : message[1]
+        when "raise"
+          klass = error_class(message[1])
+          text = message[2]
+          text = "#{text} (cooperative abort)" if klass <= Monty::ResourceError && text.start_with?("time limit")
+          raise klass, text
+        else
+          Process.wait(pid)
+          signal = $?.termsig && Signal.signame($?.termsig)
+          if INTERRUPT_SIGNALS.include?(signal)
+            raise Monty::Interrupted, "isolated run interrupted by SIG#{signal}"
+          end
+
+          raise Monty::Error, "isolated run terminated unexpectedly#{" (SIG#{signal})" if signal}"
+        end
+      end
+    end
+
+    # Waits for the child's next message. Each segment of interpreter work
+    # gets max_duration plus the grace period before escalation.
+    def wait_readable(reader, max_duration)
+      return true unless max_duration
+
+      !IO.select([reader], nil, nil, max_duration + @kill_after).nil?
+    end
+
+    def reap(pid)
+      Process.kill(:KILL, pid)
+      Process.wait(pid)
+    rescue Errno::ESRCH, Errno::ECHILD
+      nil
+    end
+
+    def error_class(name)
+      klass = Monty.const_get(name.delete_prefix("Monty::"), false)
+      (klass.is_a?(Class) && klass <= Monty::Error) ? klass : Monty::Error
+    rescue ::NameError
+      Monty::Error
+    end
+
+    # Messages are Arrays of plain values, serialized with
+    # Monty._dump_value so nothing the child sends is ever unmarshaled.
+    def write_message(io, message)
+      data = Monty._dump_value(message).pack("C*")
+      io.write([data.bytesize].pack("N"), data)
+      io.flush
+    end
+
+    def read_message(io)
+      header = io.read(4)
+      return nil if header.nil? || header.bytesize < 4
+
+      Monty._load_value(io.read(header.unpack1("N")).unpack("C*"))
+    end
+  end
+end
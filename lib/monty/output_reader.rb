@@ -0,0 +1,42 @@
+# frozen_string_literal: true
+
+module Monty
+  # Read-only, IO-like view of Complete#output, returned by
+  # Complete#output_reader. It reads from the extension's buffer, so very
+  # large output can be streamed to storage without first being copied
+  # into a single Ruby String. Readers are independent: each starts at
+  # the beginning.
+  #
+  # @example Uploading output in chunks
+  #   reader = complete.output_reader
+  #   while (chunk = reader.read(1 << 20))
+  #     upload.write(chunk)
+  #   end
+  #
+  class OutputReader
+    # Read like IO#read.
+    #
+    # @param length [Integer, nil] bytes to read, or nil for the rest
+    # @return [String, nil] with a length, up to that many bytes as a
+    #   binary String, or nil at the end; without one, the rest of the
+    #   output as a UTF-8 String ("" at the end)
+    def read(length = nil)
+      raise ArgumentError, "negative length #{length} given" if length&.negative?
+
+      _read(length)
+    end
+
+    # Yield each line, including its newline, from the current position.
+    #
+    # @yieldparam line [String]
+    # @return [self, Enumerator]
+    def each_line
+      return enum_for(:each_line) unless block_given?
+
+      while (line = gets)
+        yield line
+      end
+      self
+    end
+  end
+end
@@ -0,0 +1,110 @@
+# frozen_string_literal: true
+
+module Monty
+  # Rules a script must satisfy before it is compiled. Pass one to
+  # Run.new(policy:) to have violations raise Monty::PolicyError, so
+  # enforcement lives next to compilation rather than in each caller.
+  #
+  # Checks are static and best-effort, like Run#capabilities: they see
+  # what the source mentions, not what a given execution reaches.
+  #
+  # @example
+  #   policy = Monty::Policy.new(deny_imports: ["os"], max_complexity: 10, require_type_hints: true)
+  #   Monty::Run.new(code, policy: policy)
+  #
+  class Policy
+    # @return [Array<String>] modules the script may not import
+    attr_reader :deny_imports
+
+    # @return [Integer, nil] the highest cyclomatic complexity allowed for
+    #   module-level code and for each function
+    attr_reader :max_complexity
+
+    # @return [Boolean] whether filesystem and environment calls are forbidden
+    attr_reader :forbid_os_calls
+
+    # @return [Boolean] whether every parameter and return value needs a hint
+    attr_reader :require_type_hints
+
+    # @param deny_imports [Array<String>] modules the script may not import
+    #   (default: [])
+    # @param max_complexity [Integer, nil] highest cyclomatic complexity
+    #   allowed for module-level code and for each function: one plus the
+    #   number of if, elif, for, while, except, case, and and or keywords
+    #   in it (default: nil, no limit)
+    # @param forbid_os_calls [Boolean] forbid the calls listed under
+    #   :os_calls by Run#capabilities, e.g. os.getenv or Path.read_text
+    #   (default: false)
+    # @param require_type_hints [Boolean] require a hint on every parameter
+    #   other than self and cls, and a return hint on every function other
+    #   than __init__ (default: false)
+    def initialize(deny_imports: [], max_complexity: nil, forbid_os_calls: false, require_type_hints: false)
+      @deny_imports = deny_imports.map(&:to_s).freeze
+      @max_complexity = max_complexity
+      @forbid_os_calls = forbid_os_calls ? true : false
+      @require_type_hints = require_type_hints ? true : false
+      freeze
+    end
+
+    # Every rule the script breaks.
+    #
+    # @param code [String] Python source code
+    # @param external_functions [Array<String>] external function names
+    # @return [Array<Hash>] violations with :rule (the keyword naming the
+    #   rule), :message and :line (nil when the rule applies to the whole
+    #   script) keys, in rule order
+    def violations(code, external_functions: [])
+      facts = Monty._policy_facts(code, external_functions.map(&:to_s))
+      import_violations(facts) + complexity_violations(facts) + os_call_violations(facts) +
+        type_hint_violations(facts)
+    end
+
+    # Raise Monty::PolicyError unless the script satisfies every rule.
+    #
+    # @param code [String] Python source code
+    # @param external_functions [Array<String>] external function names
+    # @return [nil]
+    # @raise [Monty::PolicyError]
+    def enforce(code, external_functions: [])
+      found = violations(code, external_functions: external_functions)
+      raise PolicyError.new(found) unless found.empty?
+    end
+
+    private
+
+    def import_violations(facts)
+      facts[:imports].filter_map do |import|
+        next unless deny_imports.include?(import[:module])
+
+        {rule: :deny_imports, message: "import of '#{import[:module]}' is denied", line: import[:line]}
+      end
+    end
+
+    def complexity_violations(facts)
+      return [] unless max_complexity
+
+      facts[:complexity].filter_map do |measured|
+        next if measured[:complexity] <= max_complexity
+
+        message = "#{measured[:function]} has complexity #{measured[:complexity]} (max #{max_complexity})"
+        {rule: :max_complexity, message: message, line: measured[:line]}
+      end
+    end
+
+    def os_call_violations(facts)
+      return [] unless forbid_os_calls
+
+      facts[:os_calls].map do |call|
+        {rule: :forbid_os_calls, message: "#{call} is forbidden", line: nil}
+      end
+    end
+
+    def type_hint_violations(facts)
+      return [] unless require_type_hints
+
+      facts[:missing_type_hints].map do |missing|
+        {rule: :require_type_hints, message: missing[:message], line: missing[:line]}
+      end
+    end
+  end
+end
@@ -0,0 +1,42 @@
+# frozen_string_literal: true
+
+module Monty
+  class << self
+    # Callable applied to text before it leaves the binding in error
+    # messages, formatted tracebacks and audit log entries. It receives a
+    # String and returns the String to expose, or nil to disable redaction.
+    #
+    # @return [#call, nil]
+    #
+    # @example Masking API keys passed as inputs
+    #   Monty.redactor = ->(text) { text.gsub(/sk_live_\w+/, "[REDACTED]") }
+    #
+    attr_accessor :redactor
+
+    # Apply the configured redactor to +text+.
+    #
+    # @param text [String, nil]
+    # @return [String, nil]
+    def redact(text)
+      redactor = self.redactor
+      (redactor && text) ? redactor.call(text) : text
+    rescue Ractor::IsolationError
+      # A non-main Ractor cannot reach a redactor that is not shareable;
+      # withhold the text rather than expose it unredacted.
+      text && "[withheld: Monty.redactor is not Ractor-shareable]"
+    end
+
+    # Redact +text+, then cut it to +limit+ characters marked with "...".
+    # Cutting second keeps a secret that straddles the limit whole when
+    # the redactor sees it.
+    #
+    # @param text [String, nil]
+    # @param limit [Integer]
+    # @return [String, nil]
+    # @api private
+    def redact_repr(text, limit = max_repr_length)
+      text = redact(text)
+      (text && text.length > limit) ? "#{text[0, limit]}..." : text
+    end
+  end
+end
@@ -1,13 +1,49 @@
 # frozen_string_literal: true
 
+require "etc"
+
 module Monty
   class Run
+    # Guards the lazy creation of each Run's @incremental_lock.
+    INCREMENTAL_SETUP = Mutex.new
+    private_constant :INCREMENTAL_SETUP
+
+    # Per-iteration time limit for #fuzz when none is configured, so a
+    # generated input that makes the script loop forever is reported as a
+    # failure instead of hanging the fuzzer.
+    FUZZ_MAX_DURATION = 1.0
+
     # Create a new Monty::Run instance by parsing Python code.
     #
     # @param code [String] Python source code
     # @param script_name [String] filename for error messages (default: "script.py")
     # @param inputs [Array<String>] input variable names (default: [])
     # @param external_functions [Array<String>] external function names (default: [])
+    # @param disabled_modules [Array<String>] modules the script may not import,
+    #   e.g. ["json", "asyncio"] for minimal rule-engine deployments (default: []).
+    #   A best-effort lint over import statements found by static analysis,
+    #   not a sandbox boundary: dynamic forms such as __import__("os") are
+    #   not caught
+    # @param float_literals [Symbol] :allow (default), :warn to print a warning
+    #   for each float literal, float() call or true division (/), or :forbid
+    #   to raise Monty::PolicyError listing them instead, for money-handling
+    #   scripts that must use integer minor units
+    # @param intrinsics [Boolean] declare the functions in Run::INTRINSICS
+    #   and answer them in native code during #start, #execute and
+    #   #call_with_externals, so scripts don't spend their instruction
+    #   budget on inner loops. fsum, mean, stdev, dot and percentile take
+    #   lists or tuples of numbers and return a float; levenshtein (an int),
+    #   jaro_winkler and similarity (floats from 0.0 to 1.0, where
+    #   similarity is the normalized Levenshtein distance) take two strings
+    #   and raise ValueError when len(a) * len(b) exceeds 4,000,000.
+    #   json_loads and json_dumps behave like json.loads and json.dumps,
+    #   within Monty.max_json_bytes and Monty.max_json_depth. b64encode
+    #   returns a str and b64decode bytes; sha256, md5 and hmac_sha256(key,
+    #   data) return hex digests. These take str (encoded as UTF-8) or bytes.
+    #   #call, #map and #fuzz raise ArgumentError for such a Run. Not kept
+    #   by #dump (default: false)
+    # @param policy [Monty::Policy, nil] rules checked before compiling;
+    #   violations raise Monty::PolicyError listing all of them
     # @return [Monty::Run]
     #
     # @example Simple function
@@ -16,29 +52,74 @@ module Monty
     # @example With external functions
     #   run = Monty::Run.new(code, external_functions: ["fetch"])
     #
-    def self.new(code, script_name: "script.py", inputs: [], external_functions: [])
-      _new(code, script_name, inputs, external_functions)
+    # @example With a policy
+    #   policy = Monty::Policy.new(deny_imports: ["os"], max_complexity: 10)
+    #   Monty::Run.new(code, policy: policy)
+    #
+    # @example With intrinsics
+    #   run = Monty::Run.new("percentile(latencies, 95)", inputs: ["latencies"], intrinsics: true)
+    #   run.execute([120, 80, 95]) { |name, *| raise "unexpected call to #{name}" }
+    #
+    def self.new(code, script_name: "script.py", inputs: [], external_functions: [], disabled_modules: [],
+      float_literals: :allow, intrinsics: false, policy: nil)
+      policy&.enforce(code, external_functions: external_functions)
+      check_float_literals(code, script_name, float_literals)
+      _new(code, script_name, inputs, external_functions, disabled_modules, intrinsics ? true : false)
     end
 
     # Deserialize a Run from bytes previously created by #dump.
     #
     # @param bytes [String] serialized bytes
+    # @param decrypt [#call, nil] callable turning the stored ciphertext back
+    #   into the binary String passed to +encrypt+ by #dump
     # @return [Monty::Run]
-    def self.load(bytes)
+    def self.load(bytes, decrypt: nil)
+      bytes = decrypt.call(bytes).bytes if decrypt
       _load(bytes)
     end
 
+    def self.check_float_literals(code, script_name, mode)
+      return if mode == :allow
+      unless %i[warn forbid].include?(mode)
+        raise ArgumentError, "float_literals must be :allow, :warn or :forbid, got #{mode.inspect}"
+      end
+
+      uses = Monty._float_uses(code)
+      if mode == :forbid
+        violations = uses.map do |use|
+          {rule: :float_literals, message: "binary float #{use[:text]} used", line: use[:line]}
+        end
+        raise PolicyError.new(violations) unless violations.empty?
+      else
+        uses.each { |use| warn "monty: #{script_name}:#{use[:line]}: binary float #{use[:text]} used" }
+      end
+    end
+    private_class_method :check_float_literals
+
     # Execute the Python code with the given inputs.
     #
-    # @param inputs positional arguments matching the input variable names
+    # Inputs are always deep-copied into the interpreter, so a script can
+    # never mutate the Ruby objects passed in.
+    #
+    # @param positional positional arguments matching the input variable names
+    # @param inputs [Hash, nil] inputs keyed by name (String or Symbol)
+    #   instead of position; missing or unexpected names raise ArgumentError
     # @param limits [Hash, nil] resource limits (max_allocations:, max_duration:, max_memory:, etc.)
     # @param capture_output [Boolean] if true, returns a Hash with :result and :output keys
+    # @param audit [Monty::AuditLog, nil] records inputs and the result or error
+    # @param on_output [#call, nil] called with each line the script prints,
+    #   including its trailing newline, as soon as it is written; a final
+    #   line without one is passed when the run ends. Cannot be combined
+    #   with capture_output
     # @return [Object] the Python return value converted to Ruby, or Hash if capture_output
     #
     # @example Simple call
     #   run = Monty::Run.new("def add(x, y): return x + y", inputs: ["x", "y"])
     #   run.call(1, 2) # => 3
     #
+    # @example Named inputs
+    #   run.call(inputs: {x: 1, y: 2}) # => 3
+    #
     # @example With limits
     #   run.call(1, 2, limits: { max_duration: 5.0, max_memory: 1_048_576 })
     #
@@ -46,10 +127,21 @@ module Monty
     #   run = Monty::Run.new("print('hello')\nresult = 42", inputs: [])
     #   run.call(capture_output: true) # => { result: 42, output: "hello\n" }
     #
-    def call(*inputs, limits: nil, capture_output: false)
-      input_array = inputs
+    # @example Streaming output
+    #   run.call(on_output: ->(line) { logger.info(line.chomp) })
+    #
+    def call(*positional, inputs: nil, limits: nil, capture_output: false, audit: nil, on_output: nil)
+      if on_output && capture_output
+        raise ArgumentError, "on_output cannot be combined with capture_output"
+      end
 
-      if capture_output
+      input_array = resolve_inputs(positional, inputs)
+      limits = Monty.effective_limits(limits)
+      audit&.record_inputs(input_array)
+
+      result = if on_output
+        _run_streaming(input_array, limits, on_output)
+      elsif capture_output
         if limits
           _run_capturing_with_limits(input_array, limits)
         else
@@ -60,6 +152,172 @@ module Monty
       else
         _run(input_array)
       end
+
+      audit&.record_result(capture_output ? result[:result] : result)
+      result
+    rescue Monty::Error => e
+      audit&.record_error(e)
+      raise
+    end
+
+    # Execute like #call, reusing the conversion of inputs that have not
+    # changed since the previous call_incremental on this Run.
+    #
+    # Each input is compared with the one previously passed in the same
+    # position by #hash and #eql?; unchanged inputs are passed as cached
+    # Monty::SharedData instead of being converted again. Suited to
+    # dashboards re-evaluating a script as a few small inputs tick while
+    # large reference inputs stay the same. A frozen Run (see
+    # #freeze_for_ractor) has nowhere to keep the cache and converts every
+    # input, as #call does.
+    #
+    # @param inputs positional arguments matching the input variable names
+    # @param options keyword options accepted by #call
+    # @return [Object] as for #call
+    #
+    # @example
+    #   run.call_incremental(price_history, latest_tick) # converts both
+    #   run.call_incremental(price_history, next_tick)   # converts next_tick only
+    #
+    def call_incremental(*inputs, **options)
+      return call(*inputs, **options) if frozen?
+
+      lock = @incremental_lock || INCREMENTAL_SETUP.synchronize { @incremental_lock ||= Mutex.new }
+      converted = lock.synchronize { incremental_inputs(inputs) }
+      call(*converted, **options)
+    end
+
+    # Execute with CSV data as the first input.
+    #
+    # The CSV is parsed in the extension straight into a Python list of
+    # dicts keyed by the header row, skipping the Ruby Array/Hash
+    # round-trip for large tables.
+    #
+    # @param io_or_string [String, #read] CSV source
+    # @param inputs remaining positional arguments
+    # @param col_sep [String] column separator (default: ",")
+    # @param types [nil, :infer, Hash] nil keeps every cell a str, :infer
+    #   detects int/float/bool columns, or map column names to :str, :int,
+    #   :float or :bool. Empty cells in typed columns become None.
+    # @param limits [Hash, nil] resource limits, as for #call
+    # @param capture_output [Boolean] as for #call
+    # @return [Object] the Python return value converted to Ruby, or Hash if capture_output
+    #
+    # @example
+    #   run = Monty::Run.new("sum(r['amount'] for r in rows)", inputs: ["rows"])
+    #   run.run_with_csv(File.open("orders.csv"), types: {amount: :float})
+    #
+    def run_with_csv(io_or_string, *inputs, col_sep: ",", types: nil, limits: nil, capture_output: false, audit: nil)
+      data = io_or_string.respond_to?(:read) ? io_or_string.read : io_or_string
+      table = Monty._parse_csv(data, col_sep, types)
+      call(table, *inputs, limits: limits, capture_output: capture_output, audit: audit)
+    end
+
+    # Execute the code once per input set in parallel.
+    #
+    # Scripts run on a pool of native threads with the GVL released, so
+    # other Ruby threads keep running meanwhile. Printed output goes to
+    # stdout. Failures do not stop the batch: the entry for a failed set
+    # is the Monty::Error it raised.
+    #
+    # @param input_sets [Array<Array>] positional inputs for each execution
+    # @param limits [Hash, nil] resource limits applied to each execution
+    # @param concurrency [Integer] number of threads (default: processor count)
+    # @yield [index, result, stats] optional; called as each execution
+    #   finishes, in completion order, with the result or Monty::Error and a
+    #   Hash with the :duration in seconds. Raising from the block stops
+    #   the executions still running and the error propagates.
+    # @raise [Monty::Interrupted] if Monty.abort_all! is called meanwhile
+    # @return [Array<Object, Monty::Error>, nil] results in input order, or
+    #   nil when a block is given
+    #
+    # @example Batch scoring
+    #   run = Monty::Run.new("score(applicant)", inputs: ["applicant"])
+    #   scores = run.map(applicants.map { |a| [a] }, limits: {max_duration: 1})
+    #
+    # @example Persisting results as they finish
+    #   run.map(input_sets) do |index, result, stats|
+    #     Score.create!(row: index, value: result, seconds: stats[:duration])
+    #   end
+    #
+    def map(input_sets, limits: nil, concurrency: Etc.nprocessors, &block)
+      input_sets = input_sets.to_a
+      limits = Monty.effective_limits(limits)
+      _map(input_sets, Array.new(input_sets.size, limits), concurrency, nil, &block)
+    end
+
+    # Execute the code once per input set in parallel, as #map does, with
+    # policies for long or failing batches.
+    #
+    # @param input_sets [Array<Array>] positional inputs for each execution
+    # @param limits [Hash, nil] resource limits applied to each execution
+    # @param item_limits [Hash{Integer => Hash}] limits merged over +limits+
+    #   for individual entries, keyed by input index
+    # @param concurrency [Integer] number of threads (default: processor count)
+    # @param on_error [Symbol] :collect (default) runs every entry; :stop
+    #   starts no further entries after the first error
+    # @param deadline [Numeric, nil] seconds for the whole batch; no entry
+    #   starts after it and running entries get only the remaining time.
+    #   Nil or Float::INFINITY means no deadline; negative or NaN values
+    #   raise ArgumentError
+    # @return [Monty::BatchReport]
+    #
+    # @example
+    #   report = run.batch(input_sets, on_error: :stop, deadline: 30)
+    #   report.success? # => false
+    #   report.errors   # => {3 => #<Monty::Error: ZeroDivisionError: ...>}
+    #   report.skipped  # => [5, 6, 7]
+    #
+    def batch(input_sets, limits: nil, item_limits: {}, concurrency: Etc.nprocessors, on_error: :collect,
+      deadline: nil)
+      unless %i[collect stop].include?(on_error)
+        raise ArgumentError, "on_error must be :collect or :stop, got #{on_error.inspect}"
+      end
+
+      input_sets = input_sets.to_a
+      per_item = input_sets.each_index.map do |index|
+        override = item_limits[index]
+        Monty.effective_limits(override ? (limits || {}).merge(override) : limits)
+      end
+      report = BatchReport.new(input_sets.size)
+      started = Process.clock_gettime(Process::CLOCK_MONOTONIC)
+
+      stopped = catch(:monty_batch_stop) do
+        _map(input_sets, per_item, concurrency, deadline&.to_f) do |index, result, stats|
+          report.record(index, result, stats)
+          throw :monty_batch_stop, true if on_error == :stop && result.is_a?(Monty::Error)
+        end
+        false
+      end
+
+      report.finish(duration: Process.clock_gettime(Process::CLOCK_MONOTONIC) - started, stopped: stopped)
+    end
+
+    # Run the script repeatedly with random inputs and report the inputs
+    # that made it raise or exceed a limit.
+    #
+    # Each generator describes one input: :int, :float, :str, :bool or
+    # :none, an Integer Range, +[spec]+ for a list of such values, or
+    # +{key => spec}+ for a dict with those keys. Edge cases (0, -1,
+    # extreme ints, NaN, empty strings, empty lists) are drawn often.
+    #
+    # @param generators [Array] one spec per input variable
+    # @param iterations [Integer] number of runs (default: 100)
+    # @param limits [Hash, nil] resource limits applied to every run;
+    #   max_duration defaults to FUZZ_MAX_DURATION seconds
+    # @param seed [Integer, nil] makes the generated inputs reproducible
+    # @return [Array<Hash>] failures, each with :iteration, :inputs and
+    #   :error (the Monty::Error raised)
+    #
+    # @example
+    #   run = Monty::Run.new("100 // (qty - 3)", inputs: ["qty"])
+    #   run.fuzz(generators: [0..10], limits: {max_duration: 0.1}, seed: 1).first
+    #   # => {iteration: 4, inputs: [3], error: #<Monty::Error: ZeroDivisionError: ...>}
+    #
+    def fuzz(generators:, iterations: 100, limits: nil, seed: nil)
+      seed ||= Random.new_seed
+      limits = {max_duration: FUZZ_MAX_DURATION}.merge(Monty.effective_limits(limits) || {})
+      _fuzz(generators, iterations, limits, seed % (2**64))
     end
 
     # Start iterative execution for scripts with external function calls.
@@ -69,8 +327,13 @@ module Monty
     #
     # NOTE: This consumes the Run. It cannot be used again after calling start.
     #
-    # @param inputs positional arguments matching the input variable names
-    # @param limits [Hash, nil] resource limits (max_allocations:, max_duration:, max_memory:, etc.)
+    # @param positional positional arguments matching the input variable names
+    # @param inputs [Hash, nil] inputs keyed by name instead of position, as for #call
+    # @param limits [Hash, nil] resource limits (max_allocations:, max_duration:, max_memory:, etc.).
+    #   Also accepts max_pending_futures: to cap simultaneously pending async calls.
+    # @param trace [Boolean] record a timeline of interpreter segments, host
+    #   waits and conversions, available from every progress object as
+    #   #trace_events and #chrome_trace
     # @return [Monty::FunctionCall, Monty::PendingFutures, Monty::Complete]
     #
     # @example
@@ -84,11 +347,16 @@ module Monty
     #
     #   final_value = progress.value  # Monty::Complete
     #
-    def start(*inputs, limits: nil)
+    def start(*positional, inputs: nil, limits: nil, trace: false)
+      check_consumable
+
+      inputs = resolve_inputs(positional, inputs)
+      limits = Monty.effective_limits(limits)
+
       if limits
-        _start_with_limits(inputs, limits)
+        _start_with_limits(inputs, limits, trace)
       else
-        _start(inputs)
+        _start(inputs, trace)
       end
     end
 
@@ -97,9 +365,12 @@ module Monty
     # The block receives a FunctionCall object and should return the result.
     # Execution continues automatically until completion.
     #
-    # @param inputs positional arguments matching the input variable names
+    # @param positional positional arguments matching the input variable names
+    # @param inputs [Hash, nil] inputs keyed by name instead of position, as for #call
     # @param limits [Hash, nil] resource limits (max_allocations:, max_duration:, max_memory:, etc.)
     # @param capture_output [Boolean] if true, returns a Hash with :result and :output keys
+    # @param audit [Monty::AuditLog, nil] records inputs, every external and
+    #   OS call with the value returned to the script, and the result or error
     # @yield [Monty::FunctionCall] called when Python invokes an external function
     # @yieldreturn [Object] the return value to provide to the Python code
     # @return [Object] the final Python return value converted to Ruby, or Hash if capture_output
@@ -114,9 +385,11 @@ module Monty
     #     end
     #   end
     #
-    def call_with_externals(*inputs, limits: nil, capture_output: false, &block)
+    def call_with_externals(*positional, inputs: nil, limits: nil, capture_output: false, audit: nil, &block)
       raise ArgumentError, "a block is required" unless block_given?
 
+      inputs = resolve_inputs(positional, inputs)
+      audit&.record_inputs(inputs)
       progress = start(*inputs, limits: limits)
       output = +""
 
@@ -128,9 +401,12 @@ module Monty
         case progress
         when Monty::Complete
           result = progress.value
+          audit&.record_result(result)
           return capture_output ? {result: result, output: output} : result
         when Monty::FunctionCall
+          audit&.record_call(progress)
           result = yield progress
+          audit&.record_external_result(progress, result)
           progress = progress.resume(result)
         when Monty::PendingFutures
           raise Monty::Error, "async futures are not supported by call_with_externals"
@@ -138,13 +414,128 @@ module Monty
           raise Monty::Error, "unexpected progress type: #{progress.class}"
         end
       end
+    rescue Monty::Error => e
+      audit&.record_error(e)
+      raise
+    end
+
+    # Execute with a block that answers external function calls, driving the
+    # run to completion inside the extension.
+    #
+    # Unlike #call_with_externals the block receives the call's name,
+    # positional args and kwargs directly, and no FunctionCall objects are
+    # created between segments.
+    #
+    # @param positional positional arguments matching the input variable names
+    # @param inputs [Hash, nil] inputs keyed by name instead of position, as for #call
+    # @param limits [Hash, nil] resource limits (max_allocations:, max_duration:, max_memory:, etc.)
+    # @yield [name, args, kwargs] called when Python invokes an external function
+    # @yieldparam name [String] the external function name
+    # @yieldparam args [Array] positional arguments
+    # @yieldparam kwargs [Hash] keyword arguments
+    # @yieldreturn [Object] the return value to provide to the Python code
+    # @return [Object] the final Python return value converted to Ruby
+    #
+    # @example
+    #   run.execute(url) do |name, args, kwargs|
+    #     name == "fetch" ? http_get(args[0], **kwargs) : raise("unknown: #{name}")
+    #   end
+    #
+    def execute(*positional, inputs: nil, limits: nil, &block)
+      raise ArgumentError, "a block is required" unless block
+      check_consumable
+
+      _execute(resolve_inputs(positional, inputs), Monty.effective_limits(limits), &block)
+    end
+
+    # Freeze this Run and make it Ractor-shareable, so a script compiled
+    # once can be executed in parallel Ractors. A frozen Run supports the
+    # non-consuming methods (#call, #map, #typecheck, ...); #start and the
+    # methods built on it raise FrozenError. Any #call_incremental cache is
+    # discarded first, as its lock and inputs are not shareable.
+    #
+    # Errors raised inside a Ractor are redacted only if Monty.redactor is
+    # itself shareable, e.g. made so with Ractor.make_shareable; otherwise
+    # their text is withheld.
+    #
+    # @return [self]
+    #
+    # @example
+    #   run = Monty::Run.new("score(x)", inputs: ["x"]).freeze_for_ractor
+    #   ractors = batches.map { |batch| Ractor.new(run, batch) { |r, b| b.map { r.call(_1) } } }
+    #
+    def freeze_for_ractor
+      INCREMENTAL_SETUP.synchronize do
+        if defined?(@incremental_lock)
+          @incremental_lock.synchronize do
+            remove_instance_variable(:@incremental_inputs) if defined?(@incremental_inputs)
+          end
+          remove_instance_variable(:@incremental_lock)
+        end
+        Ractor.make_shareable(self)
+      end
     end
 
     # Serialize this Run to bytes for later restoration via Run.load
     #
+    # When an expiry is given it is embedded in the dump, and Run.load
+    # raises Monty::SnapshotExpiredError once it has passed.
+    #
+    # Dumps may contain sensitive data; pass +encrypt+ to protect them at
+    # rest. It receives the serialized bytes as a binary String and its
+    # return value is what #dump returns. Pair it with +decrypt+ on Run.load.
+    #
+    # @param expires_in [Numeric, nil] seconds from now until the dump expires
+    # @param expires_at [Time, nil] absolute expiry time
+    # @param encrypt [#call, nil] callable encrypting the serialized bytes
     # @return [String] serialized bytes
-    def dump
-      _dump
+    #
+    # @example With ActiveSupport::MessageEncryptor
+    #   blob = run.dump(encrypt: encryptor.method(:encrypt_and_sign))
+    #   Monty::Run.load(blob, decrypt: encryptor.method(:decrypt_and_verify))
+    #
+    def dump(expires_in: nil, expires_at: nil, encrypt: nil)
+      expires_at ||= Time.now + expires_in if expires_in
+      bytes = _dump(expires_at&.to_f)
+      encrypt ? encrypt.call(bytes.pack("C*")) : bytes
+    end
+
+    private
+
+    # The positional input Array for a call given either positional inputs
+    # or a Hash of named ones.
+    def resolve_inputs(positional, named)
+      return positional unless named
+      raise ArgumentError, "pass inputs positionally or with inputs:, not both" unless positional.empty?
+
+      _named_inputs(named)
+    end
+
+    # Converted inputs for #call_incremental, reusing the cached
+    # Monty::SharedData of each input that is unchanged. Called with
+    # @incremental_lock held, so threads sharing the Run never see another
+    # thread's half-updated cache. A thread that was waiting for the lock
+    # when #freeze_for_ractor dropped the cache converts every input.
+    def incremental_inputs(inputs)
+      return inputs if frozen?
+
+      @incremental_inputs ||= []
+      converted = inputs.each_with_index.map do |value, index|
+        key = value.hash
+        cached_key, cached_value, shared = @incremental_inputs[index]
+        unless shared && cached_key == key && cached_value.eql?(value)
+          shared = Monty::SharedData.new(value)
+          @incremental_inputs[index] = [key, value, shared]
+        end
+        shared
+      end
+      @incremental_inputs.slice!(inputs.size..)
+      converted
+    end
+
+    # A frozen Run may be shared between Ractors, so it cannot be consumed.
+    def check_consumable
+      raise FrozenError.new("can't consume a frozen #{self.class}; use #call instead", receiver: self) if frozen?
     end
   end
 end
@@ -0,0 +1,129 @@
+# frozen_string_literal: true
+
+module Monty
+  # Admission control for runs shared between tenants.
+  #
+  # Caps how many runs execute at once overall and per tenant. When a slot
+  # frees up it goes to the waiting run whose tenant currently has the
+  # fewest runs executing, then to the tenant served least recently, so
+  # one tenant submitting a burst of scripts cannot starve the others.
+  #
+  # Runs submitted with priority :interactive are admitted before any
+  # waiting :batch run, so UI-triggered runs skip the background queue.
+  # Runs already executing are never interrupted.
+  #
+  # @example In a multi-tenant request path
+  #   SCHEDULER = Monty::Scheduler.new(max_concurrency: 8, max_per_tenant: 2)
+  #
+  #   SCHEDULER.run(tenant: account.id) { rule.call(order) }
+  #   SCHEDULER.run(tenant: account.id, priority: :batch) { model.call(row) }
+  #
+  class Scheduler
+    PRIORITIES = %i[interactive batch].freeze
+
+    Waiter = Struct.new(:tenant, :rank, :sequence)
+    private_constant :Waiter
+
+    # @param max_concurrency [Integer] runs executing at once across tenants
+    # @param max_per_tenant [Integer] runs executing at once for one tenant
+    def initialize(max_concurrency:, max_per_tenant: max_concurrency)
+      @max_concurrency = max_concurrency
+      @max_per_tenant = max_per_tenant
+      @mutex = Mutex.new
+      @condition = ConditionVariable.new
+      @running = Hash.new(0)
+      @waiting = []
+      @sequence = 0
+      @grants = 0
+      @last_served = {}
+      @waits = PRIORITIES.to_h { |priority| [priority, {count: 0, total: 0.0, max: 0.0}] }
+    end
+
+    # Wait for a slot for +tenant+, then run the block in it.
+    #
+    # @param tenant [Object] tenant key, compared with #eql?
+    # @param priority [Symbol] :interactive (default) or :batch
+    # @return the block's return value
+    def run(tenant:, priority: :interactive)
+      unless PRIORITIES.include?(priority)
+        raise ArgumentError, "priority must be one of #{PRIORITIES.inspect}, got #{priority.inspect}"
+      end
+
+      acquire(tenant, priority)
+      begin
+        yield
+      ensure
+        release(tenant)
+      end
+    end
+
+    # Snapshot of the scheduler's state.
+    #
+    # @return [Hash] :running (Hash of tenant => executing runs), :queued,
+    #   and :wait (per priority, the :count, :total and :max seconds runs
+    #   spent queued)
+    def stats
+      @mutex.synchronize do
+        {running: @running.dup, queued: @waiting.size, wait: @waits.transform_values(&:dup)}
+      end
+    end
+
+    private
+
+    def acquire(tenant, priority)
+      enqueued_at = Process.clock_gettime(Process::CLOCK_MONOTONIC)
+      @mutex.synchronize do
+        waiter = Waiter.new(tenant, PRIORITIES.index(priority), @sequence += 1)
+        @waiting << waiter
+        begin
+          @condition.wait(@mutex) until next_waiter.equal?(waiter)
+        ensure
+          @waiting.delete(waiter)
+          forget_if_idle(tenant)
+          # A waiter killed or timed out after being picked would leave the
+          # slot unclaimed; let the others pick again.
+          @condition.broadcast
+        end
+        @running[tenant] += 1
+        @last_served[tenant] = @grants += 1
+        record_wait(priority, Process.clock_gettime(Process::CLOCK_MONOTONIC) - enqueued_at)
+        @condition.broadcast
+      end
+    end
+
+    def release(tenant)
+      @mutex.synchronize do
+        @running[tenant] -= 1
+        @running.delete(tenant) if @running[tenant].zero?
+        forget_if_idle(tenant)
+        @condition.broadcast
+      end
+    end
+
+    # Drop when +tenant+ was last served once it has nothing executing or
+    # queued, so the map only grows with active tenants. A returning tenant
+    # counts as never served, like a new one.
+    def forget_if_idle(tenant)
+      return if @running.key?(tenant) || @waiting.any? { |waiter| waiter.tenant.eql?(tenant) }
+
+      @last_served.delete(tenant)
+    end
+
+    def record_wait(priority, seconds)
+      wait = @waits[priority]
+      wait[:count] += 1
+      wait[:total] += seconds
+      wait[:max] = seconds if seconds > wait[:max]
+    end
+
+    def next_waiter
+      return nil if @running.values.sum >= @max_concurrency
+
+      @waiting
+        .select { |waiter| @running[waiter.tenant] < @max_per_tenant }
+        .min_by do |waiter|
+          [waiter.rank, @running[waiter.tenant], @last_served.fetch(waiter.tenant, 0), waiter.sequence]
+        end
+    end
+  end
+end
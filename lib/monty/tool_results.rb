@@ -0,0 +1,20 @@
+# frozen_string_literal: true
+
+module Monty
+  class << self
+    # Callable used to shrink a value passed to FunctionCall#resume or
+    # PendingFutures#resume that exceeds the run's max_tool_result_bytes
+    # limit. It receives the value and the limit in bytes and returns a
+    # smaller value to hand to the script instead. Without one, oversized
+    # values raise Monty::ResourceError.
+    #
+    # @return [#call, nil]
+    #
+    # @example Truncating large API responses
+    #   Monty.tool_result_summarizer = lambda do |value, max_bytes|
+    #     value.is_a?(String) ? value.byteslice(0, max_bytes) : value.take(100)
+    #   end
+    #
+    attr_accessor :tool_result_summarizer
+  end
+end
@@ -0,0 +1,25 @@
+# frozen_string_literal: true
+
+require "json"
+
+module Monty
+  # Chrome trace export for progress objects of a run started with
+  # Run#start(trace: true).
+  module Traceable
+    # The timeline as JSON for chrome://tracing or https://ui.perfetto.dev.
+    #
+    # @return [String, nil] nil unless the run was started with trace: true
+    #
+    # @example
+    #   progress = run.start(order, trace: true)
+    #   progress = progress.resume(handle(progress)) while progress.is_a?(Monty::FunctionCall)
+    #   File.write("session.trace.json", progress.chrome_trace)
+    #
+    def chrome_trace
+      events = trace_events
+      events && JSON.generate({traceEvents: events, displayTimeUnit: "ms"})
+    end
+  end
+
+  [FunctionCall, PendingFutures, Complete].each { |klass| klass.include(Traceable) }
+end
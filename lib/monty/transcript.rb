@@ -0,0 +1,155 @@
+# frozen_string_literal: true
+
+require "json"
+
+module Monty
+  # Everything a run with external functions exchanged with the host:
+  # its inputs, each external call with the value returned for it, and
+  # the final result and output.
+  #
+  # Record one against a known-good version of a script, store #to_h
+  # (e.g. as JSON), then #replay it against a new version: the recorded
+  # results are fed back in place of the real external functions and the
+  # first call that differs is reported.
+  #
+  # @example Golden test for an agent flow
+  #   transcript = Monty::Transcript.record(Monty::Run.new(v1, **opts), task) { |call| tools.dispatch(call) }
+  #   File.write("golden.json", JSON.dump(transcript.to_h))
+  #
+  #   golden = Monty::Transcript.from_h(JSON.parse(File.read("golden.json")))
+  #   expect(golden.replay(Monty::Run.new(v2, **opts))).to be_nil
+  #
+  class Transcript
+    # Where a replay stopped matching the transcript. +index+ is the
+    # position of the first differing call, or the number of recorded
+    # calls when only the final result or output differs. +expected+ and
+    # +actual+ are call Hashes (or nil when one side made no such call),
+    # or Hashes with :result and :output.
+    Divergence = Struct.new(:index, :expected, :actual, keyword_init: true)
+
+    # @return [Array] positional inputs the run was started with
+    attr_reader :inputs
+
+    # @return [Array<Hash>] external calls in order, each with :function,
+    #   :args, :kwargs and :result
+    attr_reader :calls
+
+    # @return [Object] final value of the script
+    attr_reader :result
+
+    # @return [String] everything the script printed
+    attr_reader :output
+
+    # Run to completion, answering external calls with the block, and
+    # record the exchange. Consumes +run+.
+    #
+    # @param run [Monty::Run]
+    # @param inputs positional arguments matching the input variable names
+    # @param limits [Hash, nil] resource limits
+    # @yieldparam call [Monty::FunctionCall]
+    # @yieldreturn the value to return to Python
+    # @return [Monty::Transcript]
+    def self.record(run, *inputs, limits: nil)
+      calls = []
+      outcome = run.call_with_externals(*inputs, limits: limits, capture_output: true) do |call|
+        value = yield call
+        calls << {**describe(call), result: value}
+        value
+      end
+      new(inputs: inputs, calls: calls, result: outcome[:result], output: outcome[:output])
+    end
+
+    # Rebuild a transcript from #to_h, with Symbol or String keys (as
+    # after a JSON round trip).
+    #
+    # @param hash [Hash]
+    # @return [Monty::Transcript]
+    def self.from_h(hash)
+      hash = hash.transform_keys(&:to_sym)
+      calls = hash.fetch(:calls).map do |call|
+        call = call.transform_keys(&:to_sym)
+        {**call, kwargs: call.fetch(:kwargs, {}).transform_keys(&:to_s)}
+      end
+      new(inputs: hash.fetch(:inputs), calls: calls, result: hash[:result], output: hash.fetch(:output, ""))
+    end
+
+    # @api private
+    def self.describe(call)
+      {function: call.function_name, args: call.args, kwargs: call.kwargs.transform_keys(&:to_s)}
+    end
+    private_class_method :describe
+
+    def initialize(inputs:, calls:, result:, output:)
+      @inputs = inputs
+      @calls = calls
+      @result = result
+      @output = output
+    end
+
+    # @return [Hash] :inputs, :calls, :result and :output
+    def to_h
+      {inputs: inputs, calls: calls, result: result, output: output}
+    end
+
+    # The external calls as chat messages in the OpenAI tool-calling
+    # shape: for each call, an assistant message carrying one tool call
+    # followed by the tool message with its result. Positional arguments
+    # are passed as "args" alongside the keyword arguments; results are
+    # JSON encoded.
+    #
+    # @return [String] JSON Array of messages
+    #
+    # @example
+    #   JSON.parse(transcript.transcript_json).first
+    #   # => {"role" => "assistant", "content" => nil, "tool_calls" => [{"id" => "call_0",
+    #   #     "type" => "function", "function" => {"name" => "fetch", "arguments" => "{\"args\":[1]}"}}]}
+    #
+    def transcript_json
+      messages = calls.each_with_index.flat_map do |call, index|
+        id = "call_#{index}"
+        arguments = call[:args].empty? ? call[:kwargs] : {"args" => call[:args], **call[:kwargs]}
+        [
+          {role: "assistant", content: nil, tool_calls: [
+            {id: id, type: "function", function: {name: call[:function], arguments: JSON.generate(arguments)}}
+          ]},
+          {role: "tool", tool_call_id: id, content: JSON.generate(call[:result])}
+        ]
+      end
+      JSON.generate(messages)
+    end
+
+    # Run a new version of the script on the recorded inputs, answering
+    # each external call with the recorded result as long as the calls
+    # match. Consumes +run+.
+    #
+    # @param run [Monty::Run]
+    # @param limits [Hash, nil] resource limits
+    # @return [Divergence, nil] nil when the calls, result and output all
+    #   match the transcript
+    def replay(run, limits: nil)
+      index = 0
+      catch(:monty_transcript_diverged) do
+        outcome = run.call_with_externals(*inputs, limits: limits, capture_output: true) do |call|
+          actual = self.class.send(:describe, call)
+          expected = calls[index]&.reject { |key, _| key == :result }
+          throw :monty_transcript_diverged, divergence(index, calls[index], actual) unless actual == expected
+
+          index += 1
+          calls[index - 1][:result]
+        end
+
+        return divergence(index, calls[index], nil) if index < calls.size
+
+        actual = {result: outcome[:result], output: outcome[:output]}
+        expected = {result: result, output: output}
+        divergence(index, expected, actual) unless actual == expected
+      end
+    end
+
+    private
+
+    def divergence(index, expected, actual)
+      Divergence.new(index: index, expected: expected, actual: actual)
+    end
+  end
+end
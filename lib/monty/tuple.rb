@@ -0,0 +1,20 @@
+# frozen_string_literal: true
+
+module Monty
+  # A Python tuple. Results return tuples as frozen Monty::Tuples, and
+  # Monty::Tuple inputs arrive in Python as tuples rather than lists.
+  # Being an Array, it compares equal to an Array with the same items.
+  #
+  # @example
+  #   run = Monty::Run.new("isinstance(x, tuple)", inputs: ["x"])
+  #   run.call(Monty::Tuple[1, 2]) # => true
+  class Tuple
+    # Build a frozen tuple from its items.
+    #
+    # @param items the tuple's items
+    # @return [Monty::Tuple]
+    def self.[](*items)
+      new(items).freeze
+    end
+  end
+end
@@ -0,0 +1,33 @@
+# frozen_string_literal: true
+
+RSpec.describe Monty::AuditLog do
+  it "records inputs, external calls and the result" do
+    run = Monty::Run.new("fetch(key) + 1", inputs: ["key"], external_functions: ["fetch"])
+    audit = described_class.new
+
+    expect(run.call_with_externals("k", audit: audit) { |_call| 41 }).to eq(42)
+    expect(audit.map { |entry| entry[:event] }).to eq(%i[input external_call external_result result])
+    expect(audit.to_a[1]).to include(name: "fetch", args: ['"k"'])
+    expect(audit.to_a[3][:value]).to eq("42")
+  end
+
+  it "records errors and truncates long values" do
+    run = Monty::Run.new("1 / 0 if len(s) else 0", inputs: ["s"])
+    audit = described_class.new(max_repr_length: 10)
+
+    expect { run.call("x" * 100, audit: audit) }.to raise_error(Monty::Error)
+    expect(audit.to_a.first[:value]).to eq("\"xxxxxxxxx...")
+    expect(audit.to_a.last).to include(event: :error, class: "Monty::ZeroDivisionError")
+  end
+
+  it "defaults to Monty.max_repr_length" do
+    previous = Monty.max_repr_length
+    Monty.max_repr_length = 5
+    audit = described_class.new
+    audit.record_inputs(["abcdefgh"])
+
+    expect(audit.to_a.first[:value]).to eq("\"abcd...")
+  ensure
+    Monty.max_repr_length = previous
+  end
+end
@@ -0,0 +1,57 @@
+# frozen_string_literal: true
+
+RSpec.describe "Monty::Run#batch" do
+  let(:run) { Monty::Run.new("10 // x", inputs: ["x"]) }
+
+  it "collects every result and error by default" do
+    report = run.batch([[1], [0], [5]])
+
+    expect(report).to be_a(Monty::BatchReport)
+    expect(report.results).to eq([10, nil, 2])
+    expect(report.errors.keys).to eq([1])
+    expect(report.skipped).to eq([])
+    expect(report).not_to be_success
+    expect(report.stats[0][:duration]).to be >= 0
+  end
+
+  it "stops starting entries after the first error with on_error: :stop" do
+    report = run.batch([[0]] + [[1]] * 20, concurrency: 1, on_error: :stop)
+
+    expect(report.errors.keys).to eq([0])
+    expect(report).to be_stopped
+    expect(report.skipped).not_to be_empty
+  end
+
+  it "applies per-item limit overrides" do
+    slow = Monty::Run.new("while x:\n    x -= 1\nx", inputs: ["x"])
+    report = slow.batch([[10], [10**9]], item_limits: {1 => {max_duration: 0.05}})
+
+    expect(report.results[0]).to eq(0)
+    expect(report.errors[1]).to be_a(Monty::ResourceError)
+  end
+
+  it "bounds the whole batch with a deadline" do
+    slow = Monty::Run.new("while True:\n    pass")
+    report = slow.batch([[]] * 4, concurrency: 1, deadline: 0.1)
+
+    expect(report.errors[0]).to be_a(Monty::ResourceError)
+    expect(report.skipped).to eq([1, 2, 3])
+    expect(report.duration).to be < 1
+  end
+
+  it "treats a nil deadline as none" do
+    expect(run.batch([[1], [5]], deadline: nil).results).to eq([10, 2])
+  end
+
+  it "treats an infinite deadline as none" do
+    expect(run.batch([[1], [5]], deadline: Float::INFINITY).results).to eq([10, 2])
+  end
+
+  it "rejects a negative deadline" do
+    expect { run.batch([[1]], deadline: -1) }.to raise_error(ArgumentError, /deadline/)
+  end
+
+  it "rejects a NaN deadline" do
+    expect { run.batch([[1]], deadline: Float::NAN) }.to raise_error(ArgumentError, /deadline/)
+  end
+end
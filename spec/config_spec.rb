@@ -0,0 +1,50 @@
+# frozen_string_literal: true
+
+RSpec.describe "Monty.with_config" do
+  it "applies limits to runs started inside the block" do
+    run = Monty::Run.new("x = 0\nwhile True:\n    x += 1")
+    expect {
+      Monty.with_config(limits: {max_duration: 0.1}) { run.call }
+    }.to raise_error(Monty::ResourceError)
+  end
+
+  it "keeps the stricter value when limits are combined" do
+    Monty.with_config(limits: {max_duration: 1.0}) do
+      expect(Monty.effective_limits(max_duration: 5.0)).to eq(max_duration: 1.0)
+      expect(Monty.effective_limits(max_memory: 1024)).to eq(max_duration: 1.0, max_memory: 1024)
+    end
+  end
+
+  it "compares limits written in different units" do
+    Monty.with_config(limits: {max_memory: "1MB", max_duration: "500ms"}) do
+      expect(Monty.effective_limits(max_memory: "10GB", max_duration: 2))
+        .to eq(max_memory: 1_000_000, max_duration: 0.5)
+      expect(Monty.effective_limits(max_memory: "1KiB")).to eq(max_memory: 1024, max_duration: 0.5)
+    end
+  end
+
+  it "keeps the scoped limit when a call passes nil" do
+    Monty.with_config(limits: {max_duration: 1.0}) do
+      expect(Monty.effective_limits(max_duration: nil)).to eq(max_duration: 1.0)
+    end
+  end
+
+  it "compares result byte sizes written as strings" do
+    Monty.with_config(limits: {max_result_bytes: 1024, max_tool_result_bytes: "1KB"}) do
+      expect(Monty.effective_limits(max_result_bytes: "1GB", max_tool_result_bytes: 10_000))
+        .to eq(max_result_bytes: 1024, max_tool_result_bytes: 1000)
+    end
+  end
+
+  it "raises when a limit's values cannot be compared" do
+    Monty.with_config(limits: {max_allocations: 100}) do
+      expect { Monty.effective_limits(max_allocations: "lots") }
+        .to raise_error(ArgumentError, /cannot merge limit :max_allocations/)
+    end
+  end
+
+  it "restores the previous configuration afterwards" do
+    Monty.with_config(limits: {max_duration: 1.0}) {}
+    expect(Monty.current_config[:limits]).to be_nil
+  end
+end
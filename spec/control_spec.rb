@@ -0,0 +1,108 @@
+# frozen_string_literal: true
+
+RSpec.describe "Monty run controls" do
+  after { Monty.undrain! }
+
+  it "rejects new runs while draining" do
+    run = Monty::Run.new("1 + 1")
+    Monty.drain!
+
+    expect(Monty.draining?).to be(true)
+    expect { run.call }.to raise_error(Monty::Interrupted, /draining/)
+
+    Monty.undrain!
+    expect(run.call).to eq(2)
+  end
+
+  it "stops paused runs at their next resume" do
+    run = Monty::Run.new("fetch() + fetch()", external_functions: ["fetch"])
+    progress = run.start
+    Monty.abort_all!
+
+    expect { progress.resume(1) }.to raise_error(Monty::Interrupted) { |error|
+      expect(error.stats[:segments]).to eq(1)
+    }
+  end
+
+  it "stops scripts that are executing" do
+    run = Monty::Run.new("while True:\n    pass")
+    workers = [
+      Thread.new { run.call },
+      Thread.new { run.call(limits: {max_duration: 60}) },
+      Thread.new { run.map(Array.new(4) { [] }, concurrency: 2) }
+    ]
+    Thread.pass until Monty.active_runs.count { |r| r[:state] == :running } >= 3
+    Monty.abort_all!
+
+    workers.each do |worker|
+      expect { worker.value }.to raise_error(Monty::Interrupted, /abort_all!/)
+    end
+  end
+
+  it "does not affect runs started after the abort" do
+    Monty.abort_all!
+    run = Monty::Run.new("fetch() + 1", external_functions: ["fetch"])
+
+    expect(run.start.resume(41).value).to eq(42)
+  end
+
+  it "lists in-flight runs and cancels one by id" do
+    run = Monty::Run.new("fetch()", script_name: "rule.py", external_functions: ["fetch"])
+    progress = run.start
+    entry = Monty.active_runs.find { |r| r[:id] == progress.run_id }
+
+    expect(entry).to include(script_name: "rule.py", state: :paused)
+    expect(entry[:elapsed]).to be >= 0
+
+    expect(Monty.cancel_run(progress.run_id)).to be(true)
+    expect { progress.resume(1) }.to raise_error(Monty::Interrupted, /cancelled/)
+  end
+
+  it "cancels a script that is executing" do
+    run = Monty::Run.new("while True:\n    pass", script_name: "spin.py")
+    worker = Thread.new { run.call }
+    Thread.pass until (entry = Monty.active_runs.find { |r| r[:script_name] == "spin.py" })
+
+    expect(Monty.cancel_run(entry[:id])).to be(true)
+    expect { worker.value }.to raise_error(Monty::Interrupted, /cancelled/)
+  end
+
+  it "tracks runs started within another and cancels the whole tree" do
+    parent = Monty::Run.new("fetch()", external_functions: ["fetch"]).start
+    child, grandchild = Monty.within_run(parent.run_id) do
+      child = Monty::Run.new("fetch()", external_functions: ["fetch"]).start
+      [child, Monty.within_run(child.run_id) { Monty::Run.new("fetch()", external_functions: ["fetch"]).start }]
+    end
+    unrelated = Monty::Run.new("fetch()", external_functions: ["fetch"]).start
+
+    parents = Monty.active_runs.to_h { |r| [r[:id], r[:parent_id]] }
+    expect(parents.values_at(parent.run_id, child.run_id, grandchild.run_id))
+      .to eq([nil, parent.run_id, child.run_id])
+
+    expect(Monty.cancel_tree(parent.run_id)).to eq(3)
+    [parent, child, grandchild].each do |progress|
+      expect { progress.resume(1) }.to raise_error(Monty::Interrupted)
+    end
+    expect(unrelated.resume(1).value).to eq(1)
+  end
+
+  it "keeps the parent run per fiber" do
+    parent = Monty::Run.new("fetch()", external_functions: ["fetch"]).start
+    other_fiber = Fiber.new { Monty::Run.new("fetch()", external_functions: ["fetch"]).start }
+    child, sibling = Monty.within_run(parent.run_id) do
+      [Monty::Run.new("fetch()", external_functions: ["fetch"]).start, other_fiber.resume]
+    end
+
+    parents = Monty.active_runs.to_h { |r| [r[:id], r[:parent_id]] }
+    expect(parents.values_at(child.run_id, sibling.run_id)).to eq([parent.run_id, nil])
+  end
+
+  it "drops completed runs from the list" do
+    progress = Monty::Run.new("fetch()", external_functions: ["fetch"]).start
+    id = progress.run_id
+    progress.resume(1).value
+
+    GC.start
+    expect(Monty.active_runs.map { |r| r[:id] }).not_to include(id)
+  end
+end
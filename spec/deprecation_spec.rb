@@ -0,0 +1,52 @@
+# frozen_string_literal: true
+
+RSpec.describe "Monty.deprecate_method" do
+  let(:klass) do
+    Class.new do
+      def self.name
+        "Monty::Example#{object_id}"
+      end
+
+      def evaluate(value, scale: 1)
+        value * scale
+      end
+    end
+  end
+
+  let(:events) { [] }
+
+  before do
+    Monty.deprecate_method(klass, :run, :evaluate)
+    Monty.instrumenter = ->(event, payload) { events << [event, payload] }
+  end
+
+  after { Monty.instrumenter = nil }
+
+  it "forwards the old name to the new one" do
+    expect(klass.new.run(2, scale: 3)).to eq(6)
+  end
+
+  it "reports a single deprecation event per process" do
+    instance = klass.new
+    3.times { instance.run(1) }
+
+    expect(events.size).to eq(1)
+    event, payload = events.first
+    expect(event).to eq("deprecation.monty")
+    expect(payload[:method]).to eq("#{klass.name}#run")
+    expect(payload[:replacement]).to eq("#{klass.name}#evaluate")
+    expect(payload[:caller]).to include("deprecation_spec.rb")
+  end
+
+  it "warns when no instrumenter is configured" do
+    Monty.instrumenter = nil
+
+    expect { klass.new.run(1) }.to output(/#run is deprecated, use .*#evaluate instead/).to_stderr
+  end
+
+  it "can be called from a non-main Ractor" do
+    ractor = Ractor.new(klass) { |k| k.new.run(2, scale: 3) }
+
+    expect(ractor.respond_to?(:value) ? ractor.value : ractor.take).to eq(6)
+  end
+end
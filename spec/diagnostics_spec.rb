@@ -0,0 +1,25 @@
+# frozen_string_literal: true
+
+RSpec.describe "Monty.diagnostics" do
+  it "returns an empty array for valid code" do
+    expect(Monty.diagnostics("x + 1", inputs: ["x"])).to eq([])
+  end
+
+  it "reports syntax errors with a span instead of raising" do
+    diagnostics = Monty.diagnostics("x = 1\ndef !!!")
+
+    expect(diagnostics.size).to eq(1)
+    expect(diagnostics.first[:severity]).to eq(:error)
+    expect(diagnostics.first[:message]).to include("SyntaxError")
+    expect(diagnostics.first[:span][:line]).to eq(2)
+  end
+
+  it "warns about declared inputs and external functions the code never uses" do
+    diagnostics = Monty.diagnostics("x + 1", inputs: ["x", "y"], external_functions: ["fetch"])
+
+    expect(diagnostics).to eq([
+      {severity: :warning, message: "input 'y' is never used", span: nil},
+      {severity: :warning, message: "external function 'fetch' is never called", span: nil}
+    ])
+  end
+end
@@ -0,0 +1,29 @@
+# frozen_string_literal: true
+
+RSpec.describe "Monty.diff" do
+  it "returns an empty array for equal values" do
+    expect(Monty.diff({"a" => [1, 2]}, {"a" => [1.0, 2]})).to eq([])
+  end
+
+  it "reports added, removed and changed paths" do
+    before = {"total" => 10, "items" => [1, 2, 3], "note" => "x"}
+    after = {"total" => 12, "items" => [1, 2], "currency" => "EUR"}
+
+    expect(Monty.diff(before, after)).to contain_exactly(
+      {op: :changed, path: ["total"], from: 10, to: 12},
+      {op: :removed, path: ["items", 2], from: 3},
+      {op: :removed, path: ["note"], from: "x"},
+      {op: :added, path: ["currency"], to: "EUR"}
+    )
+  end
+
+  it "accepts Monty::Object handles" do
+    before = Monty::Run.new("{'score': 1, 'tags': ('a',)}").start.object
+    after = Monty::Run.new("{'score': 2, 'tags': ('a', 'b')}").start.object
+
+    expect(Monty.diff(before, after)).to eq([
+      {op: :changed, path: ["score"], from: 1, to: 2},
+      {op: :added, path: ["tags", 1], to: "b"}
+    ])
+  end
+end
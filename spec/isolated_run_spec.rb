@@ -0,0 +1,50 @@
+# frozen_string_literal: true
+
+RSpec.describe Monty::IsolatedRun do
+  before { skip "fork is not available" unless Process.respond_to?(:fork) }
+
+  it "executes the script in a child process" do
+    run = Monty::IsolatedRun.new("x * 2", inputs: ["x"])
+    expect(run.call(21)).to eq(42)
+  end
+
+  it "forwards external calls to the parent" do
+    parent_pid = Process.pid
+    run = Monty::IsolatedRun.new("fetch('a') + '!'", external_functions: ["fetch"])
+
+    result = run.call { |call| "#{call.function_name}:#{Process.pid == parent_pid}" }
+    expect(result).to eq("fetch:true!")
+  end
+
+  it "keeps Python types and captured output across the process boundary" do
+    run = Monty::IsolatedRun.new("print('hi')\n(1, {2, 3})")
+    result = run.call(capture_output: true)
+
+    expect(result).to eq(result: [1, Set[2, 3]], output: "hi\n")
+    expect(result[:result]).to be_a(Monty::Tuple)
+  end
+
+  it "reports non-Monty exceptions raised in the child" do
+    input = Object.new
+    def input.to_monty = raise(ArgumentError, "boom")
+    run = Monty::IsolatedRun.new("x", inputs: ["x"])
+
+    expect { run.call(input) }.to raise_error(Monty::Error, /isolated run failed: ArgumentError: boom/)
+  end
+
+  it "re-raises interpreter errors in the parent" do
+    run = Monty::IsolatedRun.new("1 / 0")
+    expect { run.call }.to raise_error(Monty::Error)
+  end
+
+  it "reports cooperative aborts of time limits" do
+    run = Monty::IsolatedRun.new("x = 0\nwhile True:\n    x += 1")
+    expect { run.call(limits: {max_duration: 0.1}) }
+      .to raise_error(Monty::TimeLimitExceeded, /cooperative abort/)
+  end
+
+  it "accepts a max_duration written with a unit" do
+    run = Monty::IsolatedRun.new("x + 1", inputs: ["x"])
+    expect(run.call(1, limits: {max_duration: "2s"})).to eq(2)
+  end
+end
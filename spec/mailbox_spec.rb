@@ -0,0 +1,41 @@
+# frozen_string_literal: true
+
+require "timeout"
+
+# Worker threads hand results back through a mailbox that the calling
+# Ruby thread drains; these specs exercise it through Run#map.
+RSpec.describe "Run#map result delivery" do
+  it "yields every execution on the calling thread" do
+    run = Monty::Run.new("x + 1", inputs: ["x"])
+    caller = Thread.current
+    threads = []
+
+    run.map(Array.new(8) { |i| [i] }, concurrency: 4) { threads << Thread.current }
+
+    expect(threads.size).to eq(8)
+    expect(threads).to all(equal(caller))
+  end
+
+  it "returns from the wait when the calling thread is interrupted" do
+    run = Monty::Run.new("while True:\n    pass")
+    started = Process.clock_gettime(Process::CLOCK_MONOTONIC)
+
+    expect {
+      Timeout.timeout(0.1) { run.map(Array.new(20) { [] }, limits: {max_duration: 0.3}, concurrency: 1) }
+    }.to raise_error(Timeout::Error)
+    expect(Process.clock_gettime(Process::CLOCK_MONOTONIC) - started).to be < 2
+  end
+
+  it "stops delivering once the block raises" do
+    run = Monty::Run.new("x", inputs: ["x"])
+    seen = 0
+
+    expect {
+      run.map(Array.new(50) { |i| [i] }, concurrency: 1) do
+        seen += 1
+        raise ArgumentError, "stop" if seen == 3
+      end
+    }.to raise_error(ArgumentError, "stop")
+    expect(seen).to eq(3)
+  end
+end
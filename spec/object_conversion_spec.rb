@@ -45,6 +45,79 @@ RSpec.describe "MontyObject conversion" do
       expect(run.call(["a", "b"])).to eq(["a", "b"])
     end
 
+    it "converts Monty::Tuple to tuple" do
+      run = Monty::Run.new("isinstance(x, tuple)", inputs: ["x"])
+      expect(run.call(Monty::Tuple[1, 2])).to eq(true)
+      expect(run.call([1, 2])).to eq(false)
+    end
+
+    it "round-trips tuples" do
+      run = Monty::Run.new("x", inputs: ["x"])
+      result = run.call(Monty::Tuple[1, Monty::Tuple["a"]])
+      expect(result).to be_a(Monty::Tuple)
+      expect(result[1]).to be_a(Monty::Tuple)
+      expect(result).to be_frozen
+    end
+
+    it "converts Monty::Bytes to bytes" do
+      run = Monty::Run.new("(type(x) == bytes, x[:2], len(x))", inputs: ["x"])
+      blob = Monty::Bytes["\xFF\x00\xD8".b * 100_000]
+      is_bytes, head, size = run.call(blob)
+      expect(is_bytes).to eq(true)
+      expect(head).to eq("\xFF\x00".b)
+      expect(head.encoding).to eq(Encoding::BINARY)
+      expect(size).to eq(300_000)
+    end
+
+    it "keeps passing plain Strings as str, whatever their encoding" do
+      run = Monty::Run.new("type(x) == str", inputs: ["x"])
+      expect(run.call("héllo")).to eq(true)
+      expect(run.call("plain".b)).to eq(true)
+      expect(run.call(Monty::Bytes["héllo"])).to eq(false)
+    end
+
+    it "converts Sets to sets" do
+      run = Monty::Run.new("(type(x) == set, x | {3})", inputs: ["x"])
+      expect(run.call(Set[1, 2])).to eq([true, Set[1, 2, 3]])
+    end
+
+    it "converts frozen Sets to frozensets" do
+      run = Monty::Run.new("(type(x) == frozenset, x)", inputs: ["x"])
+      is_frozenset, result = run.call(Set["a"].freeze)
+      expect(is_frozenset).to eq(true)
+      expect(result).to eq(Set["a"])
+      expect(result).to be_frozen
+    end
+
+    it "rejects Set elements Python cannot hash" do
+      run = Monty::Run.new("x", inputs: ["x"])
+
+      expect { run.call(Set[[1, 2]]) }.to raise_error(TypeError, "unhashable type: 'list'")
+      expect { run.call(Set[{a: 1}]) }.to raise_error(TypeError, "unhashable type: 'dict'")
+      expect { run.call(Set[Set[1]]) }.to raise_error(TypeError, "unhashable type: 'set'")
+      expect(run.call(Set[Set[1].freeze])).to eq(Set[Set[1]])
+    end
+
+    it "converts objects through #to_monty" do
+      money = Struct.new(:cents, :currency) do
+        def to_monty
+          {"cents" => cents, "currency" => currency}
+        end
+      end
+      run = Monty::Run.new("[p['cents'] for p in x]", inputs: ["x"])
+      expect(run.call([money.new(150, "EUR"), money.new(99, "USD")])).to eq([150, 99])
+    end
+
+    it "bounds #to_monty chains by max_conversion_depth" do
+      looping = Class.new {
+        def to_monty
+          self
+        end
+      }.new
+      run = Monty::Run.new("x", inputs: ["x"])
+      expect { run.call(looping) }.to raise_error(Monty::ConversionError, /max_conversion_depth/)
+    end
+
     it "converts hashes to dicts" do
       run = Monty::Run.new("x", inputs: ["x"])
       expect(run.call({})).to eq({})
@@ -105,6 +178,81 @@ RSpec.describe "MontyObject conversion" do
       expect(result).to be_a(Array)
       expect(result).to be_frozen
     end
+
+    it "maps set to Set" do
+      result = Monty::Run.new("{1, 2}").call
+      expect(result).to eq(Set[1, 2])
+      expect(result).not_to be_frozen
+    end
+
+    it "maps frozenset to frozen Set" do
+      result = Monty::Run.new("frozenset([1, 2])").call
+      expect(result).to eq(Set[1, 2])
+      expect(result).to be_frozen
+    end
+
+    it "maps tuple to Monty::Tuple" do
+      run = Monty::Run.new("(1, [2, 3])")
+      result = run.call
+      expect(result).to be_a(Monty::Tuple)
+      expect(result).to eq([1, [2, 3]])
+      expect(result[1]).not_to be_a(Monty::Tuple)
+    end
+  end
+
+  describe "Monty.repr" do
+    it "shows how an input looks inside the script" do
+      expect(Monty.repr(nil)).to eq("None")
+      expect(Monty.repr([1, "a", true, 1.0])).to eq("[1, 'a', True, 1.0]")
+      expect(Monty.repr({name: Monty::Tuple[1]})).to eq("{'name': (1,)}")
+    end
+
+    it "formats floats like Python's repr" do
+      expect([1e20, 1e-7, 1.5e-7, 1e16, 9999999999999998.0, 0.0001, -0.0, 0.1].map { |f| Monty.repr(f) })
+        .to eq(["1e+20", "1e-07", "1.5e-07", "1e+16", "9999999999999998.0", "0.0001", "-0.0", "0.1"])
+    end
+
+    it "raises like an input conversion would" do
+      expect { Monty.repr(Object.new) }.to raise_error(TypeError, /cannot convert Object/)
+    end
+  end
+
+  describe "Monty.conversion_report" do
+    it "is empty for convertible values" do
+      value = {"a" => [1, 2.0, nil], :b => Set["x"], "c" => Monty::Tuple[true]}
+      expect(Monty.conversion_report(value)).to eq([])
+      expect(Monty.convertible?(value)).to eq(true)
+    end
+
+    it "reports the path and class of every failing element" do
+      value = {"ok" => 1, "when" => Time.at(0), "rows" => [1, [Object.new]]}
+      report = Monty.conversion_report(value)
+      expect(report.map { |f| [f[:path], f[:class]] }).to eq([[["when"], Time], [["rows", 1, 0], Object]])
+      expect(report.first[:message]).to eq("cannot convert Time to a Python object")
+      expect(Monty.convertible?(value)).to eq(false)
+    end
+
+    it "reports unconvertible hash keys" do
+      report = Monty.conversion_report({Object.new => 1})
+      expect(report.map { |f| f[:class] }).to eq([Object])
+    end
+
+    it "reports recursive structures instead of looping" do
+      list = [1]
+      list << list
+      expect(Monty.conversion_report(list)).to eq([
+        {path: [1], class: Array, message: "cannot convert a recursive Array"}
+      ])
+    end
+
+    it "reports values nested too deeply" do
+      previous = Monty.max_conversion_depth
+      Monty.max_conversion_depth = 2
+      report = Monty.conversion_report([[[1]]])
+      expect(report.map { |f| f[:path] }).to eq([[0, 0]])
+    ensure
+      Monty.max_conversion_depth = previous
+    end
   end
 
   describe "Python operations on Ruby inputs" do
@@ -128,4 +276,45 @@ RSpec.describe "MontyObject conversion" do
       expect(run.call(3, 1)).to eq(10)
     end
   end
+
+  describe "shared and recursive inputs" do
+    it "converts a subobject shared between inputs into equal values" do
+      tags = ["a", "b"]
+      run = Monty::Run.new("[x['tags'] == y, len(y)]", inputs: ["x", "y"])
+      expect(run.call({"tags" => tags, "more" => tags}, tags)).to eq([true, 2])
+    end
+
+    it "raises ConversionError for recursive structures" do
+      loop_array = [1]
+      loop_array << loop_array
+      run = Monty::Run.new("x", inputs: ["x"])
+      expect { run.call(loop_array) }.to raise_error(Monty::ConversionError, /recursive Array/)
+    end
+  end
+
+  describe "max_conversion_depth" do
+    around do |example|
+      previous = Monty.max_conversion_depth
+      Monty.max_conversion_depth = 10
+      example.run
+    ensure
+      Monty.max_conversion_depth = previous
+    end
+
+    it "raises ConversionError for deeply nested inputs" do
+      nested = 20.times.reduce([]) { |acc, _| [acc] }
+      run = Monty::Run.new("x", inputs: ["x"])
+      expect { run.call(nested) }.to raise_error(Monty::ConversionError)
+    end
+
+    it "raises ConversionError for deeply nested results" do
+      run = Monty::Run.new("x = []\nfor _ in range(20):\n    x = [x]\nx")
+      expect { run.call }.to raise_error(Monty::ConversionError)
+    end
+
+    it "converts values within the limit" do
+      run = Monty::Run.new("x", inputs: ["x"])
+      expect(run.call([[[1]]])).to eq([[[1]]])
+    end
+  end
 end
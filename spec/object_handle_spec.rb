@@ -0,0 +1,34 @@
+# frozen_string_literal: true
+
+RSpec.describe Monty::Object do
+  def object_for(code)
+    Monty::Run.new(code).start.object
+  end
+
+  it "converts to a Ruby value on demand" do
+    expect(object_for("[1, 2, 3]").to_ruby).to eq([1, 2, 3])
+  end
+
+  it "compares with Python equality semantics" do
+    expect(object_for("1")).to eq(object_for("1.0"))
+    expect(object_for("{'a': 1, 'b': 2}")).to eq(object_for("{'b': 2, 'a': 1}"))
+    expect(object_for("[1, 2]")).not_to eq(object_for("(1, 2)"))
+  end
+
+  it "can be used as a Hash key" do
+    table = {object_for("(1, 'a')") => :found}
+    expect(table[object_for("(1, 'a')")]).to eq(:found)
+    expect([object_for("{1, 2}"), object_for("{2, 1}")].uniq.size).to eq(1)
+  end
+
+  describe "#[]" do
+    it "indexes lists and dicts without converting the whole result" do
+      obj = object_for("{'rows': [10, 20, 30]}")
+      expect(obj["rows"][-1].to_ruby).to eq(30)
+    end
+
+    it "raises Monty::Error for missing keys" do
+      expect { object_for("{'a': 1}")["b"] }.to raise_error(Monty::Error, /KeyError/)
+    end
+  end
+end
@@ -0,0 +1,41 @@
+# frozen_string_literal: true
+
+RSpec.describe Monty::OutputReader do
+  let(:complete) { Monty::Run.new("print('first')\nprint('second é')\nprint('third', end='')").start }
+
+  it "reads the same text as Complete#output" do
+    expect(complete.output_reader.read).to eq(complete.output)
+    expect(complete.output_reader.read.encoding).to eq(Encoding::UTF_8)
+  end
+
+  it "yields lines with their newlines" do
+    expect(complete.output_reader.each_line.to_a).to eq(["first\n", "second é\n", "third"])
+  end
+
+  it "reads in binary chunks like IO#read" do
+    reader = complete.output_reader
+    chunks = []
+    while (chunk = reader.read(4))
+      chunks << chunk
+    end
+
+    expect(chunks.first).to eq("firs".b)
+    expect(chunks.first.encoding).to eq(Encoding::BINARY)
+    expect(chunks.join.force_encoding(Encoding::UTF_8)).to eq(complete.output)
+    expect(reader).to be_eof
+    expect(reader.read).to eq("")
+  end
+
+  it "tracks and resets its position" do
+    reader = complete.output_reader
+    expect(reader.size).to eq(complete.output.bytesize)
+    expect(reader.gets).to eq("first\n")
+    expect(reader.pos).to eq(6)
+    reader.rewind
+    expect(reader.gets).to eq("first\n")
+  end
+
+  it "rejects negative lengths" do
+    expect { complete.output_reader.read(-1) }.to raise_error(ArgumentError)
+  end
+end
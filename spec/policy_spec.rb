@@ -0,0 +1,80 @@
+# frozen_string_literal: true
+
+RSpec.describe Monty::Policy do
+  let(:code) do
+    <<~PYTHON
+      import os
+
+      def grade(score):
+          if score > 90 and score < 100:
+              return "A"
+          elif score > 80 or score == 0:
+              return "B"
+          return "C"
+
+      print(grade(int(os.getenv("SCORE"))))
+    PYTHON
+  end
+
+  it "allows everything by default" do
+    expect(described_class.new.violations(code)).to eq([])
+  end
+
+  it "denies imports" do
+    policy = described_class.new(deny_imports: ["os", "json"])
+    expect(policy.violations(code)).to eq([
+      {rule: :deny_imports, message: "import of 'os' is denied", line: 1}
+    ])
+  end
+
+  it "caps complexity per function" do
+    policy = described_class.new(max_complexity: 4)
+    expect(policy.violations(code)).to eq([
+      {rule: :max_complexity, message: "grade has complexity 5 (max 4)", line: 3}
+    ])
+  end
+
+  it "forbids os calls" do
+    policy = described_class.new(forbid_os_calls: true)
+    expect(policy.violations(code)).to eq([
+      {rule: :forbid_os_calls, message: "os.getenv is forbidden", line: nil}
+    ])
+  end
+
+  it "forbids os calls made through an alias" do
+    policy = described_class.new(forbid_os_calls: true)
+    expect(policy.violations("import os as o\no.getenv('HOME')")).to eq([
+      {rule: :forbid_os_calls, message: "os.getenv is forbidden", line: nil}
+    ])
+  end
+
+  it "requires type hints" do
+    policy = described_class.new(require_type_hints: true)
+    expect(policy.violations(code).map { |v| v[:message] }).to eq([
+      'Function "grade" is missing a type annotation for argument "score"',
+      'Function "grade" is missing a return type annotation'
+    ])
+    expect(policy.violations("def grade(score: int) -> str:\n    return 'A'")).to eq([])
+  end
+
+  it "is frozen" do
+    expect(described_class.new(deny_imports: ["os"])).to be_frozen
+  end
+
+  describe "with Run.new(policy:)" do
+    it "raises PolicyError listing every violation" do
+      policy = described_class.new(deny_imports: ["os"], forbid_os_calls: true)
+      expect { Monty::Run.new(code, policy: policy) }.to raise_error(Monty::PolicyError) { |error|
+        expect(error.violations.map { |v| v[:rule] }).to eq(%i[deny_imports forbid_os_calls])
+        expect(error.message).to eq("script violates policy: line 1: import of 'os' is denied; os.getenv is forbidden")
+        expect(error).to be_a(Monty::Error)
+      }
+    end
+
+    it "compiles scripts that satisfy the policy" do
+      policy = described_class.new(deny_imports: ["os"], max_complexity: 3)
+      run = Monty::Run.new("x + 1", inputs: ["x"], policy: policy)
+      expect(run.call(1)).to eq(2)
+    end
+  end
+end
@@ -0,0 +1,42 @@
+# frozen_string_literal: true
+
+RSpec.describe "Monty.redactor" do
+  around do |example|
+    Monty.redactor = ->(text) { text.gsub(/sk_\w+/, "[REDACTED]") }
+    example.run
+  ensure
+    Monty.redactor = nil
+  end
+
+  it "masks secrets in error messages and tracebacks" do
+    run = Monty::Run.new("raise ValueError('bad key ' + key)", inputs: ["key"])
+
+    expect { run.call("sk_live_123") }.to raise_error(Monty::Error) { |error|
+      expect(error.message).to include("[REDACTED]")
+      expect(error.message).not_to include("sk_live_123")
+      expect(error.inspect).not_to include("sk_live_123")
+      expect(error.full_message).not_to include("sk_live_123")
+      expect(error.formatted_traceback).not_to include("sk_live_123")
+    }
+  end
+
+  it "masks secrets in audit log entries" do
+    audit = Monty::AuditLog.new
+    Monty::Run.new("len(key)", inputs: ["key"]).call("sk_live_123", audit: audit)
+
+    expect(audit.to_a.first[:value]).to eq('"[REDACTED]"')
+  end
+
+  it "redacts call arguments before cutting them to max_repr_length" do
+    previous = Monty.max_repr_length
+    Monty.max_repr_length = 4
+    run = Monty::Run.new("fetch(key)", inputs: ["key"], external_functions: ["fetch"])
+    call = run.start("sk_live_0123456789")
+
+    expect(call.args_repr).to eq("'[RE...")
+    expect(call.inspect).not_to include("sk_")
+    expect(call.resume(1).segments.first[:args]).to eq("'[RE...")
+  ensure
+    Monty.max_repr_length = previous
+  end
+end
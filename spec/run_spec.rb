@@ -17,18 +17,82 @@ RSpec.describe Monty::Run do
       expect { Monty::Run.new("def !!!") }.to raise_error(Monty::SyntaxError)
     end
 
+    it "rejects imports of disabled modules" do
+      expect { Monty::Run.new("import json\njson.dumps(1)", disabled_modules: ["json"]) }
+        .to raise_error(Monty::Error, /module 'json' is disabled/)
+      expect(Monty::Run.new("import json\njson.dumps(1)", disabled_modules: ["asyncio"])).to be_a(Monty::Run)
+    end
+
+    it "finds disabled imports after a semicolon or a compound statement header" do
+      ["x = 1; import json", "if True: import json", "for i in range(1): from json import dumps"].each do |code|
+        expect { Monty::Run.new(code, disabled_modules: ["json"]) }
+          .to raise_error(Monty::Error, /module 'json' is disabled/)
+      end
+    end
+
+    it "warns on or forbids float literals" do
+      code = "total = price * 1.1\nround(total)"
+      expect { Monty::Run.new(code, inputs: ["price"], float_literals: :warn) }
+        .to output(/script.py:1: binary float 1.1 used/).to_stderr
+      expect { Monty::Run.new(code, inputs: ["price"], float_literals: :forbid) }
+        .to raise_error(Monty::PolicyError, /line 1: binary float 1.1 used/) { |error|
+          expect(error.violations).to eq([{rule: :float_literals, message: "binary float 1.1 used", line: 1}])
+        }
+      expect(Monty::Run.new("price * 110 // 100", inputs: ["price"], float_literals: :forbid)).to be_a(Monty::Run)
+      expect { Monty::Run.new("price / 3", inputs: ["price"], float_literals: :forbid) }
+        .to raise_error(Monty::PolicyError, %r{binary float '/' division})
+    end
+
     it "uses default script_name" do
       run = Monty::Run.new("42")
       expect(run).to be_a(Monty::Run)
     end
   end
 
+  describe "#defined_names" do
+    it "lists module-level functions, classes and variables" do
+      code = <<~PYTHON
+        LIMIT = 10
+
+        def solve(x):
+            total = 0
+            return x
+
+        class Point:
+            pass
+      PYTHON
+
+      names = Monty::Run.new(code).defined_names
+      expect(names.map { |n| [n[:name], n[:kind]] }).to eq([
+        ["LIMIT", :variable], ["solve", :function], ["Point", :class]
+      ])
+      expect(names[1][:line]).to eq(3)
+    end
+
+    it "does not treat keyword arguments in calls as bindings" do
+      names = Monty::Run.new("LIMIT = 1\nprint(LIMIT, sep=\"\")").defined_names
+      expect(names.map { |n| n[:name] }).to eq(["LIMIT"])
+    end
+  end
+
   describe "#call" do
     it "evaluates a simple expression" do
       run = Monty::Run.new("x + y", inputs: ["x", "y"])
       expect(run.call(1, 2)).to eq(3)
     end
 
+    it "accepts inputs by name" do
+      run = Monty::Run.new("x - y", inputs: ["x", "y"])
+      expect(run.call(inputs: {y: 1, "x" => 5})).to eq(4)
+    end
+
+    it "lists missing and unexpected named inputs" do
+      run = Monty::Run.new("x - y", inputs: ["x", "y"])
+      expect { run.call(inputs: {x: 1, z: 2}) }
+        .to raise_error(ArgumentError, "missing inputs: y; unexpected inputs: z")
+      expect { run.call(1, inputs: {y: 2}) }.to raise_error(ArgumentError, /not both/)
+    end
+
     it "returns None as nil" do
       run = Monty::Run.new("None")
       expect(run.call).to be_nil
@@ -134,6 +198,62 @@ RSpec.describe Monty::Run do
       expect(result[:output]).to include("hello")
       expect(result[:output]).to include("world")
     end
+
+    it "streams printed lines to on_output as they are written" do
+      code = <<~PYTHON
+        print('hello', 'there')
+        print('partial', end='')
+        42
+      PYTHON
+
+      lines = []
+      result = Monty::Run.new(code).call(on_output: ->(line) { lines << line })
+
+      expect(result).to eq(42)
+      expect(lines).to eq(["hello there\n", "partial"])
+    end
+
+    it "raises the on_output callback's exception" do
+      run = Monty::Run.new("print('a')\nprint('b')\n1")
+      expect { run.call(on_output: ->(_line) { raise IOError, "socket closed" }) }
+        .to raise_error(IOError, "socket closed")
+    end
+
+    it "rejects bad on_output limits before the script prints anything" do
+      lines = []
+      run = Monty::Run.new("print('side effect')\n1")
+      expect { run.call(limits: {max_result_bytes: "lots"}, on_output: ->(line) { lines << line }) }
+        .to raise_error(ArgumentError, /max_result_bytes/)
+      expect(lines).to eq([])
+    end
+
+    it "stops an on_output run at the next line after Monty.abort_all!" do
+      run = Monty::Run.new("while True:\n    print('tick')")
+      lines = []
+      on_output = ->(line) {
+        lines << line
+        Monty.abort_all! if lines.size == 3
+      }
+
+      expect { run.call(limits: {max_duration: 10}, on_output: on_output) }
+        .to raise_error(Monty::Interrupted, /abort_all!/)
+      expect(lines.size).to eq(3)
+    end
+
+    it "rejects on_output together with capture_output" do
+      run = Monty::Run.new("1")
+      expect { run.call(capture_output: true, on_output: ->(_line) {}) }.to raise_error(ArgumentError)
+    end
+
+    it "never mutates the Ruby inputs" do
+      items = [1, 2]
+      config = {"flags" => ["a"]}
+      run = Monty::Run.new("items.append(3)\nconfig['flags'].clear()\nlen(items)", inputs: ["items", "config"])
+
+      expect(run.call(items, config)).to eq(3)
+      expect(items).to eq([1, 2])
+      expect(config).to eq({"flags" => ["a"]})
+    end
   end
 
   describe "#call with limits" do
@@ -142,6 +262,493 @@ RSpec.describe Monty::Run do
       result = run.call(1, limits: {max_duration: 5.0})
       expect(result).to eq(2)
     end
+
+    it "accepts human-friendly memory and duration values" do
+      run = Monty::Run.new("x + 1", inputs: ["x"])
+      expect(run.call(1, limits: {max_memory: "512MB", max_duration: "2.5s"})).to eq(2)
+      expect { run.call(1, limits: {max_memory: "lots"}) }.to raise_error(ArgumentError, /max_memory/)
+    end
+
+    it "rejects negative, NaN and infinite durations" do
+      run = Monty::Run.new("x + 1", inputs: ["x"])
+      nan_duration = Object.new
+      def nan_duration.to_f = Float::NAN
+
+      expect { run.call(1, limits: {max_duration: -1}) }.to raise_error(ArgumentError, /max_duration/)
+      expect { run.call(1, limits: {max_duration: nan_duration}) }.to raise_error(ArgumentError, /max_duration/)
+      expect { run.call(1, limits: {max_duration: "9" * 400 + "h"}) }.to raise_error(ArgumentError, /max_duration/)
+    end
+
+    it "raises before converting results over max_result_items" do
+      run = Monty::Run.new("list(range(n))", inputs: ["n"])
+      expect(run.call(10, limits: {max_result_items: 100})).to eq((0...10).to_a)
+      expect { run.call(1_000, limits: {max_result_items: 100}) }
+        .to raise_error(Monty::ResourceError, /result too large/)
+    end
+
+    it "raises before converting results over max_result_bytes" do
+      run = Monty::Run.new("'x' * n", inputs: ["n"])
+      expect { run.call(10_000, limits: {max_result_bytes: "1KB"}) }
+        .to raise_error(Monty::ResourceError, /more than 1000 bytes/)
+    end
+
+    it "raises OverflowError for ints beyond 64 bits with result_integer_overflow: :raise" do
+      run = Monty::Run.new("2 ** n", inputs: ["n"])
+      expect(run.call(70)).to eq(2**70)
+      expect(run.call(62, limits: {result_integer_overflow: :raise})).to eq(2**62)
+      expect { run.call(70, limits: {result_integer_overflow: :raise}) }
+        .to raise_error(Monty::OverflowError, /int result exceeds 64 bits/)
+    end
+
+    it "checks only the final result under result_integer_overflow: :raise" do
+      run = Monty::Run.new("len(str(x * 2 ** 70))", inputs: ["x"])
+      expect(run.call(2**70, limits: {result_integer_overflow: :raise})).to eq(43)
+    end
+
+    it "raises TimeLimitExceeded when max_duration is exceeded" do
+      run = Monty::Run.new("x = 0\nwhile True:\n    x += 1")
+      expect { run.call(limits: {max_duration: 0.05}) }.to raise_error(Monty::TimeLimitExceeded) { |error|
+        expect(error).to be_a(Monty::ResourceError)
+        expect(error.python_class).to eq("TimeoutError")
+      }
+    end
+
+    it "raises RecursionLimitExceeded when max_recursion_depth is exceeded" do
+      run = Monty::Run.new("def f(n):\n    return f(n + 1)\nf(0)")
+      expect { run.call(limits: {max_recursion_depth: 50}) }
+        .to raise_error(Monty::RecursionLimitExceeded)
+      expect(Monty::RecursionLimitExceeded).to be < Monty::ResourceError
+    end
+
+    it "keeps Python classes for TimeoutError and RecursionError raised by the script" do
+      run = Monty::Run.new("raise TimeoutError('upstream slow')")
+      expect { run.call(limits: {max_duration: 5}) }.to raise_error(Monty::TimeoutError) { |error|
+        expect(error).not_to be_a(Monty::ResourceError)
+      }
+
+      run = Monty::Run.new("raise RecursionError('too deep')")
+      expect { run.call }.to raise_error(Monty::RecursionError) { |error|
+        expect(error).to be_a(Monty::RuntimeError)
+        expect(error).not_to be_a(Monty::ResourceError)
+      }
+    end
+
+    it "keeps Python classes for script errors whose message mentions a limit" do
+      run = Monty::Run.new("raise TimeoutError('rate limit exceeded')")
+      expect { run.call }.to raise_error(Monty::TimeoutError) { |error|
+        expect(error).not_to be_a(Monty::ResourceError)
+      }
+
+      run = Monty::Run.new("raise MemoryError('cache size limit exceeded')")
+      expect { run.call }.to raise_error(Monty::Error) { |error|
+        expect(error).not_to be_a(Monty::ResourceError)
+      }
+    end
+
+    it "lets other threads run while the script executes" do
+      run = Monty::Run.new("x = 0\nfor i in range(n):\n    x += i\nx", inputs: ["n"])
+      ticks = 0
+      ticker = Thread.new { loop { ticks += 1; sleep 0.001 } }
+      run.call(2_000_000, limits: {max_duration: 10})
+      ticker.kill
+
+      expect(ticks).to be > 1
+    end
+
+    it "runs one Run from several threads at once" do
+      run = Monty::Run.new("x = 0\nfor i in range(n):\n    x += i\nx", inputs: ["n"])
+      threads = 4.times.map { |n| Thread.new { run.call(10_000 + n) } }
+
+      expect(threads.map(&:value)).to eq(4.times.map { |n| (10_000 + n) * (9_999 + n) / 2 })
+    end
+
+    it "refuses to consume a Run another thread is executing" do
+      run = Monty::Run.new("x = 0\nfor i in range(n):\n    x += i\nx", inputs: ["n"])
+      worker = Thread.new { run.call(2_000_000, limits: {max_duration: 10}) }
+      sleep 0.01
+
+      expect { run.start(1) }.to raise_error(Monty::Error, /executing on another thread/)
+      worker.join
+    end
+
+    it "stops a script that never returns on Thread#raise" do
+      run = Monty::Run.new("while True:\n    pass")
+      [{}, {capture_output: true}, {limits: {max_duration: 60}}].each do |options|
+        started = Process.clock_gettime(Process::CLOCK_MONOTONIC)
+        worker = Thread.new { run.call(**options) }
+        Thread.pass until Monty.active_runs.any?
+        worker.raise(Interrupt)
+
+        expect { worker.value }.to raise_error(Interrupt)
+        expect(Process.clock_gettime(Process::CLOCK_MONOTONIC) - started).to be < 2
+      end
+    end
+
+    it "rejects unknown limit keys" do
+      run = Monty::Run.new("x + 1", inputs: ["x"])
+      expect { run.call(1, limits: {max_duration_seconds: 5.0}) }
+        .to raise_error(ArgumentError, /max_duration_seconds/)
+    end
+
+    it "leaves the Run usable when start rejects its limits or inputs" do
+      run = Monty::Run.new("fetch(x)", inputs: ["x"], external_functions: ["fetch"])
+      expect { run.start(1, limits: {max_duration_seconds: 5.0}) }.to raise_error(ArgumentError)
+      expect { run.start(Object.new) }.to raise_error(TypeError)
+
+      expect(run.start(1).resume(2).value).to eq(2)
+    end
+
+    it "ignores unknown limit keys with strict: false" do
+      run = Monty::Run.new("x + 1", inputs: ["x"])
+      expect(run.call(1, limits: {max_duration_seconds: 5.0, strict: false})).to eq(2)
+    end
+  end
+
+  describe "#warnings" do
+    it "reports unused declarations without failing compilation" do
+      run = Monty::Run.new("x * 2", inputs: ["x", "rate"])
+
+      expect(run.call(3)).to eq(6)
+      expect(run.warnings.map { |w| w[:message] }).to eq(["input 'rate' is never used"])
+    end
+
+    it "is empty when every declaration is referenced" do
+      expect(Monty::Run.new("x * 2", inputs: ["x"]).warnings).to eq([])
+    end
+  end
+
+  describe "#compile_stats" do
+    it "reports analysis and compile durations in seconds" do
+      stats = Monty::Run.new("x * 2", inputs: ["x"]).compile_stats
+
+      expect(stats.keys).to eq([:analysis_duration, :compile_duration])
+      expect(stats.values).to all(be_a(Float).and(be >= 0))
+    end
+
+    it "is nil for loaded Runs" do
+      run = Monty::Run.load(Monty::Run.new("1").dump)
+      expect(run.compile_stats).to be_nil
+    end
+  end
+
+  describe "#call_graph" do
+    it "maps each function to the functions and externals it calls" do
+      code = <<~PYTHON
+        def price(item):
+            return fetch_price(item) * tax(item)
+
+        def tax(item):
+            return 1
+
+        result = price(order)
+      PYTHON
+
+      graph = Monty::Run.new(code, inputs: ["order"], external_functions: ["fetch_price"]).call_graph
+      expect(graph).to eq(
+        "<module>" => {functions: ["price"], externals: []},
+        "price" => {functions: ["tax"], externals: ["fetch_price"]},
+        "tax" => {functions: [], externals: []}
+      )
+    end
+
+    it "qualifies methods and nested functions by their enclosing scope" do
+      code = <<~PYTHON
+        class Box:
+            def area(self):
+                def helper():
+                    return 1
+                return helper()
+      PYTHON
+
+      graph = Monty::Run.new(code).call_graph
+      expect(graph.keys).to eq(["<module>", "Box.area", "Box.area.helper"])
+    end
+  end
+
+  describe "#== and #hash" do
+    it "treats Runs compiled from the same program as equal" do
+      a = Monty::Run.new("x + 1", inputs: ["x"])
+      b = Monty::Run.new("x + 1", inputs: ["x"])
+      expect(a).to eq(b)
+      expect(a).to eql(b)
+      expect(a.hash).to eq(b.hash)
+      expect(Set[a, b].size).to eq(1)
+      expect({a => :cached}[b]).to eq(:cached)
+    end
+
+    it "distinguishes different programs" do
+      run = Monty::Run.new("x + 1", inputs: ["x"])
+      expect(run).not_to eq(Monty::Run.new("x + 2", inputs: ["x"]))
+      expect(run).not_to eq(Monty::Run.new("x + 1", inputs: ["y"]))
+      expect(run).not_to eq(Monty::Run.new("x + 1", inputs: ["x"], intrinsics: true))
+      expect(run).not_to eq("x + 1")
+    end
+
+    it "keeps comparing after the Run is consumed" do
+      a = Monty::Run.new("1")
+      b = Monty::Run.new("1")
+      hash = a.hash
+      a.start
+      expect(a).to eq(b)
+      expect(a.hash).to eq(hash)
+    end
+
+    it "compares a Run consumed before it was ever compared by identity" do
+      a = Monty::Run.new("1")
+      b = Monty::Run.new("1")
+      a.start
+      b.start
+
+      expect(a).to eq(a)
+      expect(a.hash).to eq(a.hash)
+      expect(a).not_to eq(b)
+      expect(a).not_to eq(Monty::Run.new("1"))
+    end
+  end
+
+  describe "#capabilities" do
+    it "summarizes what the script could do" do
+      code = <<~PYTHON
+        import os
+        from pathlib import Path
+
+        def walk(n):
+            return [] if n == 0 else [fetch(n)] + walk(n - 1)
+
+        token = os.getenv("TOKEN")
+        text = Path("notes.txt").read_text()
+        print(len(walk(3)))
+      PYTHON
+
+      run = Monty::Run.new(code, external_functions: ["fetch", "store"])
+      expect(run.capabilities).to eq(
+        modules: ["os", "pathlib"],
+        builtins: ["print", "len"],
+        os_calls: ["os.getenv", "Path.read_text"],
+        external_functions: ["fetch"],
+        loops: false,
+        recursion: true
+      )
+    end
+
+    it "reports os calls made through import aliases" do
+      code = <<~PYTHON
+        import os as o
+        from os import getenv as ge, path
+        if o: home = o.environ.get("HOME"); ge("TOKEN")
+        path.exists("/tmp")
+      PYTHON
+
+      expect(Monty::Run.new(code).capabilities[:os_calls])
+        .to eq(["os.environ.get", "os.getenv", "os.path.exists"])
+    end
+
+    it "reports loops, including comprehensions" do
+      caps = Monty::Run.new("sum(x * x for x in range(10))").capabilities
+      expect(caps).to include(loops: true, recursion: false, builtins: ["sum", "range"])
+    end
+  end
+
+  describe "#typecheck" do
+    it "reports literals that contradict type hints" do
+      code = <<~PYTHON
+        def scale(x: float, label: str = 0) -> str:
+            return 1
+
+        limit: int = "10"
+        scale("2", label="x")
+        total = "count: " + 3
+        scale(2.0)
+      PYTHON
+
+      issues = Monty::Run.new(code).typecheck
+      expect(issues.map { |i| [i[:line], i[:message]] }).to eq([
+        [1, 'Incompatible default for argument "label" (default has type "int", argument has type "str")'],
+        [2, 'Incompatible return value type (got "int", expected "str")'],
+        [4, 'Incompatible types in assignment (expression has type "str", variable has type "int")'],
+        [5, 'Argument 1 to "scale" has incompatible type "str"; expected "float"'],
+        [6, 'Unsupported operand types for + ("str" and "int")']
+      ])
+      expect(issues.map { |i| i[:severity] }.uniq).to eq([:warning])
+    end
+
+    it "accepts compatible and non-literal values" do
+      code = <<~PYTHON
+        from typing import Optional
+
+        def f(x: float, y: Optional[int] = None) -> list[int]:
+            return [x]
+
+        z: float = 1
+        f(1, y=None)
+        f(g())
+        s = "%d" % 5 + "x"
+      PYTHON
+
+      expect(Monty::Run.new(code).typecheck).to eq([])
+    end
+  end
+
+  describe "#fuzz" do
+    it "reports generated inputs that make the script raise" do
+      run = Monty::Run.new("100 // (qty - 3)", inputs: ["qty"])
+      failures = run.fuzz(generators: [0..10], iterations: 200, seed: 42)
+
+      expect(failures).not_to be_empty
+      expect(failures.map { |f| f[:inputs] }.uniq).to eq([[3]])
+      expect(failures.first[:error]).to be_a(Monty::Error)
+      expect(failures.first[:error].message).to include("ZeroDivisionError")
+    end
+
+    it "generates lists and dicts from nested specs" do
+      code = "assert isinstance(order['qty'], int) and all(isinstance(t, str) for t in tags)"
+      run = Monty::Run.new(code, inputs: ["order", "tags"])
+
+      expect(run.fuzz(generators: [{"qty" => :int}, [:str]], iterations: 50, seed: 7)).to eq([])
+    end
+
+    it "is reproducible for a given seed" do
+      run = Monty::Run.new("x / y", inputs: ["x", "y"])
+      first = run.fuzz(generators: [:float, :int], iterations: 50, seed: 3)
+      second = run.fuzz(generators: [:float, :int], iterations: 50, seed: 3)
+
+      summary = ->(failures) { failures.map { |f| [f[:iteration], f[:inputs].inspect] } }
+      expect(summary.call(first)).to eq(summary.call(second))
+    end
+
+    it "reports inputs that make the script hang under the default time limit" do
+      run = Monty::Run.new("while n > 0:\n    pass", inputs: ["n"])
+      failures = run.fuzz(generators: [1..1], iterations: 2, seed: 1)
+
+      expect(failures.map { |f| f[:iteration] }).to eq([0, 1])
+      expect(failures.map { |f| f[:error] }).to all(be_a(Monty::TimeLimitExceeded))
+    end
+
+    it "lets other threads run and stops on Monty.abort_all!" do
+      run = Monty::Run.new("x = 0\nfor i in range(n):\n    x += i\nx", inputs: ["n"])
+      ticks = 0
+      ticker = Thread.new { loop { ticks += 1; sleep 0.001 } }
+      worker = Thread.new { run.fuzz(generators: [200_000..200_000], iterations: 10_000, seed: 1) }
+      sleep 0.05
+      Monty.abort_all!
+
+      expect { worker.value }.to raise_error(Monty::Interrupted, /abort_all!/)
+      ticker.kill
+      expect(ticks).to be > 1
+    end
+
+    it "rejects unknown generators" do
+      run = Monty::Run.new("x", inputs: ["x"])
+      expect { run.fuzz(generators: [:date]) }.to raise_error(ArgumentError, /unknown fuzz generator :date/)
+    end
+  end
+
+  describe "#call_incremental" do
+    it "reconverts only inputs that changed" do
+      run = Monty::Run.new("sum(table) + tick", inputs: ["table", "tick"])
+      table = (1..100).to_a
+
+      expect(run.call_incremental(table, 1)).to eq(5051)
+      allow(Monty::SharedData).to receive(:new).and_call_original
+      expect(run.call_incremental(table, 2)).to eq(5052)
+      expect(Monty::SharedData).to have_received(:new).once
+
+      table << 1
+      expect(run.call_incremental(table, 2)).to eq(5053)
+    end
+
+    it "reconverts an input whose hash collides with the previous one" do
+      colliding = Struct.new(:value) do
+        def hash = 0
+
+        def to_monty = value
+      end
+      run = Monty::Run.new("x", inputs: ["x"])
+
+      expect(run.call_incremental(colliding.new(1))).to eq(1)
+      expect(run.call_incremental(colliding.new(2))).to eq(2)
+    end
+
+    it "gives each thread its own inputs when the Run is shared" do
+      run = Monty::Run.new("x * 2", inputs: ["x"])
+      threads = 4.times.map do |t|
+        Thread.new { 50.times.map { |i| run.call_incremental(t * 100 + i) } }
+      end
+
+      expect(threads.map(&:value)).to eq(4.times.map { |t| 50.times.map { |i| (t * 100 + i) * 2 } })
+    end
+  end
+
+  describe "#run_with_csv" do
+    let(:csv) { "name,score,active\nada,3,true\n\"lee, b\",4.5,false\n" }
+
+    it "passes rows as a list of dicts of strings" do
+      run = Monty::Run.new("rows", inputs: ["rows"])
+      expect(run.run_with_csv(csv)).to eq([
+        {"name" => "ada", "score" => "3", "active" => "true"},
+        {"name" => "lee, b", "score" => "4.5", "active" => "false"}
+      ])
+    end
+
+    it "infers or applies column types" do
+      run = Monty::Run.new("[(r['score'], r['active']) for r in rows]", inputs: ["rows"])
+      expect(run.run_with_csv(StringIO.new(csv), types: :infer)).to eq([[3.0, true], [4.5, false]])
+      expect { run.run_with_csv(csv, types: {score: :int}) }.to raise_error(Monty::Error, /column 'score'/)
+    end
+
+    it "accepts a custom separator and further inputs" do
+      run = Monty::Run.new("[r['a'] * n for r in rows]", inputs: ["rows", "n"])
+      expect(run.run_with_csv("a;b\n2;x\n", 3, col_sep: ";", types: {a: :int})).to eq([6])
+    end
+
+    it "raises on an unterminated quoted field" do
+      run = Monty::Run.new("rows", inputs: ["rows"])
+      expect { run.run_with_csv("name,note\nada,\"fine\nlee,ok\n") }
+        .to raise_error(Monty::Error, /CSV: unterminated quoted field starting on line 2/)
+    end
+  end
+
+  describe "#map" do
+    it "returns results and errors per input set in order" do
+      run = Monty::Run.new("10 // x", inputs: ["x"])
+      results = run.map([[1], [0], [5]], concurrency: 2)
+
+      expect(results[0]).to eq(10)
+      expect(results[1]).to be_a(Monty::Error)
+      expect(results[1].message).to include("ZeroDivisionError")
+      expect(results[2]).to eq(2)
+    end
+
+    it "applies limits to each execution" do
+      run = Monty::Run.new("while x:\n    pass\nx", inputs: ["x"])
+      results = run.map([[0], [1]], limits: {max_duration: 0.1})
+
+      expect(results[0]).to eq(0)
+      expect(results[1]).to be_a(Monty::ResourceError)
+    end
+
+    it "yields each execution as it finishes when given a block" do
+      run = Monty::Run.new("x * 2", inputs: ["x"])
+      seen = {}
+
+      returned = run.map([[1], [2], [3]]) do |index, result, stats|
+        expect(stats[:duration]).to be >= 0
+        seen[index] = result
+      end
+
+      expect(returned).to be_nil
+      expect(seen).to eq({0 => 2, 1 => 4, 2 => 6})
+    end
+
+    it "stops starting executions when the waiting thread is interrupted" do
+      run = Monty::Run.new("while True:\n    pass")
+      started = Process.clock_gettime(Process::CLOCK_MONOTONIC)
+      worker = Thread.new { run.map(Array.new(20) { [] }, limits: {max_duration: 0.2}, concurrency: 1) }
+      worker.raise(Interrupt)
+
+      expect { worker.value }.to raise_error(Interrupt)
+      expect(Process.clock_gettime(Process::CLOCK_MONOTONIC) - started).to be < 2
+    end
   end
 
   describe "#start" do
@@ -171,6 +778,78 @@ RSpec.describe Monty::Run do
       expect(progress).to be_a(Monty::FunctionCall)
     end
 
+    it "records a Chrome trace timeline when trace: true" do
+      run = Monty::Run.new("fetch(1) + 1", external_functions: ["fetch"])
+      progress = run.start(trace: true)
+      complete = progress.resume(41)
+      complete.value
+
+      names = complete.trace_events.map { |event| event[:name] }
+      expect(names).to eq(["convert inputs", "segment 1", "fetch", "convert result", "segment 2", "convert value"])
+      expect(complete.trace_events).to all(include(ph: "X", pid: 1, tid: 1))
+      expect(JSON.parse(complete.chrome_trace)["traceEvents"].size).to eq(6)
+    end
+
+    it "rejects tool results over max_tool_result_bytes" do
+      run = Monty::Run.new("len(fetch())", external_functions: ["fetch"])
+      progress = run.start(limits: {max_tool_result_bytes: 100})
+
+      expect { progress.resume("x" * 1000) }.to raise_error(Monty::ResourceError, /tool result too large/)
+    end
+
+    it "can be resumed again after a tool result is rejected" do
+      run = Monty::Run.new("len(fetch())", external_functions: ["fetch"])
+      progress = run.start(limits: {max_tool_result_bytes: 100})
+
+      expect { progress.resume("x" * 1000) }.to raise_error(Monty::ResourceError)
+      expect(progress.resume("x" * 10).value).to eq(10)
+    end
+
+    it "shrinks oversized tool results with the summarizer" do
+      Monty.tool_result_summarizer = ->(value, max_bytes) { value.byteslice(0, max_bytes) }
+      run = Monty::Run.new("len(fetch())", external_functions: ["fetch"])
+      progress = run.start(limits: {max_tool_result_bytes: 100})
+
+      expect(progress.resume("x" * 1000).value).to eq(100)
+    ensure
+      Monty.tool_result_summarizer = nil
+    end
+
+    it "truncates argument reprs in inspect but keeps full args" do
+      previous = Monty.max_repr_length
+      Monty.max_repr_length = 12
+      run = Monty::Run.new("fetch(data)", inputs: ["data"], external_functions: ["fetch"])
+      call = run.start("y" * 1000)
+
+      expect(call.args_repr).to eq("'yyyyyyyyyyy...")
+      expect(call.inspect).to eq("#<Monty::FunctionCall fetch('yyyyyyyyyyy...)>")
+      expect(call.args).to eq(["y" * 1000])
+    ensure
+      Monty.max_repr_length = previous
+    end
+
+    it "logs every segment on the Complete" do
+      code = <<~PYTHON
+        print("start")
+        a = fetch("x", retries=2)
+        print("got", a)
+        a + 1
+      PYTHON
+      run = Monty::Run.new(code, external_functions: ["fetch"])
+      complete = run.start.resume(41)
+
+      segments = complete.segments
+      expect(segments.map { |s| s.slice(:function, :args, :result, :output) }).to eq([
+        {function: "fetch", args: "'x', retries=2", result: "41", output: "start\n"},
+        {function: nil, args: nil, result: nil, output: "got 41\n"}
+      ])
+      expect(segments).to all(include(:duration))
+    end
+
+    it "has no timeline unless tracing" do
+      expect(Monty::Run.new("1").start.trace_events).to be_nil
+    end
+
     it "resumes execution with provided result" do
       code = <<~PYTHON
         result = fetch("https://example.com")
@@ -208,6 +887,100 @@ RSpec.describe Monty::Run do
       expect(progress).to be_a(Monty::Complete)
       expect(progress.value).to eq("first second")
     end
+
+    context "with deferred calls" do
+      let(:code) do
+        <<~PYTHON
+          import asyncio
+          a, b = await asyncio.gather(fetch(1), fetch(2))
+          a + b
+        PYTHON
+      end
+
+      it "resolves deferred calls through PendingFutures" do
+        progress = Monty::Run.new(code, external_functions: ["fetch"]).start
+        ids = []
+        while progress.is_a?(Monty::FunctionCall)
+          ids << progress.call_id
+          progress = progress.defer
+        end
+
+        expect(progress).to be_a(Monty::PendingFutures)
+        expect(progress.pending_call_ids).to match_array(ids)
+        expect(progress.resume(ids.map { |id| [id, id * 10] }).value).to eq(ids.sum * 10)
+      end
+
+      it "can resume pending futures again after a result is rejected" do
+        progress = Monty::Run.new(code, external_functions: ["fetch"]).start(limits: {max_tool_result_bytes: 100})
+        ids = []
+        while progress.is_a?(Monty::FunctionCall)
+          ids << progress.call_id
+          progress = progress.defer
+        end
+
+        expect { progress.resume(ids.map { |id| [id, "x" * 1000] }) }.to raise_error(Monty::ResourceError)
+        expect(progress.resume(ids.map { |id| [id, id * 10] }).value).to eq(ids.sum * 10)
+      end
+
+      it "raises when more calls are pending than max_pending_futures" do
+        progress = Monty::Run.new(code, external_functions: ["fetch"]).start(limits: {max_pending_futures: 1})
+
+        expect {
+          progress = progress.defer while progress.is_a?(Monty::FunctionCall)
+        }.to raise_error(Monty::ResourceError, /pending futures limit exceeded: 2 pending \(limit: 1\)/)
+      end
+    end
+  end
+
+  describe "progress #stats" do
+    it "accumulates timing across segments" do
+      run = Monty::Run.new("fetch('a')", external_functions: ["fetch"])
+      progress = run.start
+      expect(progress.stats[:segments]).to eq(1)
+
+      complete = progress.resume("done")
+      expect(complete.stats[:segments]).to eq(2)
+      expect(complete.stats[:duration]).to be >= complete.stats[:last_segment_duration]
+    end
+
+    it "separates interpreter time from time spent suspended" do
+      run = Monty::Run.new("fetch('a')", external_functions: ["fetch"])
+      progress = run.start
+      sleep 0.05
+      stats = progress.resume("done").stats
+
+      expect(stats[:suspended_duration]).to be >= 0.05
+      expect(stats[:duration]).to be < stats[:suspended_duration]
+    end
+  end
+
+  describe "Complete#to_columns" do
+    it "converts a list of dicts into column arrays" do
+      run = Monty::Run.new("[{'a': 1, 'b': 'x'}, {'a': 2}, {'b': 'z', 'c': True}]")
+      columns = run.start.to_columns
+
+      expect(columns).to eq(
+        "a" => [1, 2, nil],
+        "b" => ["x", nil, "z"],
+        "c" => [nil, nil, true]
+      )
+      expect(columns.keys).to all(be_frozen)
+    end
+
+    it "raises for results that are not tables" do
+      expect { Monty::Run.new("[1, 2]").start.to_columns }.to raise_error(Monty::Error, /list of dicts/)
+    end
+  end
+
+  describe "Complete#to_arrow_ipc" do
+    it "encodes tabular results as an Arrow IPC stream" do
+      complete = Monty::Run.new("[{'a': 1, 'b': 'x'}, {'a': 2.5}]").start
+      skip "built without the arrow feature" unless complete.respond_to?(:to_arrow_ipc)
+
+      bytes = complete.to_arrow_ipc
+      expect(bytes.encoding).to eq(Encoding::ASCII_8BIT)
+      expect(bytes.bytesize).to be > 0
+    end
   end
 
   describe "#call_with_externals" do
@@ -251,6 +1024,232 @@ RSpec.describe Monty::Run do
     end
   end
 
+  describe "#execute" do
+    it "yields name, args and kwargs until the run completes" do
+      code = <<~PYTHON
+        a = fetch("x", retries=2)
+        b = fetch("y")
+        a + b
+      PYTHON
+
+      run = Monty::Run.new(code, external_functions: ["fetch"])
+      calls = []
+      result = run.execute do |name, args, kwargs|
+        calls << [name, args, kwargs]
+        args[0].upcase
+      end
+
+      expect(result).to eq("XY")
+      expect(calls).to eq([["fetch", ["x"], {"retries" => 2}], ["fetch", ["y"], {}]])
+    end
+
+    it "supports inputs and limits" do
+      run = Monty::Run.new("double(n)", inputs: ["n"], external_functions: ["double"])
+      result = run.execute(21, limits: {max_duration: 5.0}) { |_name, args, _kwargs| args[0] * 2 }
+      expect(result).to eq(42)
+    end
+
+    it "raises without a block" do
+      run = Monty::Run.new("42")
+      expect { run.execute }.to raise_error(ArgumentError)
+    end
+  end
+
+  describe "intrinsics" do
+    it "answers intrinsic calls without yielding to the host" do
+      code = <<~PYTHON
+        [fsum(xs), mean(xs), stdev(xs), dot(xs, [1, 0, 0, 0]), percentile(xs, 50)]
+      PYTHON
+
+      run = Monty::Run.new(code, inputs: ["xs"], intrinsics: true)
+      result = run.execute([2, 4, 4, 6]) { |name, *| raise "unexpected call to #{name}" }
+
+      expect(result[0..1]).to eq([16.0, 4.0])
+      expect(result[2]).to be_within(1e-9).of(Math.sqrt(8.0 / 3))
+      expect(result[3..]).to eq([2.0, 4.0])
+    end
+
+    it "answers string similarity intrinsics" do
+      code = <<~PYTHON
+        [levenshtein(a, b), jaro_winkler("MARTHA", "MARHTA"), similarity(a, b)]
+      PYTHON
+
+      run = Monty::Run.new(code, inputs: ["a", "b"], intrinsics: true)
+      distance, jaro_winkler, ratio = run.execute("kitten", "sitting") { |name, *| raise "unexpected call to #{name}" }
+
+      expect(distance).to eq(3)
+      expect(jaro_winkler).to be_within(1e-4).of(0.9611)
+      expect(ratio).to be_within(1e-9).of(4.0 / 7)
+    end
+
+    it "refuses string similarity inputs too long to compare quickly" do
+      code = <<~PYTHON
+        try:
+            levenshtein(a, b)
+        except ValueError as e:
+            str(e)
+      PYTHON
+
+      run = Monty::Run.new(code, inputs: ["a", "b"], intrinsics: true)
+      expect(run.execute("a" * 5000, "b" * 5000) { nil }).to match(/levenshtein\(\) inputs too long: 5000 x 5000/)
+    end
+
+    it "parses and serializes JSON natively" do
+      code = <<~PYTHON
+        data = json_loads(text)
+        data["b"].append(None)
+        json_dumps(data)
+      PYTHON
+
+      run = Monty::Run.new(code, inputs: ["text"], intrinsics: true)
+      result = run.execute('{"b": [1, 2.5, "\u00e9"], "a": true}') { |name, *| raise "unexpected call to #{name}" }
+
+      expect(result).to eq('{"b": [1, 2.5, "\\u00e9", null], "a": true}')
+    end
+
+    it "answers base64 and digest intrinsics" do
+      code = <<~PYTHON
+        [b64encode("hi"), b64decode("aGk="), sha256("abc"), md5(b"abc"), hmac_sha256("key", message)]
+      PYTHON
+
+      run = Monty::Run.new(code, inputs: ["message"], intrinsics: true)
+      result = run.execute("The quick brown fox jumps over the lazy dog") { |name, *| raise "unexpected call to #{name}" }
+
+      expect(result).to eq([
+        "aGk=",
+        "hi".b,
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        "900150983cd24fb0d6963f7d28e17f72",
+        "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+      ])
+    end
+
+    it "enforces Monty.max_json_depth" do
+      original = Monty.max_json_depth
+      Monty.max_json_depth = 2
+      code = <<~PYTHON
+        try:
+            json_loads("[[[1]]]")
+        except ValueError as e:
+            str(e)
+      PYTHON
+
+      run = Monty::Run.new(code, intrinsics: true)
+      expect(run.execute { nil }).to match(/max_json_depth/)
+    ensure
+      Monty.max_json_depth = original
+    end
+
+    it "refuses one-shot entry points that cannot answer intrinsics" do
+      run = Monty::Run.new("mean(xs)", inputs: ["xs"], intrinsics: true)
+      message = /must be driven with #start, #execute or #call_with_externals/
+
+      expect { run.call([1, 2]) }.to raise_error(ArgumentError, message)
+      expect { run.call([1, 2], capture_output: true) }.to raise_error(ArgumentError, message)
+      expect { run.map([[[1, 2]]]) }.to raise_error(ArgumentError, message)
+      expect { run.fuzz(generators: [[:int]]) }.to raise_error(ArgumentError, message)
+      expect(run.call_with_externals([1, 2]) { nil }).to eq(1.5)
+    end
+
+    it "still hands other external calls to the host" do
+      run = Monty::Run.new("mean(fetch())", external_functions: ["fetch"], intrinsics: true)
+      progress = run.start
+
+      expect(progress.function_name).to eq("fetch")
+      expect(progress.resume([1, 2]).value).to eq(1.5)
+    end
+
+    it "raises bad arguments inside the script" do
+      code = <<~PYTHON
+        try:
+            mean([])
+        except ValueError as e:
+            str(e)
+      PYTHON
+
+      run = Monty::Run.new(code, intrinsics: true)
+      expect(run.execute { nil }).to eq("mean requires at least one data point")
+    end
+
+    it "rejects external functions named like an intrinsic" do
+      expect { Monty::Run.new("mean(1)", external_functions: ["mean"], intrinsics: true) }
+        .to raise_error(ArgumentError, /mean/)
+    end
+  end
+
+  describe "#freeze_for_ractor" do
+    it "executes one compiled Run in parallel Ractors" do
+      run = Monty::Run.new("x * 2", inputs: ["x"]).freeze_for_ractor
+      expect(Ractor.shareable?(run)).to be(true)
+
+      ractors = [1, 2, 3].map { |n| Ractor.new(run, n) { |r, x| r.call(x) } }
+      results = ractors.map { |r| r.respond_to?(:value) ? r.value : r.take }
+      expect(results).to eq([2, 4, 6])
+    end
+
+    it "raises Monty errors with their usual classes inside a Ractor" do
+      run = Monty::Run.new("1 / 0").freeze_for_ractor
+      ractor = Ractor.new(run) do |r|
+        r.call
+      rescue Monty::Error => e
+        e.class.name
+      end
+      expect(ractor.respond_to?(:value) ? ractor.value : ractor.take).to eq("Monty::ZeroDivisionError")
+    end
+
+    it "redacts errors inside a Ractor with a shareable redactor" do
+      Monty.redactor = Module.new { def self.call(text) = text.gsub(/sk_\w+/, "[REDACTED]") }
+      run = Monty::Run.new("raise ValueError(key)", inputs: ["key"]).freeze_for_ractor
+      ractor = Ractor.new(run) do |r|
+        r.call("sk_live_123")
+      rescue Monty::Error => e
+        e.message
+      end
+
+      message = ractor.respond_to?(:value) ? ractor.value : ractor.take
+      expect(message).to include("[REDACTED]")
+      expect(message).not_to include("sk_live_123")
+    ensure
+      Monty.redactor = nil
+    end
+
+    it "withholds error text inside a Ractor when the redactor is not shareable" do
+      Monty.redactor = ->(text) { text.gsub(/sk_\w+/, "[REDACTED]") }
+      run = Monty::Run.new("raise ValueError(key)", inputs: ["key"]).freeze_for_ractor
+      ractor = Ractor.new(run) do |r|
+        r.call("sk_live_123")
+      rescue Monty::Error => e
+        e.message
+      end
+
+      expect(ractor.respond_to?(:value) ? ractor.value : ractor.take).not_to include("sk_live_123")
+    ensure
+      Monty.redactor = nil
+    end
+
+    it "falls back to plain calls for call_incremental on a frozen Run" do
+      run = Monty::Run.new("x + 1", inputs: ["x"]).freeze_for_ractor
+
+      expect(run.call_incremental(1)).to eq(2)
+      expect(run.call_incremental(2)).to eq(3)
+    end
+
+    it "drops the call_incremental cache when freezing" do
+      run = Monty::Run.new("x + 1", inputs: ["x"])
+      expect(run.call_incremental(1)).to eq(2)
+
+      run.freeze_for_ractor
+      expect(Ractor.shareable?(run)).to be(true)
+      expect(run.call_incremental(2)).to eq(3)
+    end
+
+    it "refuses to consume a frozen Run" do
+      run = Monty::Run.new("1").freeze_for_ractor
+      expect { run.start }.to raise_error(FrozenError)
+      expect { run.execute { nil } }.to raise_error(FrozenError)
+    end
+  end
+
   describe "#dump / .load" do
     it "round-trips serialization" do
       run = Monty::Run.new("x + 1", inputs: ["x"])
@@ -259,6 +1258,69 @@ RSpec.describe Monty::Run do
       restored = Monty::Run.load(bytes)
       expect(restored.call(41)).to eq(42)
     end
+
+    it "applies encrypt and decrypt callbacks" do
+      key = 0x5a
+      xor = ->(data) { data.bytes.map { |b| b ^ key }.pack("C*") }
+
+      blob = Monty::Run.new("x + 1", inputs: ["x"]).dump(encrypt: xor)
+      expect(blob).to be_a(String)
+      expect(Monty::Run.load(blob, decrypt: xor).call(1)).to eq(2)
+    end
+
+    it "loads dumps that have not expired yet" do
+      bytes = Monty::Run.new("x + 1", inputs: ["x"]).dump(expires_in: 60)
+      expect(Monty::Run.load(bytes).call(1)).to eq(2)
+    end
+
+    it "raises SnapshotExpiredError for expired dumps" do
+      bytes = Monty::Run.new("x + 1", inputs: ["x"]).dump(expires_at: Time.now - 1)
+      expect { Monty::Run.load(bytes) }.to raise_error(Monty::SnapshotExpiredError)
+    end
+
+    it "raises Monty::Error for a dump whose expiry header is cut short" do
+      expect { Monty::Run.load("MONTYTTL\x00\x01".b) }.to raise_error(Monty::Error, /truncated snapshot/) { |error|
+        expect(error).not_to be_a(Monty::SnapshotExpiredError)
+      }
+    end
+  end
+
+  describe "FunctionCall#dump / .load" do
+    let(:code) do
+      <<~PYTHON
+        print("before")
+        price = fetch_price(item)
+        price * 2
+      PYTHON
+    end
+
+    it "resumes a paused call restored from a dump" do
+      call = Monty::Run.new(code, inputs: ["item"], external_functions: ["fetch_price"]).start("apple")
+      bytes = call.dump
+
+      restored = Monty::FunctionCall.load(bytes)
+      expect(restored.function_name).to eq("fetch_price")
+      expect(restored.args).to eq(["apple"])
+      expect(restored.output).to eq("before\n")
+      expect(restored.resume(21).value).to eq(42)
+    end
+
+    it "leaves the original call resumable" do
+      call = Monty::Run.new(code, inputs: ["item"], external_functions: ["fetch_price"]).start("apple")
+      call.dump
+      expect(call.resume(1).value).to eq(2)
+    end
+
+    it "applies expiry and encryption like Run#dump" do
+      xor = ->(data) { data.bytes.map { |b| b ^ 0x5a }.pack("C*") }
+      call = Monty::Run.new(code, inputs: ["item"], external_functions: ["fetch_price"]).start("apple")
+
+      blob = call.dump(encrypt: xor)
+      expect(Monty::FunctionCall.load(blob, decrypt: xor).resume(2).value).to eq(4)
+
+      expired = call.dump(expires_at: Time.now - 1)
+      expect { Monty::FunctionCall.load(expired) }.to raise_error(Monty::SnapshotExpiredError)
+    end
   end
 
   describe "error handling" do
@@ -271,10 +1333,75 @@ RSpec.describe Monty::Run do
       expect { Monty::Run.new("def !!!") }.to raise_error(Monty::SyntaxError)
     end
 
+    it "formats a CPython-style traceback" do
+      code = <<~PYTHON
+        def divide(a, b):
+            return a / b
+
+        divide(1, 0)
+      PYTHON
+
+      error = begin
+        Monty::Run.new(code).call
+      rescue Monty::Error => e
+        e
+      end
+
+      traceback = Monty.format_traceback(error)
+      expect(traceback).to start_with("Traceback (most recent call last):")
+      expect(traceback).to include('File "script.py", line 2, in divide')
+      expect(traceback).to include("return a / b")
+      expect(traceback.lines.last).to start_with("ZeroDivisionError")
+    end
+
+    it "exposes structured frames and the Python exception class" do
+      code = <<~PYTHON
+        def divide(a, b):
+            return a / b
+
+        divide(1, 0)
+      PYTHON
+
+      error = begin
+        Monty::Run.new(code).call
+      rescue Monty::Error => e
+        e
+      end
+
+      expect(error.python_class).to eq("ZeroDivisionError")
+      expect(error.traceback).to eq([
+        {file: "script.py", line: 4, function: "<module>", source_line: "divide(1, 0)"},
+        {file: "script.py", line: 2, function: "divide", source_line: "return a / b"}
+      ])
+    end
+
+    it "has no frames for errors raised by the binding" do
+      error = Monty::Error.new("boom")
+      expect(error.traceback).to eq([])
+      expect(error.python_class).to be_nil
+    end
+
+    it "raises the Monty::Error subclass matching the Python exception" do
+      expect { Monty::Run.new("1 / 0").call }.to raise_error(Monty::ZeroDivisionError)
+      expect { Monty::Run.new("{}['k']").call }.to raise_error(Monty::KeyError)
+      expect { Monty::Run.new("int('x')").call }.to raise_error(Monty::ValueError)
+      expect { Monty::Run.new("1 + 'a'").call }.to raise_error(Monty::TypeError)
+    end
+
+    it "mirrors Python's exception hierarchy" do
+      expect(Monty::ZeroDivisionError).to be < Monty::ArithmeticError
+      expect(Monty::KeyError).to be < Monty::LookupError
+      expect(Monty::IndexError).to be < Monty::LookupError
+      expect(Monty::ValueError).to be < Monty::Error
+      expect { Monty::Run.new("[][0]").call }.to raise_error(Monty::LookupError)
+    end
+
     it "provides error hierarchy" do
       expect(Monty::SyntaxError).to be < Monty::Error
       expect(Monty::ResourceError).to be < Monty::Error
       expect(Monty::ConsumedError).to be < Monty::Error
+      expect(Monty::Interrupted).to be < Monty::Error
+      expect(Monty::SnapshotExpiredError).to be < Monty::Error
       expect(Monty::Error).to be < StandardError
     end
   end
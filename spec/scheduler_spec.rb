@@ -0,0 +1,102 @@
+# frozen_string_literal: true
+
+RSpec.describe Monty::Scheduler do
+  let(:run) { Monty::Run.new("x * 2", inputs: ["x"]) }
+
+  it "runs the block and returns its value" do
+    scheduler = described_class.new(max_concurrency: 2)
+    expect(scheduler.run(tenant: "a") { run.call(21) }).to eq(42)
+    expect(scheduler.stats).to include(running: {}, queued: 0)
+    expect(scheduler.stats[:wait][:interactive][:count]).to eq(1)
+  end
+
+  it "caps concurrent runs per tenant" do
+    scheduler = described_class.new(max_concurrency: 4, max_per_tenant: 1)
+    peak = 0
+    current = 0
+    lock = Mutex.new
+
+    threads = 4.times.map do
+      Thread.new do
+        scheduler.run(tenant: "a") do
+          lock.synchronize { peak = [peak, current += 1].max }
+          sleep 0.01
+          lock.synchronize { current -= 1 }
+        end
+      end
+    end
+    threads.each(&:join)
+
+    expect(peak).to eq(1)
+  end
+
+  it "gives a freed slot to the tenant served least recently" do
+    scheduler = described_class.new(max_concurrency: 1)
+    order = Queue.new
+    gate = Queue.new
+
+    holder = Thread.new { scheduler.run(tenant: "a") { gate.pop } }
+    sleep 0.01 until scheduler.stats[:running]["a"] == 1
+
+    waiters = [["a", 1], ["a", 2], ["b", 1]].each_with_index.map do |(tenant, n), index|
+      thread = Thread.new { scheduler.run(tenant: tenant) { order << [tenant, n] } }
+      sleep 0.01 until scheduler.stats[:queued] == index + 1
+      thread
+    end
+    gate << :go
+    [holder, *waiters].each(&:join)
+
+    expect(Array.new(3) { order.pop }).to eq([["b", 1], ["a", 1], ["a", 2]])
+  end
+
+  it "admits interactive runs before queued batch runs" do
+    scheduler = described_class.new(max_concurrency: 1)
+    order = Queue.new
+    gate = Queue.new
+
+    holder = Thread.new { scheduler.run(tenant: "a") { gate.pop } }
+    sleep 0.01 until scheduler.stats[:running]["a"] == 1
+
+    waiters = [[:batch, "b"], [:interactive, "c"]].each_with_index.map do |(priority, tenant), index|
+      thread = Thread.new { scheduler.run(tenant: tenant, priority: priority) { order << priority } }
+      sleep 0.01 until scheduler.stats[:queued] == index + 1
+      thread
+    end
+    gate << :go
+    [holder, *waiters].each(&:join)
+
+    expect(Array.new(2) { order.pop }).to eq(%i[interactive batch])
+    expect(scheduler.stats[:wait][:batch][:max]).to be > 0
+  end
+
+  it "hands the slot on when a queued run is killed" do
+    scheduler = described_class.new(max_concurrency: 1)
+    gate = Queue.new
+
+    holder = Thread.new { scheduler.run(tenant: "a") { gate.pop } }
+    sleep 0.01 until scheduler.stats[:running]["a"] == 1
+    killed = Thread.new { scheduler.run(tenant: "b") { :unreachable } }
+    sleep 0.01 until scheduler.stats[:queued] == 1
+    waiting = Thread.new { scheduler.run(tenant: "c", priority: :batch) { :ran } }
+    sleep 0.01 until scheduler.stats[:queued] == 2
+
+    gate << :go
+    killed.kill
+    holder.join
+
+    expect(waiting.join(5)&.value).to eq(:ran)
+    expect(scheduler.stats).to include(running: {}, queued: 0)
+  end
+
+  it "forgets tenants once they have nothing running or queued" do
+    scheduler = described_class.new(max_concurrency: 2)
+    1_000.times { |n| scheduler.run(tenant: "tenant-#{n}") { run.call(n) } }
+
+    expect(scheduler.instance_variable_get(:@last_served)).to be_empty
+  end
+
+  it "rejects unknown priorities" do
+    scheduler = described_class.new(max_concurrency: 1)
+    expect { scheduler.run(tenant: "a", priority: :urgent) {} }.to raise_error(ArgumentError)
+  end
+end
@@ -0,0 +1,20 @@
+# frozen_string_literal: true
+
+RSpec.describe Monty::SharedData do
+  let(:rates) { described_class.new({"USD" => 1.0, "EUR" => 0.9, "tiers" => [1, 2, 3]}) }
+
+  it "can be passed to many runs as an input" do
+    run = Monty::Run.new("rates[currency] * amount", inputs: ["rates", "currency", "amount"])
+
+    expect(run.call(rates, "EUR", 10)).to eq(9.0)
+    expect(run.call(rates, "USD", 10)).to eq(10.0)
+  end
+
+  it "is not changed by scripts that mutate it" do
+    run = Monty::Run.new("rates['tiers'].append(4)\nlen(rates['tiers'])", inputs: ["rates"])
+
+    expect(run.call(rates)).to eq(4)
+    expect(run.call(rates)).to eq(4)
+    expect(rates.to_ruby["tiers"]).to eq([1, 2, 3])
+  end
+end
@@ -0,0 +1,69 @@
+# frozen_string_literal: true
+
+require "json"
+
+RSpec.describe Monty::Transcript do
+  let(:v1) do
+    <<~PYTHON
+      price = fetch_price(item)
+      print("price", price)
+      price * qty
+    PYTHON
+  end
+
+  def run_for(code)
+    Monty::Run.new(code, inputs: ["item", "qty"], external_functions: ["fetch_price", "fetch_tax"])
+  end
+
+  let(:transcript) do
+    described_class.record(run_for(v1), "apple", 3) { |call| (call.function_name == "fetch_price") ? 2 : 0 }
+  end
+
+  it "records inputs, external calls, result and output" do
+    expect(transcript.inputs).to eq(["apple", 3])
+    expect(transcript.calls).to eq([{function: "fetch_price", args: ["apple"], kwargs: {}, result: 2}])
+    expect(transcript.result).to eq(6)
+    expect(transcript.output).to eq("price 2\n")
+  end
+
+  it "replays cleanly against an equivalent version" do
+    refactored = "p = fetch_price(item)\nprint('price', p)\nqty * p\n"
+
+    expect(transcript.replay(run_for(refactored))).to be_nil
+  end
+
+  it "reports the first call that differs" do
+    changed = "price = fetch_price(item.upper())\nprice * qty\n"
+
+    divergence = transcript.replay(run_for(changed))
+    expect(divergence.index).to eq(0)
+    expect(divergence.actual).to eq(function: "fetch_price", args: ["APPLE"], kwargs: {})
+  end
+
+  it "reports a changed result when the calls match" do
+    changed = "price = fetch_price(item)\nprint('price', price)\nprice * qty + 1\n"
+
+    divergence = transcript.replay(run_for(changed))
+    expect(divergence.index).to eq(1)
+    expect(divergence.expected[:result]).to eq(6)
+    expect(divergence.actual[:result]).to eq(7)
+  end
+
+  it "round-trips through to_h with string keys" do
+    restored = described_class.from_h(JSON.parse(JSON.dump(transcript.to_h)))
+
+    expect(restored.replay(run_for(v1))).to be_nil
+  end
+
+  it "exports the calls as OpenAI tool-call messages" do
+    messages = JSON.parse(transcript.transcript_json)
+
+    expect(messages).to eq([
+      {"role" => "assistant", "content" => nil, "tool_calls" => [
+        {"id" => "call_0", "type" => "function",
+         "function" => {"name" => "fetch_price", "arguments" => "{\"args\":[\"apple\"]}"}}
+      ]},
+      {"role" => "tool", "tool_call_id" => "call_0", "content" => "2"}
+    ])
+  end
+end